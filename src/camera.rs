@@ -1,3 +1,5 @@
+use rand::Rng;
+
 use crate::geometry::{Point3, RandomVectorType, Ray, Vec3};
 
 pub struct Camera {
@@ -8,6 +10,8 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f64,
+    time0: f64,
+    time1: f64,
 }
 
 pub struct CameraOrientation {
@@ -21,6 +25,9 @@ pub struct CameraSettings {
     pub aspect_ratio: f64,
     pub aperture: f64,
     pub focus_dist: f64,
+    // shutter open/close times
+    pub time0: f64,
+    pub time1: f64,
 }
 
 impl Camera {
@@ -49,6 +56,8 @@ impl Camera {
             u,
             v,
             lens_radius,
+            time0: s.time0,
+            time1: s.time1,
         }
     }
 
@@ -56,11 +65,15 @@ impl Camera {
         let rd = self.lens_radius * Vec3::random(RandomVectorType::InUnitDisk);
         let offset = &self.u * rd.x + &self.v * rd.y;
 
+        // Sample a time uniformly over the interval the shutter is open
+        let time = self.time0 + rand::thread_rng().gen::<f64>() * (self.time1 - self.time0);
+
         Ray::new(
             &self.origin + &offset,
             &self.lower_left_corner + s * &self.horizontal + t * &self.vertical
                 - &self.origin
                 - &offset,
+            time,
         )
     }
 }
@@ -81,6 +94,8 @@ mod test {
             aspect_ratio,
             aperture: 0.0,
             focus_dist: (&orientation.lookfrom - &orientation.lookat).length(),
+            time0: 0.,
+            time1: 0.,
         };
         Camera::new(settings, orientation)
     }
@@ -101,15 +116,26 @@ mod test {
         let camera = get_camera(2.0 / 1.0);
         assert_eq!(
             camera.get_ray(2., 3.),
-            Ray::new(Vec3::new(0., 0., 0.), Vec3::new(6.0, 5.0, -1.0))
+            Ray::new(Vec3::new(0., 0., 0.), Vec3::new(6.0, 5.0, -1.0), 0.)
         );
         assert_eq!(
             camera.get_ray(7., 14.),
-            Ray::new(Vec3::new(0., 0., 0.), Vec3::new(26.0, 27.0, -1.0))
+            Ray::new(Vec3::new(0., 0., 0.), Vec3::new(26.0, 27.0, -1.0), 0.)
         );
         assert_eq!(
             camera.get_ray(13., 21.),
-            Ray::new(Vec3::new(0., 0., 0.), Vec3::new(50.0, 41.0, -1.0))
+            Ray::new(Vec3::new(0., 0., 0.), Vec3::new(50.0, 41.0, -1.0), 0.)
         );
     }
+
+    #[test]
+    fn test_camera_get_ray_time() {
+        let mut camera = get_camera(2.0 / 1.0);
+        camera.time0 = 1.;
+        camera.time1 = 2.;
+        for _ in 0..100 {
+            let r = camera.get_ray(0.5, 0.5);
+            assert!(1. <= r.time && r.time < 2.);
+        }
+    }
 }
@@ -14,6 +14,59 @@ impl Color {
         Self { r, g, b }
     }
 
+    /// The perceived brightness, with the Rec. 709 weights.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// The light given off by a black body at `temperature` kelvin, from Planck's law, as the
+    /// spectral radiance in W/(sr·m²·nm) at a red, green and blue wavelength.
+    ///
+    /// It rises very steeply with temperature: about 0.8 in red at 1500 K, 24 at 2000 K, and over
+    /// 20000 in every channel at the 5800 K of the sun, so it will need scaling to suit a
+    /// scene.
+    pub fn blackbody(temperature: f64) -> Self {
+        // the second radiation constant hc/k in m·K, and 2hc² in W·m²/sr
+        const C2: f64 = 1.438_777e-2;
+        const TWO_H_C2: f64 = 1.191_043e-16;
+
+        let radiance = |wavelength_nm: f64| {
+            if temperature <= 0. {
+                return 0.;
+            }
+            let wavelength = wavelength_nm * 1e-9;
+            let per_metre = TWO_H_C2
+                / (f64::powi(wavelength, 5) * f64::exp_m1(C2 / (wavelength * temperature)));
+            per_metre * 1e-9
+        };
+        Self {
+            r: radiance(700.),
+            g: radiance(546.1),
+            b: radiance(435.8),
+        }
+    }
+
+    /// A false colour for `t` between 0 and 1, running from dark blue through cyan, green and
+    /// yellow to red, for showing a quantity as an image.
+    pub fn heat(t: f64) -> Self {
+        const RAMP: [(f64, f64, f64); 5] = [
+            (0., 0., 0.3),
+            (0., 0.8, 1.),
+            (0., 1., 0.),
+            (1., 1., 0.),
+            (1., 0., 0.),
+        ];
+        let x = t.clamp(0., 1.) * (RAMP.len() - 1) as f64;
+        let i = usize::min(x as usize, RAMP.len() - 2);
+        let f = x - i as f64;
+        let (a, b) = (RAMP[i], RAMP[i + 1]);
+        Self {
+            r: a.0 + f * (b.0 - a.0),
+            g: a.1 + f * (b.1 - a.1),
+            b: a.2 + f * (b.2 - a.2),
+        }
+    }
+
     /// Generate a random color
     pub fn random(min: f64, max: f64) -> Self {
         let mut rng = rand::thread_rng();
@@ -130,6 +183,31 @@ mod test {
         assert_eq!(c.b, 3.0);
     }
 
+    #[test]
+    fn test_color_blackbody() {
+        // 2hc² / λ⁵ / (exp(hc / λkT) - 1) at 700 nm and 2000 K
+        let c = Color::blackbody(2000.);
+        assert!(f64::abs(c.r / 24.39 - 1.) < 0.001);
+
+        // a fire is red, and hotter ones are brighter and whiter
+        let cool = Color::blackbody(1500.);
+        let hot = Color::blackbody(3000.);
+        assert!(cool.r > cool.g && cool.g > cool.b);
+        assert!(hot.r > cool.r && hot.g > cool.g && hot.b > cool.b);
+        assert!(hot.b / hot.r > cool.b / cool.r);
+
+        assert_eq!(Color::blackbody(0.), Color::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn test_color_heat() {
+        assert_eq!(Color::heat(0.), Color::new(0., 0., 0.3));
+        assert_eq!(Color::heat(0.5), Color::new(0., 1., 0.));
+        assert_eq!(Color::heat(1.), Color::new(1., 0., 0.));
+        assert_eq!(Color::heat(2.), Color::heat(1.));
+        assert_eq!(Color::heat(0.625), Color::new(0.5, 1., 0.));
+    }
+
     #[test]
     fn test_color_equality() {
         let c1 = Color {
@@ -0,0 +1,97 @@
+use rand::Rng;
+
+use crate::geometry::Ray;
+use crate::material::types::PhaseFunction;
+
+/// A uniform medium filling the whole scene, like fog or haze, which every ray travels through
+/// between surfaces.
+///
+/// Unlike a `ConstantMedium` it has no boundary and isn't part of the world; it's handed to
+/// `Ray::color` instead, which gives it a chance to absorb or scatter the ray on every segment of
+/// the path. Since it goes on for ever, a ray that misses everything is certain to be scattered
+/// somewhere, so scenes with fog should be closed in.
+pub struct Atmosphere {
+    absorption: f64,
+    scattering: f64,
+    phase_function: PhaseFunction,
+}
+
+impl Atmosphere {
+    /// An atmosphere with the given absorption and scattering coefficients, the chance per unit
+    /// length of light being absorbed or scattered.
+    pub fn new(absorption: f64, scattering: f64, phase_function: PhaseFunction) -> Atmosphere {
+        Atmosphere {
+            absorption,
+            scattering,
+            phase_function,
+        }
+    }
+
+    /// The fraction of light that survives each interaction with the medium, rather than being
+    /// absorbed.
+    pub fn albedo(&self) -> f64 {
+        let extinction = self.absorption + self.scattering;
+        if extinction > 0. {
+            self.scattering / extinction
+        } else {
+            0.
+        }
+    }
+
+    /// Where, if anywhere, the medium interrupts `ray` before it reaches `t_max`, and the ray that
+    /// carries on from there in a new direction.
+    ///
+    /// The distance is drawn from the exponential distribution of free paths, so the chance of
+    /// getting through untouched is exactly the transmittance along the ray, and the light that
+    /// does get through needs no further weighting.
+    pub fn interact(&self, ray: &Ray, t_max: f64) -> Option<Ray> {
+        let extinction = self.absorption + self.scattering;
+        if extinction <= 0. {
+            return None;
+        }
+
+        let random: f64 = rand::thread_rng().gen();
+        let t = -f64::ln(1. - random) / (extinction * ray.direction.length());
+        if t >= t_max {
+            return None;
+        }
+
+        Some(Ray::new(
+            ray.at(t),
+            self.phase_function.sample(&ray.direction),
+            ray.time,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Point3, Vec3};
+
+    #[test]
+    fn test_atmosphere_interact() {
+        let atmosphere = Atmosphere::new(0.1, 0.3, PhaseFunction::Isotropic);
+        assert!(f64::abs(atmosphere.albedo() - 0.75) < Vec3::TOL);
+
+        // a ray of length 2 per unit t, so the optical depth up to t = 1 is 0.8
+        let ray = Ray::new(Point3::new(1., 2., 3.), Vec3::new(0., 2., 0.), 0.);
+        let n = 20000;
+        let mut interactions = 0;
+        for _ in 0..n {
+            if let Some(scattered) = atmosphere.interact(&ray, 1.) {
+                let offset = &scattered.origin - &ray.origin;
+                assert!(offset.x == 0. && offset.z == 0.);
+                assert!(0. <= offset.y && offset.y < 2.);
+                interactions += 1;
+            }
+        }
+        let expected = 1. - f64::exp(-0.8);
+        assert!(f64::abs(interactions as f64 / n as f64 - expected) < 0.015);
+
+        let vacuum = Atmosphere::new(0., 0., PhaseFunction::Isotropic);
+        assert!(vacuum.interact(&ray, f64::INFINITY).is_none());
+        assert_eq!(vacuum.albedo(), 0.);
+    }
+}
@@ -67,10 +67,63 @@ impl Block {
 
 impl Hittable for Block {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        self.sides.hit(&ray, t_min, t_max)
+        let rec = self.sides.hit(&ray, t_min, t_max)?;
+
+        // The sides all face along a positive axis; the ones at the minimum corner need to be
+        // flipped to face out of the block.
+        let mut outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let axis = (0..3).find(|&i| outward_normal[i] != 0.).unwrap();
+        if rec.p[axis] < (self.bbox.minimum[axis] + self.bbox.maximum[axis]) / 2. {
+            outward_normal = -outward_normal;
+        }
+        Some(HitRecord::new(
+            ray,
+            rec.t,
+            rec.p,
+            outward_normal,
+            rec.material_rc,
+            rec.u,
+            rec.v,
+        ))
     }
 
     fn bounding_box(&self) -> Option<&AABB> {
         Some(&self.bbox)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Vec3;
+    use crate::hittable::test_util::grey;
+
+    #[test]
+    fn test_block_hit() {
+        let material = grey();
+        let block = Block::new(Point3::new(0., 0., 0.), Point3::new(1., 2., 3.), material);
+
+        // every face is a front face from outside and a back face from inside
+        let r = Ray::new(Point3::new(0.5, 1., -5.), Vec3::new(0., 0., 1.), 0.);
+        let rec = block.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 5.) < Vec3::TOL);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0., 0., -1.));
+
+        let r = Ray::new(Point3::new(0.5, 1., 1.), Vec3::new(0., -1., 0.), 0.);
+        let rec = block.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.) < Vec3::TOL);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+
+        let r = Ray::new(Point3::new(0.5, 1., 1.), Vec3::new(1., 0., 0.), 0.);
+        let rec = block.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(-1., 0., 0.));
+    }
+}
@@ -0,0 +1,163 @@
+use std::rc::Rc;
+
+use super::cone::azimuth;
+use super::polynomial::solve_quadratic;
+use super::{Onb, Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A cylinder of `radius` from `p0` to `p1`, closed with a hemisphere at each end.
+///
+/// `u` goes around the axis and `v` runs from the pole past `p0` to the pole past `p1`.
+pub struct Capsule {
+    p0: Point3,
+    onb: Onb,
+    height: f64,
+    radius: f64,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Capsule {
+    pub fn new(p0: Point3, p1: Point3, radius: f64, material: Rc<dyn Material>) -> Capsule {
+        let r = Vec3::new(radius, radius, radius);
+        let bbox = AABB::surrounding_box(
+            &AABB {
+                minimum: &p0 - &r,
+                maximum: &p0 + &r,
+            },
+            &AABB {
+                minimum: &p1 - &r,
+                maximum: &p1 + &r,
+            },
+        );
+        let axis = &p1 - &p0;
+        Capsule {
+            p0,
+            onb: Onb::from_w(&axis),
+            height: axis.length(),
+            radius,
+            material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // work in a frame with p0 at the origin and the axis along z
+        let o = self.onb.to_local(&(&ray.origin - &self.p0));
+        let d = self.onb.to_local(&ray.direction);
+        let rr = self.radius * self.radius;
+        let in_range = |t: &f64| t_min <= *t && *t <= t_max;
+
+        // (t, local normal) of the closest hit so far
+        let mut closest: Option<(f64, Vec3)> = None;
+
+        let body = solve_quadratic([
+            o.x * o.x + o.y * o.y - rr,
+            2. * (o.x * d.x + o.y * d.y),
+            d.x * d.x + d.y * d.y,
+        ]);
+        for t in body.into_iter().filter(in_range) {
+            let p = &o + t * &d;
+            if 0. <= p.z && p.z <= self.height {
+                closest = Some((t, Vec3::new(p.x, p.y, 0.) / self.radius));
+                break;
+            }
+        }
+
+        // only the half of each end sphere that's outside the body counts
+        for (z, outside) in &[(0., -1.), (self.height, 1.)] {
+            let oc = &o - Vec3::new(0., 0., *z);
+            let cap = solve_quadratic([
+                oc.length_squared() - rr,
+                2. * Vec3::dot(&oc, &d),
+                d.length_squared(),
+            ]);
+            for t in cap.into_iter().filter(in_range) {
+                if matches!(closest, Some((closest_t, _)) if closest_t <= t) {
+                    break;
+                }
+                let normal = (&oc + t * &d) / self.radius;
+                if normal.z * outside >= 0. {
+                    closest = Some((t, normal));
+                    break;
+                }
+            }
+        }
+
+        let (t, normal) = closest?;
+        let p = &o + t * &d;
+        let v = (p.z + self.radius) / (self.height + 2. * self.radius);
+        Some(HitRecord::new(
+            ray,
+            t,
+            ray.at(t),
+            self.onb.local(&normal),
+            self.material.clone(),
+            azimuth(&p),
+            v,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    fn get_capsule() -> Capsule {
+        let material = grey();
+        Capsule::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 2., 0.),
+            1.,
+            material,
+        )
+    }
+
+    #[test]
+    fn test_capsule_hit() {
+        let capsule = get_capsule();
+
+        // body
+        let r = Ray::new(Point3::new(5., 1., 0.), Vec3::new(-1., 0., 0.), 0.);
+        let rec = capsule.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(1., 0., 0.));
+        assert!(f64::abs(rec.v - 0.5) < Vec3::TOL);
+
+        // top pole
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = capsule.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+
+        // from inside the body, out through the bottom hemisphere
+        let r = Ray::new(Point3::new(0., 1.5, 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = capsule.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.5) < Vec3::TOL);
+        assert!(!rec.front_face);
+
+        // past the rounded end
+        let r = Ray::new(Point3::new(5., -0.9, 0.9), Vec3::new(-1., 0., 0.), 0.);
+        assert!(capsule.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_capsule_bounding_box() {
+        assert_eq!(
+            *get_capsule().bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-1., -1., -1.),
+                maximum: Vec3::new(1., 3., 1.),
+            }
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use super::disk::circle_bbox;
+use super::polynomial::solve_quadratic;
+use super::{Onb, Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A cone frustum along the axis from `p0` to `p1`, with radius `r0` at `p0` and `r1` at `p1`.
+/// Setting either radius to zero gives a pointed cone. Unless it's open, each end with a non-zero
+/// radius is capped with a flat disk.
+///
+/// On the side, `u` goes around the axis and `v` runs from `p0` to `p1`; on the caps, `u` goes
+/// around the axis and `v` runs from the center to the rim.
+pub struct Cone {
+    p0: Point3,
+    onb: Onb,
+    height: f64,
+    r0: f64,
+    r1: f64,
+    capped: bool,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Cone {
+    pub fn new(p0: Point3, r0: f64, p1: Point3, r1: f64, material: Rc<dyn Material>) -> Cone {
+        Cone::build(p0, r0, p1, r1, true, material)
+    }
+
+    /// A cone without its end caps.
+    pub fn open(p0: Point3, r0: f64, p1: Point3, r1: f64, material: Rc<dyn Material>) -> Cone {
+        Cone::build(p0, r0, p1, r1, false, material)
+    }
+
+    fn build(
+        p0: Point3,
+        r0: f64,
+        p1: Point3,
+        r1: f64,
+        capped: bool,
+        material: Rc<dyn Material>,
+    ) -> Cone {
+        assert!(r0 >= 0. && r1 >= 0. && r0 + r1 > 0.);
+        let axis = &p1 - &p0;
+        let onb = Onb::from_w(&axis);
+        let bbox =
+            AABB::surrounding_box(&circle_bbox(&p0, &onb.w, r0), &circle_bbox(&p1, &onb.w, r1));
+        Cone {
+            p0,
+            onb,
+            height: axis.length(),
+            r0,
+            r1,
+            capped,
+            material,
+            bbox,
+        }
+    }
+}
+
+/// The angle around the local z-axis, as a texture coordinate in `[0, 1]`.
+pub(super) fn azimuth(p: &Vec3) -> f64 {
+    (f64::atan2(p.y, p.x) + PI) / (2. * PI)
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // work in a frame with p0 at the origin and the axis along z
+        let o = self.onb.to_local(&(&ray.origin - &self.p0));
+        let d = self.onb.to_local(&ray.direction);
+
+        // (t, local normal, u, v) of the closest hit so far
+        let mut closest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut t_max = t_max;
+
+        // side: x^2 + y^2 = (r0 + k z)^2 for 0 <= z <= h
+        let k = (self.r1 - self.r0) / self.height;
+        let r_o = self.r0 + k * o.z;
+        let a = d.x * d.x + d.y * d.y - k * k * d.z * d.z;
+        let half_b = o.x * d.x + o.y * d.y - r_o * k * d.z;
+        let c = o.x * o.x + o.y * o.y - r_o * r_o;
+        for t in solve_quadratic([c, 2. * half_b, a]) {
+            if t < t_min || t > t_max {
+                continue;
+            }
+            let p = &o + t * &d;
+            if p.z < 0. || p.z > self.height {
+                continue;
+            }
+            let normal = Vec3::new(p.x, p.y, -k * (self.r0 + k * p.z));
+            closest = Some((t, normal.unit_vector(), azimuth(&p), p.z / self.height));
+            t_max = t;
+            break;
+        }
+
+        // caps
+        if self.capped && d.z != 0. {
+            for (z, r, normal_z) in &[(0., self.r0, -1.), (self.height, self.r1, 1.)] {
+                let t = (z - o.z) / d.z;
+                if *r == 0. || t < t_min || t > t_max {
+                    continue;
+                }
+                let p = &o + t * &d;
+                let rho = f64::sqrt(p.x * p.x + p.y * p.y);
+                if rho > *r {
+                    continue;
+                }
+                closest = Some((t, Vec3::new(0., 0., *normal_z), azimuth(&p), rho / r));
+                t_max = t;
+            }
+        }
+
+        let (t, normal, u, v) = closest?;
+        Some(HitRecord::new(
+            ray,
+            t,
+            ray.at(t),
+            self.onb.local(&normal),
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+/// A circular cylinder along the axis from `p0` to `p1`: a cone whose two radii are the same.
+pub struct Cylinder {
+    cone: Cone,
+}
+
+impl Cylinder {
+    pub fn new(p0: Point3, p1: Point3, radius: f64, material: Rc<dyn Material>) -> Cylinder {
+        Cylinder {
+            cone: Cone::new(p0, radius, p1, radius, material),
+        }
+    }
+
+    /// A cylinder without its end caps, like a length of pipe.
+    pub fn open(p0: Point3, p1: Point3, radius: f64, material: Rc<dyn Material>) -> Cylinder {
+        Cylinder {
+            cone: Cone::open(p0, radius, p1, radius, material),
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.cone.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.cone.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    #[test]
+    fn test_cylinder_hit() {
+        let cylinder = Cylinder::new(Point3::new(0., 0., 0.), Point3::new(0., 2., 0.), 1., grey());
+
+        // side
+        let r = Ray::new(Point3::new(5., 0.5, 0.), Vec3::new(-1., 0., 0.), 0.);
+        let rec = cylinder.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(1., 0., 0.));
+        assert!(f64::abs(rec.v - 0.25) < Vec3::TOL);
+
+        // top cap
+        let r = Ray::new(Point3::new(0.5, 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = cylinder.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 3.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+        assert!(f64::abs(rec.v - 0.5) < Vec3::TOL);
+
+        // from inside, out through the bottom cap
+        let r = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = cylinder.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.) < Vec3::TOL);
+        assert!(!rec.front_face);
+
+        // above the top, and past the side
+        let r = Ray::new(Point3::new(5., 2.5, 0.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(cylinder.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(5., 1., 1.5), Vec3::new(-1., 0., 0.), 0.);
+        assert!(cylinder.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_open_cylinder_hit() {
+        let pipe = Cylinder::open(Point3::new(0., 0., 0.), Point3::new(0., 2., 0.), 1., grey());
+
+        // straight down the pipe without touching it
+        let r = Ray::new(Point3::new(0.5, 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(pipe.hit(&r, 0., f64::INFINITY).is_none());
+
+        // in through the top and out through the inside of the wall
+        let r = Ray::new(Point3::new(0., 3., 0.), Vec3::new(1., -1., 0.), 0.);
+        let rec = pipe.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.) < Vec3::TOL);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(-1., 0., 0.));
+    }
+
+    #[test]
+    fn test_cone_hit() {
+        // a cone with its point up at (0, 1, 0) and a 45 degree slope
+        let cone = Cone::new(
+            Point3::new(0., 0., 0.),
+            1.,
+            Point3::new(0., 1., 0.),
+            0.,
+            grey(),
+        );
+
+        let r = Ray::new(Point3::new(5., 0.5, 0.), Vec3::new(-1., 0., 0.), 0.);
+        let rec = cone.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.5) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(1., 1., 0.).unit_vector());
+
+        // the other nappe of the double cone, above the point, must not be hit
+        let r = Ray::new(Point3::new(5., 1.5, 0.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(cone.hit(&r, 0., f64::INFINITY).is_none());
+
+        let r = Ray::new(Point3::new(0.5, -5., 0.), Vec3::new(0., 1., 0.), 0.);
+        let rec = cone.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 5.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., -1., 0.));
+    }
+
+    #[test]
+    fn test_cone_bounding_box() {
+        let frustum = Cone::new(
+            Point3::new(0., 0., 0.),
+            2.,
+            Point3::new(0., 0., 3.),
+            1.,
+            grey(),
+        );
+        assert_eq!(
+            *frustum.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-2., -2., 0.),
+                maximum: Vec3::new(2., 2., 3.),
+            }
+        );
+    }
+}
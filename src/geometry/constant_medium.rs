@@ -6,7 +6,7 @@ use rand::Rng;
 use crate::color::Color;
 use crate::geometry::{Ray, Vec3};
 use crate::hittable::{HitRecord, Hittable, AABB};
-use crate::material::types::Isotropic;
+use crate::material::types::{Anisotropic, PhaseFunction};
 use crate::material::Material;
 
 pub struct ConstantMedium {
@@ -16,63 +16,80 @@ pub struct ConstantMedium {
 }
 
 impl ConstantMedium {
-    pub fn from_color(boundary: Box<dyn Hittable>, density: f64, color: Color) -> ConstantMedium {
+    /// A medium that scatters with the given material, like an `Anisotropic` one.
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f64,
+        phase_function: Rc<dyn Material>,
+    ) -> ConstantMedium {
         ConstantMedium {
             boundary,
             neg_inv_density: -1. / density,
-            phase_function: Rc::new(Isotropic::from_color(color)),
+            phase_function,
         }
     }
+
+    /// A medium that scatters equally in all directions.
+    pub fn from_color(boundary: Box<dyn Hittable>, density: f64, color: Color) -> ConstantMedium {
+        ConstantMedium::new(
+            boundary,
+            density,
+            Rc::new(Anisotropic::from_color(color, PhaseFunction::Isotropic)),
+        )
+    }
 }
 
 impl Hittable for ConstantMedium {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         let mut rng = rand::thread_rng();
 
-        let mut rec1 = match self.boundary.hit(ray, NEG_INFINITY, INFINITY) {
-            Some(rec) => rec,
-            None => return None,
-        };
-        let mut rec2 = match self.boundary.hit(ray, rec1.t + 0.0001, INFINITY) {
-            Some(rec) => rec,
-            None => return None,
-        };
+        // The boundary may be entered and left several times, like a hollow `Csg` shell, so try
+        // each stretch of the ray that's inside it in turn.
+        let mut t_start = NEG_INFINITY;
+        loop {
+            let mut rec1 = self.boundary.hit(ray, t_start, INFINITY)?;
+            let mut rec2 = self.boundary.hit(ray, rec1.t + 0.0001, INFINITY)?;
+            t_start = rec2.t + 0.0001;
 
-        if rec1.t < t_min {
-            rec1.t = t_min;
-        }
-        if rec2.t > t_max {
-            rec2.t = t_max;
-        }
-        if rec1.t >= rec2.t {
-            return None;
-        }
+            if rec2.t < t_min {
+                continue;
+            }
+            if rec1.t < t_min {
+                rec1.t = t_min;
+            }
+            if rec2.t > t_max {
+                rec2.t = t_max;
+            }
+            if rec1.t >= rec2.t {
+                return None;
+            }
 
-        if rec1.t < 0. {
-            rec1.t = 0.;
-        }
+            if rec1.t < 0. {
+                rec1.t = 0.;
+            }
 
-        let ray_length = ray.direction.length();
-        let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * f64::ln(rng.gen());
+            let ray_length = ray.direction.length();
+            let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
+            let hit_distance = self.neg_inv_density * f64::ln(rng.gen());
 
-        if hit_distance > distance_inside_boundary {
-            return None;
-        }
+            if hit_distance > distance_inside_boundary {
+                continue;
+            }
 
-        let t = rec1.t + hit_distance / ray_length;
-        let p = ray.at(t);
+            let t = rec1.t + hit_distance / ray_length;
+            let p = ray.at(t);
 
-        let rec = HitRecord::new(
-            &ray,
-            t,
-            p,
-            Vec3::new(0., 0., 0.),
-            self.phase_function.clone(),
-            0.,
-            0.,
-        );
-        Some(rec)
+            let rec = HitRecord::new(
+                &ray,
+                t,
+                p,
+                Vec3::new(0., 0., 0.),
+                self.phase_function.clone(),
+                0.,
+                0.,
+            );
+            return Some(rec);
+        }
     }
 
     fn bounding_box(&self) -> Option<&AABB> {
@@ -0,0 +1,260 @@
+use super::{Point3, Ray};
+use crate::hittable::{HitRecord, Hittable, AABB};
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Operation {
+    fn contains(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            Operation::Union => in_a || in_b,
+            Operation::Intersection => in_a && in_b,
+            Operation::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// A boolean combination of two closed objects.
+///
+/// The ray is followed through the surfaces of both, keeping track of whether it's inside each
+/// one, and the hit is the first surface where it enters or leaves the combined solid. That
+/// surface keeps its own material and texture coordinates; where `b` is cut out of `a`, its
+/// normal is flipped to point out of the result. Since the front face is always right, the
+/// result refracts correctly as a `Dielectric` and works as a `ConstantMedium` boundary.
+pub struct Csg {
+    a: Box<dyn Hittable>,
+    b: Box<dyn Hittable>,
+    operation: Operation,
+    bbox: Option<AABB>,
+}
+
+impl Csg {
+    /// Everything inside either `a` or `b`.
+    pub fn union(a: Box<dyn Hittable>, b: Box<dyn Hittable>) -> Csg {
+        let bbox = match (a.bounding_box(), b.bounding_box()) {
+            (Some(box_a), Some(box_b)) => Some(AABB::surrounding_box(box_a, box_b)),
+            _ => None,
+        };
+        Csg {
+            a,
+            b,
+            operation: Operation::Union,
+            bbox,
+        }
+    }
+
+    /// Everything inside both `a` and `b`.
+    pub fn intersection(a: Box<dyn Hittable>, b: Box<dyn Hittable>) -> Csg {
+        let bbox = match (a.bounding_box(), b.bounding_box()) {
+            (Some(box_a), Some(box_b)) => Some(overlap(box_a, box_b)),
+            (Some(bbox), None) | (None, Some(bbox)) => Some(bbox.clone()),
+            (None, None) => None,
+        };
+        Csg {
+            a,
+            b,
+            operation: Operation::Intersection,
+            bbox,
+        }
+    }
+
+    /// Everything inside `a` but not inside `b`.
+    pub fn difference(a: Box<dyn Hittable>, b: Box<dyn Hittable>) -> Csg {
+        let bbox = a.bounding_box().cloned();
+        Csg {
+            a,
+            b,
+            operation: Operation::Difference,
+            bbox,
+        }
+    }
+}
+
+/// The box shared by `box0` and `box1`. If they don't overlap, it's empty, with its minimum above
+/// its maximum, so no ray hits it.
+fn overlap(box0: &AABB, box1: &AABB) -> AABB {
+    AABB {
+        minimum: Point3::new(
+            f64::max(box0.minimum.x, box1.minimum.x),
+            f64::max(box0.minimum.y, box1.minimum.y),
+            f64::max(box0.minimum.z, box1.minimum.z),
+        ),
+        maximum: Point3::new(
+            f64::min(box0.maximum.x, box1.maximum.x),
+            f64::min(box0.maximum.y, box1.maximum.y),
+            f64::min(box0.maximum.z, box1.maximum.z),
+        ),
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Whether the ray starts inside an object depends on its next surface, even one past
+        // t_max: if the ray leaves through it, it must have been inside.
+        let mut hit_a = self.a.hit(ray, t_min, f64::INFINITY);
+        let mut hit_b = self.b.hit(ray, t_min, f64::INFINITY);
+        let mut in_a = hit_a.as_ref().is_some_and(|rec| !rec.front_face);
+        let mut in_b = hit_b.as_ref().is_some_and(|rec| !rec.front_face);
+
+        loop {
+            let from_a = match (&hit_a, &hit_b) {
+                (None, None) => return None,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (Some(rec_a), Some(rec_b)) => rec_a.t <= rec_b.t,
+            };
+            let rec = if from_a { hit_a.take() } else { hit_b.take() }.unwrap();
+            if rec.t > t_max {
+                return None;
+            }
+
+            let was_inside = self.operation.contains(in_a, in_b);
+            if from_a {
+                in_a = rec.front_face;
+            } else {
+                in_b = rec.front_face;
+            }
+            if was_inside != self.operation.contains(in_a, in_b) {
+                let mut outward_normal = if rec.front_face {
+                    rec.normal
+                } else {
+                    -rec.normal
+                };
+                if let (false, Operation::Difference) = (from_a, self.operation) {
+                    outward_normal = -outward_normal;
+                }
+                return Some(HitRecord::new(
+                    ray,
+                    rec.t,
+                    rec.p,
+                    outward_normal,
+                    rec.material_rc,
+                    rec.u,
+                    rec.v,
+                ));
+            }
+
+            // step past the surface, which didn't change whether we're in the result
+            if from_a {
+                hit_a = self.a.hit(ray, rec.t + 0.0001, f64::INFINITY);
+            } else {
+                hit_b = self.b.hit(ray, rec.t + 0.0001, f64::INFINITY);
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.bbox.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Block, Sphere, Vec3};
+    use crate::hittable::test_util::grey;
+
+    // spheres of radius 1 at x = -0.5 and x = 0.5
+    fn get_spheres() -> (Box<dyn Hittable>, Box<dyn Hittable>) {
+        let material = grey();
+        (
+            Box::new(Sphere::new(Point3::new(-0.5, 0., 0.), 1., material.clone())),
+            Box::new(Sphere::new(Point3::new(0.5, 0., 0.), 1., material)),
+        )
+    }
+
+    // all the surfaces of `object` along the x-axis from the left, as (x, outward normal x)
+    fn trace_x_axis(object: &dyn Hittable) -> Vec<(f64, f64)> {
+        let ray = Ray::new(Point3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        let mut surfaces = Vec::new();
+        let mut t_min = 0.;
+        while let Some(rec) = object.hit(&ray, t_min, f64::INFINITY) {
+            let outward_x = if rec.front_face {
+                rec.normal.x
+            } else {
+                -rec.normal.x
+            };
+            surfaces.push((rec.p.x, outward_x));
+            t_min = rec.t + 0.0001;
+        }
+        surfaces
+    }
+
+    fn assert_surfaces(object: &dyn Hittable, expected: &[(f64, f64)]) {
+        let surfaces = trace_x_axis(object);
+        assert_eq!(surfaces.len(), expected.len());
+        for ((x, outward_x), (expected_x, expected_outward_x)) in surfaces.iter().zip(expected) {
+            assert!(f64::abs(x - expected_x) < Vec3::TOL);
+            assert!(f64::abs(outward_x - expected_outward_x) < Vec3::TOL);
+        }
+    }
+
+    #[test]
+    fn test_csg_union() {
+        let (a, b) = get_spheres();
+        assert_surfaces(&Csg::union(a, b), &[(-1.5, -1.), (1.5, 1.)]);
+    }
+
+    #[test]
+    fn test_csg_intersection() {
+        let (a, b) = get_spheres();
+        assert_surfaces(&Csg::intersection(a, b), &[(-0.5, -1.), (0.5, 1.)]);
+    }
+
+    #[test]
+    fn test_csg_difference() {
+        let (a, b) = get_spheres();
+        let difference = Csg::difference(a, b);
+        assert_surfaces(&difference, &[(-1.5, -1.), (-0.5, 1.)]);
+
+        // starting inside, the cut surface is where the ray leaves
+        let ray = Ray::new(Point3::new(-1., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        let rec = difference.hit(&ray, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 0.5) < Vec3::TOL);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(-1., 0., 0.));
+
+        // nothing is left inside b
+        let ray = Ray::new(Point3::new(1., -5., 0.), Vec3::new(0., 1., 0.), 0.);
+        assert!(difference.hit(&ray, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_csg_hollow_block() {
+        let material = grey();
+        let shell = Csg::difference(
+            Box::new(Block::new(
+                Point3::new(-2., -2., -2.),
+                Point3::new(2., 2., 2.),
+                material.clone(),
+            )),
+            Box::new(Sphere::new(Point3::new(0., 0., 0.), 1., material)),
+        );
+        assert_surfaces(&shell, &[(-2., -1.), (-1., 1.), (1., -1.), (2., 1.)]);
+    }
+
+    #[test]
+    fn test_csg_bounding_box() {
+        let (a, b) = get_spheres();
+        assert_eq!(
+            *Csg::intersection(a, b).bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-0.5, -1., -1.),
+                maximum: Vec3::new(0.5, 1., 1.),
+            }
+        );
+        let (a, b) = get_spheres();
+        assert_eq!(
+            *Csg::difference(a, b).bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-1.5, -1., -1.),
+                maximum: Vec3::new(0.5, 1., 1.),
+            }
+        );
+    }
+}
@@ -0,0 +1,417 @@
+use std::rc::Rc;
+
+use super::polynomial::solve_quadratic;
+use super::{Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// The cross section of a `Curve`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveShape {
+    /// A flat strip that always turns to face the ray, which is cheap and looks right for thin
+    /// strands like hair and fur, where the width is at most a pixel or so.
+    Ribbon,
+    /// A round tube, for strands thick enough for their roundness to show, like grass or wire.
+    Cylinder,
+}
+
+/// A strand following a cubic Bézier curve through four control points, whose width changes
+/// steadily from `width0` at the start to `width1` at the end.
+///
+/// The curve is split into a few pieces, each with a tight bounding box, when it's made. A ray
+/// that gets into a piece's box splits it further, as many times as it takes for the halves to be
+/// close to straight, and then tests those straight parts. Texture coordinates run along the
+/// strand in `u`, from 0 at the start to 1 at the end, and across it in `v`. Hits also carry the
+/// direction of the strand, for materials like `Hair`.
+pub struct Curve {
+    control_points: [Point3; 4],
+    width0: f64,
+    width1: f64,
+    shape: CurveShape,
+    material: Rc<dyn Material>,
+    pieces: Vec<Piece>,
+    // how many more times to split a piece to make it straight enough
+    depth: u32,
+    bbox: AABB,
+}
+
+struct Piece {
+    control_points: [Point3; 4],
+    u0: f64,
+    u1: f64,
+    bbox: AABB,
+}
+
+impl Curve {
+    /// The number of pieces the curve is split into up front.
+    const PIECES: usize = 4;
+
+    pub fn new(
+        control_points: [Point3; 4],
+        width0: f64,
+        width1: f64,
+        shape: CurveShape,
+        material: Rc<dyn Material>,
+    ) -> Curve {
+        let max_width = f64::max(width0, width1);
+        let pieces: Vec<Piece> = (0..Curve::PIECES)
+            .map(|i| {
+                let u0 = i as f64 / Curve::PIECES as f64;
+                let u1 = (i + 1) as f64 / Curve::PIECES as f64;
+                let control_points = [
+                    blossom(&control_points, u0, u0, u0),
+                    blossom(&control_points, u0, u0, u1),
+                    blossom(&control_points, u0, u1, u1),
+                    blossom(&control_points, u1, u1, u1),
+                ];
+                let bbox = hull_bbox(&control_points, max_width / 2.);
+                Piece {
+                    control_points,
+                    u0,
+                    u1,
+                    bbox,
+                }
+            })
+            .collect();
+        let bbox = pieces
+            .iter()
+            .skip(1)
+            .fold(pieces[0].bbox.clone(), |bbox, piece| {
+                AABB::surrounding_box(&bbox, &piece.bbox)
+            });
+
+        // Split until the curve strays from a straight line by less than a twentieth of its
+        // width, from how far the control points of a piece are from being evenly spaced.
+        let mut bend: f64 = 0.;
+        for piece in pieces.iter() {
+            let p = &piece.control_points;
+            for i in 0..2 {
+                let second_difference = &p[i] - 2. * &p[i + 1] + &p[i + 2];
+                for axis in 0..3 {
+                    bend = f64::max(bend, f64::abs(second_difference[axis]));
+                }
+            }
+        }
+        let epsilon = f64::max(max_width, 1e-6) / 20.;
+        let splits = f64::log2(f64::sqrt(2.) * 6. * bend / (8. * epsilon)) / 2.;
+        let depth = f64::round(splits).clamp(0., 8.) as u32;
+
+        Curve {
+            control_points,
+            width0,
+            width1,
+            shape,
+            material,
+            pieces,
+            depth,
+            bbox,
+        }
+    }
+
+    /// The direction of the curve at `u`.
+    fn tangent(&self, u: f64) -> Vec3 {
+        let p = &self.control_points;
+        let derivative = (1. - u) * (1. - u) * (&p[1] - &p[0])
+            + 2. * u * (1. - u) * (&p[2] - &p[1])
+            + u * u * (&p[3] - &p[2]);
+        if derivative.near_zero() {
+            (&p[3] - &p[0]).unit_vector()
+        } else {
+            derivative.unit_vector()
+        }
+    }
+
+    fn hit_piece(
+        &self,
+        ray: &Ray,
+        control_points: &[Point3; 4],
+        (u0, u1): (f64, f64),
+        depth: u32,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord> {
+        if depth == 0 {
+            return self.hit_segment(
+                ray,
+                &control_points[0],
+                &control_points[3],
+                (u0, u1),
+                t_min,
+                t_max,
+            );
+        }
+
+        // split in half, and try each half whose box the ray gets into
+        let halves = [
+            [
+                blossom(control_points, 0., 0., 0.),
+                blossom(control_points, 0., 0., 0.5),
+                blossom(control_points, 0., 0.5, 0.5),
+                blossom(control_points, 0.5, 0.5, 0.5),
+            ],
+            [
+                blossom(control_points, 0.5, 0.5, 0.5),
+                blossom(control_points, 0.5, 0.5, 1.),
+                blossom(control_points, 0.5, 1., 1.),
+                blossom(control_points, 1., 1., 1.),
+            ],
+        ];
+        let u_mid = (u0 + u1) / 2.;
+        let ranges = [(u0, u_mid), (u_mid, u1)];
+        let max_width = f64::max(self.width(u0), self.width(u1));
+
+        let mut closest = None;
+        let mut t_max = t_max;
+        for (half, &range) in halves.iter().zip(ranges.iter()) {
+            if !hull_bbox(half, max_width / 2.).hit(ray, t_min, t_max) {
+                continue;
+            }
+            if let Some(rec) = self.hit_piece(ray, half, range, depth - 1, t_min, t_max) {
+                t_max = rec.t;
+                closest = Some(rec);
+            }
+        }
+        closest
+    }
+
+    /// A hit on the straight stretch of strand from `a` to `b`, which runs from `u0` to `u1`.
+    fn hit_segment(
+        &self,
+        ray: &Ray,
+        a: &Point3,
+        b: &Point3,
+        (u0, u1): (f64, f64),
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord> {
+        let axis = b - a;
+        let length_squared = axis.length_squared();
+        if length_squared == 0. {
+            return None;
+        }
+        let w = &ray.origin - a;
+
+        // where along the segment the ray comes closest, and how far off it is there
+        let a11 = ray.direction.length_squared();
+        let a12 = Vec3::dot(&ray.direction, &axis);
+        let denom = a11 * length_squared - a12 * a12;
+        if denom <= 1e-12 * a11 * length_squared {
+            return None;
+        }
+        let s = (a11 * Vec3::dot(&axis, &w) - a12 * Vec3::dot(&ray.direction, &w)) / denom;
+        let s = s.clamp(0., 1.);
+        let u = u0 + s * (u1 - u0);
+        let radius = self.width(u) / 2.;
+
+        let (t, s, outward_normal) = match self.shape {
+            CurveShape::Ribbon => {
+                let on_axis = a + s * &axis;
+                let t = Vec3::dot(&ray.direction, &(&on_axis - &ray.origin)) / a11;
+                if (ray.at(t) - on_axis).length() > radius {
+                    return None;
+                }
+                (t, s, -&ray.direction)
+            }
+            CurveShape::Cylinder => {
+                // A cylinder around the segment's axis, with the radius where the ray comes
+                // closest, and round ends so that neighbouring segments join up around bends.
+                let s_at = |t: f64| Vec3::dot(&(ray.at(t) - a), &axis) / length_squared;
+                let in_range = |t: &f64| t_min <= *t && *t <= t_max;
+                let unit_axis = &axis / f64::sqrt(length_squared);
+                let d = &ray.direction - Vec3::dot(&ray.direction, &unit_axis) * &unit_axis;
+                let wp = &w - Vec3::dot(&w, &unit_axis) * &unit_axis;
+                let body = solve_quadratic([
+                    wp.length_squared() - radius * radius,
+                    2. * Vec3::dot(&wp, &d),
+                    d.length_squared(),
+                ]);
+                let mut closest = body
+                    .into_iter()
+                    .filter(in_range)
+                    .find(|&t| (0. ..=1.).contains(&s_at(t)));
+
+                for (end, s_end) in [(a, 0.), (b, 1.)].iter() {
+                    let oc = &ray.origin - *end;
+                    let cap = solve_quadratic([
+                        oc.length_squared() - radius * radius,
+                        2. * Vec3::dot(&oc, &ray.direction),
+                        a11,
+                    ]);
+                    let outside = |t: &f64| (s_at(*t) - s_end) * (s_end - 0.5) >= 0.;
+                    if let Some(t) = cap.into_iter().filter(in_range).find(outside) {
+                        if closest.is_none_or(|closest| t < closest) {
+                            closest = Some(t);
+                        }
+                    }
+                }
+
+                let t = closest?;
+                let s = s_at(t).clamp(0., 1.);
+                let on_axis = a + s * &axis;
+                (t, s, (ray.at(t) - on_axis).unit_vector())
+            }
+        };
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let u = u0 + s * (u1 - u0);
+        let side = Vec3::cross(&ray.direction, &axis).unit_vector();
+        let offset = Vec3::dot(&(&p - (a + s * &axis)), &side) / radius;
+        let v = 0.5 + 0.5 * offset.clamp(-1., 1.);
+
+        let mut rec = HitRecord::new(
+            ray,
+            t,
+            p,
+            outward_normal.unit_vector(),
+            self.material.clone(),
+            u,
+            v,
+        );
+        rec.tangent = Some(self.tangent(u));
+        Some(rec)
+    }
+
+    fn width(&self, u: f64) -> f64 {
+        self.width0 + u * (self.width1 - self.width0)
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for piece in self.pieces.iter() {
+            if !piece.bbox.hit(ray, t_min, t_max) {
+                continue;
+            }
+            let range = (piece.u0, piece.u1);
+            if let Some(rec) =
+                self.hit_piece(ray, &piece.control_points, range, self.depth, t_min, t_max)
+            {
+                t_max = rec.t;
+                closest = Some(rec);
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+/// The blossom of the cubic Bézier curve with the given control points: the curve's point at `u`
+/// is `blossom(p, u, u, u)`, and the control points of the part from `u0` to `u1` are
+/// `blossom(p, u0, u0, u0)`, `blossom(p, u0, u0, u1)`, and so on.
+fn blossom(p: &[Point3; 4], u0: f64, u1: f64, u2: f64) -> Point3 {
+    let lerp = |t: f64, a: &Point3, b: &Point3| (1. - t) * a + t * b;
+    let a = [
+        lerp(u0, &p[0], &p[1]),
+        lerp(u0, &p[1], &p[2]),
+        lerp(u0, &p[2], &p[3]),
+    ];
+    let b = [lerp(u1, &a[0], &a[1]), lerp(u1, &a[1], &a[2])];
+    lerp(u2, &b[0], &b[1])
+}
+
+/// A box around the control points, which the curve never leaves, grown by `radius`.
+fn hull_bbox(p: &[Point3; 4], radius: f64) -> AABB {
+    let mut minimum = p[0].clone();
+    let mut maximum = p[0].clone();
+    for point in p.iter().skip(1) {
+        for axis in 0..3 {
+            minimum[axis] = f64::min(minimum[axis], point[axis]);
+            maximum[axis] = f64::max(maximum[axis], point[axis]);
+        }
+    }
+    let grow = Vec3::new(radius, radius, radius);
+    AABB {
+        minimum: minimum - &grow,
+        maximum: maximum + &grow,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    // a quarter circle-ish arc from (0, 0, 0) up to (1, 1, 0), tapering from 0.2 wide to 0.1
+    fn get_curve(shape: CurveShape) -> Curve {
+        Curve::new(
+            [
+                Point3::new(0., 0., 0.),
+                Point3::new(0.5, 0., 0.),
+                Point3::new(1., 0.5, 0.),
+                Point3::new(1., 1., 0.),
+            ],
+            0.2,
+            0.1,
+            shape,
+            grey(),
+        )
+    }
+
+    #[test]
+    fn test_curve_blossom() {
+        let curve = get_curve(CurveShape::Ribbon);
+        let p = &curve.control_points;
+        assert_eq!(blossom(p, 0., 0., 0.), p[0]);
+        assert_eq!(blossom(p, 1., 1., 1.), p[3]);
+        assert_eq!(blossom(p, 0., 0., 1.), p[1]);
+        assert_eq!(blossom(p, 0., 1., 1.), p[2]);
+        // by symmetry, the middle of this curve is on the line x + y = 1
+        let middle = blossom(p, 0.5, 0.5, 0.5);
+        assert!(f64::abs(middle.x - 0.6875) < Vec3::TOL);
+        assert!(f64::abs(middle.y - 0.3125) < Vec3::TOL);
+
+        // the pieces join up
+        for pair in curve.pieces.windows(2) {
+            assert_eq!(pair[0].control_points[3], pair[1].control_points[0]);
+        }
+        assert!(curve.depth > 0);
+    }
+
+    #[test]
+    fn test_curve_hit() {
+        for &shape in [CurveShape::Ribbon, CurveShape::Cylinder].iter() {
+            let curve = get_curve(shape);
+
+            // straight down the z axis through the middle of the curve
+            let ray = Ray::new(Point3::new(0.6875, 0.3125, 5.), Vec3::new(0., 0., -1.), 0.);
+            let rec = curve.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            assert!(f64::abs(rec.u - 0.5) < 0.01);
+            assert!(f64::abs(rec.v - 0.5) < 0.01);
+            assert!(f64::abs(rec.p.x - 0.6875) < 0.001);
+            assert!(f64::abs(rec.p.y - 0.3125) < 0.001);
+            assert!(rec.front_face);
+            assert!((&rec.normal - Vec3::new(0., 0., 1.)).length() < 0.01);
+            // the tangent at the middle is along the diagonal
+            let tangent = rec.tangent.unwrap();
+            assert!((tangent - Vec3::new(1., 1., 0.).unit_vector()).length() < 1e-6);
+
+            // the width at the middle is 0.15, so just inside it on either side hits, and just
+            // outside misses
+            let across = Vec3::new(1., -1., 0.).unit_vector();
+            for &(offset, hits) in [(0.07, true), (-0.07, true), (0.08, false)].iter() {
+                let origin = Point3::new(0.6875, 0.3125, 5.) + offset * &across;
+                let ray = Ray::new(origin, Vec3::new(0., 0., -1.), 0.);
+                assert_eq!(curve.hit(&ray, 0.001, f64::INFINITY).is_some(), hits);
+            }
+
+            // beyond the end
+            let ray = Ray::new(Point3::new(1., 1.1, 5.), Vec3::new(0., 0., -1.), 0.);
+            assert!(curve.hit(&ray, 0.001, f64::INFINITY).is_none());
+        }
+
+        // seen from the side, a cylinder is hit on its surface, nearer than its axis
+        let curve = get_curve(CurveShape::Cylinder);
+        let ray = Ray::new(Point3::new(0.6875, 0.3125, 5.), Vec3::new(0., 0., -1.), 0.);
+        let rec = curve.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - (5. - 0.075)) < 0.001);
+    }
+}
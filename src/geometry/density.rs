@@ -0,0 +1,211 @@
+use std::rc::Rc;
+
+use super::{Point3, Vec3};
+use crate::hittable::AABB;
+use crate::texture::{Perlin, Texture};
+
+/// How dense a participating medium is at each point.
+pub trait DensityField {
+    fn density(&self, p: &Point3) -> f64;
+
+    /// An upper bound on the density anywhere. The tighter it is, the fewer steps it takes to
+    /// track a ray through the medium.
+    fn max_density(&self) -> f64;
+}
+
+/// Densities sampled on a regular grid spanning `bounds`, with the first and last samples along
+/// each axis on its faces, and trilinearly interpolated in between. Outside the bounds, the
+/// density is zero.
+pub struct DensityGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    // x varying fastest, then y
+    data: Vec<f64>,
+    bounds: AABB,
+    max_density: f64,
+}
+
+impl DensityGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f64>, bounds: AABB) -> DensityGrid {
+        assert!(nx >= 2 && ny >= 2 && nz >= 2 && data.len() == nx * ny * nz);
+        let max_density = data.iter().cloned().fold(0., f64::max);
+        DensityGrid {
+            nx,
+            ny,
+            nz,
+            data,
+            bounds,
+            max_density,
+        }
+    }
+
+    /// A grid filled in by calling `f` with the position of each sample.
+    pub fn from_fn<F: Fn(&Point3) -> f64>(
+        nx: usize,
+        ny: usize,
+        nz: usize,
+        bounds: AABB,
+        f: F,
+    ) -> DensityGrid {
+        let extent = &bounds.maximum - &bounds.minimum;
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let p = &bounds.minimum
+                        + Vec3::new(
+                            extent.x * i as f64 / (nx - 1) as f64,
+                            extent.y * j as f64 / (ny - 1) as f64,
+                            extent.z * k as f64 / (nz - 1) as f64,
+                        );
+                    data.push(f(&p));
+                }
+            }
+        }
+        DensityGrid::new(nx, ny, nz, data, bounds)
+    }
+
+    fn sample(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.data[i + self.nx * (j + self.ny * k)]
+    }
+}
+
+impl DensityField for DensityGrid {
+    fn density(&self, p: &Point3) -> f64 {
+        let mut cell = [0; 3];
+        let mut fraction = [0.; 3];
+        let counts = [self.nx, self.ny, self.nz];
+        for axis in 0..3 {
+            let min = self.bounds.minimum[axis];
+            let max = self.bounds.maximum[axis];
+            if p[axis] < min || p[axis] > max {
+                return 0.;
+            }
+            let x = (p[axis] - min) / (max - min) * (counts[axis] - 1) as f64;
+            cell[axis] = usize::min(x as usize, counts[axis] - 2);
+            fraction[axis] = x - cell[axis] as f64;
+        }
+
+        let [i, j, k] = cell;
+        let [u, v, w] = fraction;
+        let mut accum = 0.;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let weight = (if di == 1 { u } else { 1. - u })
+                        * (if dj == 1 { v } else { 1. - v })
+                        * (if dk == 1 { w } else { 1. - w });
+                    accum += weight * self.sample(i + di, j + dj, k + dk);
+                }
+            }
+        }
+        accum
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+/// The brightness of a texture, which should be between 0 and 1, as a density of up to
+/// `max_density`. Textures are looked up by position alone, so solid ones like `Noise` work
+/// best.
+pub struct TextureDensity {
+    pub texture: Rc<dyn Texture>,
+    pub max_density: f64,
+}
+
+impl DensityField for TextureDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        let brightness = self.texture.value(0., 0., p).luminance();
+        self.max_density * brightness.clamp(0., 1.)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+/// Perlin turbulence with `depth` octaves at `frequency` cells per unit, scaled by `density`,
+/// for billowing clouds.
+pub struct NoiseDensity {
+    pub perlin: Perlin,
+    pub frequency: f64,
+    pub depth: u32,
+    pub density: f64,
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        self.density * self.perlin.turbulence(&(self.frequency * p), self.depth)
+    }
+
+    fn max_density(&self) -> f64 {
+        // each octave of noise is at most 1, at half the weight of the last
+        2. * self.density
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::color::Color;
+    use crate::texture::{NoiseStrategy, SolidColor};
+
+    fn get_bounds() -> AABB {
+        AABB {
+            minimum: Point3::new(0., 0., 0.),
+            maximum: Point3::new(2., 2., 2.),
+        }
+    }
+
+    #[test]
+    fn test_density_grid() {
+        // rising linearly along x, from 0 to 2
+        let grid = DensityGrid::from_fn(3, 2, 2, get_bounds(), |p| p.x);
+        assert_eq!(grid.max_density(), 2.);
+        assert!(f64::abs(grid.density(&Point3::new(0.5, 1., 1.)) - 0.5) < Vec3::TOL);
+        assert!(f64::abs(grid.density(&Point3::new(1.75, 0.3, 2.)) - 1.75) < Vec3::TOL);
+        assert!(f64::abs(grid.density(&Point3::new(2., 2., 2.)) - 2.) < Vec3::TOL);
+        assert_eq!(grid.density(&Point3::new(2.1, 1., 1.)), 0.);
+        assert_eq!(grid.density(&Point3::new(1., -0.1, 1.)), 0.);
+    }
+
+    #[test]
+    fn test_density_grid_trilinear() {
+        // only the far corner is dense, so the middle gets an eighth of it
+        let mut data = vec![0.; 8];
+        data[7] = 8.;
+        let grid = DensityGrid::new(2, 2, 2, data, get_bounds());
+        assert!(f64::abs(grid.density(&Point3::new(1., 1., 1.)) - 1.) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_texture_density() {
+        let field = TextureDensity {
+            texture: Rc::new(SolidColor {
+                color: Color::new(0.5, 0.5, 0.5),
+            }),
+            max_density: 4.,
+        };
+        assert!(f64::abs(field.density(&Point3::new(1., 2., 3.)) - 2.) < Vec3::TOL);
+        assert_eq!(field.max_density(), 4.);
+    }
+
+    #[test]
+    fn test_noise_density() {
+        let field = NoiseDensity {
+            perlin: Perlin::new(NoiseStrategy::PerlinInterpolation),
+            frequency: 3.,
+            depth: 7,
+            density: 0.5,
+        };
+        for n in 0..100 {
+            let p = Point3::new(n as f64 * 0.37, n as f64 * 0.11, n as f64 * -0.23);
+            let density = field.density(&p);
+            assert!(0. <= density && density <= field.max_density());
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use super::planar::{padded, PlanarFrame};
+use super::{Onb, Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A flat ring around `center`, facing along `normal`, between `inner_radius` and
+/// `outer_radius`.
+///
+/// Texture coordinates are polar: `u` goes once around the ring, and `v` runs from the inner edge
+/// to the outer edge.
+pub struct Annulus {
+    frame: PlanarFrame,
+    // the inner radius as a fraction of the outer one
+    inner_ratio: f64,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Annulus {
+    pub fn new(
+        center: Point3,
+        normal: Vec3,
+        inner_radius: f64,
+        outer_radius: f64,
+        material: Rc<dyn Material>,
+    ) -> Annulus {
+        assert!(0. <= inner_radius && inner_radius < outer_radius);
+        let onb = Onb::from_w(&normal);
+
+        let bbox = circle_bbox(&center, &onb.w, outer_radius);
+        Annulus {
+            frame: PlanarFrame::new(center, outer_radius * onb.u, outer_radius * onb.v),
+            inner_ratio: inner_radius / outer_radius,
+            material,
+            bbox: padded(bbox),
+        }
+    }
+}
+
+/// The bounds of a circle of radius `r` around `center` facing along the unit vector `n`, which
+/// extends `r * sqrt(1 - n_i^2)` along each axis i.
+pub(super) fn circle_bbox(center: &Point3, n: &Vec3, r: f64) -> AABB {
+    let mut bbox = AABB {
+        minimum: center.clone(),
+        maximum: center.clone(),
+    };
+    for i in 0..3 {
+        let extent = r * f64::sqrt(f64::max(0., 1. - n[i] * n[i]));
+        bbox.minimum[i] -= extent;
+        bbox.maximum[i] += extent;
+    }
+    bbox
+}
+
+impl Hittable for Annulus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, p, alpha, beta) = self.frame.intersect(ray, t_min, t_max)?;
+        let r = f64::sqrt(alpha * alpha + beta * beta);
+        if r < self.inner_ratio || r > 1. {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            ray,
+            t,
+            p,
+            self.frame.normal.clone(),
+            self.material.clone(),
+            (f64::atan2(beta, alpha) + PI) / (2. * PI),
+            (r - self.inner_ratio) / (1. - self.inner_ratio),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+/// A flat disk around `center`, facing along `normal`: a ring with no hole.
+pub struct Disk {
+    ring: Annulus,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Rc<dyn Material>) -> Disk {
+        Disk {
+            ring: Annulus::new(center, normal, 0., radius, material),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.ring.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.ring.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    #[test]
+    fn test_disk_hit() {
+        let disk = Disk::new(Point3::new(0., 0., -2.), Vec3::new(0., 0., 1.), 1., grey());
+
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.), 0.);
+        let rec = disk.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+        assert!(f64::abs(rec.v) < Vec3::TOL);
+
+        let r = Ray::new(Point3::new(0.8, 0.8, 0.), Vec3::new(0., 0., -1.), 0.);
+        assert!(disk.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(0.8, 0.8, 0.), Vec3::new(-0.1, -0.1, -1.), 0.);
+        assert!(disk.hit(&r, 0., f64::INFINITY).is_some());
+    }
+
+    #[test]
+    fn test_annulus_hit() {
+        let annulus = Annulus::new(
+            Point3::new(0., 1., 0.),
+            Vec3::new(0., 1., 0.),
+            1.,
+            2.,
+            grey(),
+        );
+
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(annulus.hit(&r, 0., f64::INFINITY).is_none());
+
+        let r = Ray::new(Point3::new(1.5, 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = annulus.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        assert!(f64::abs(rec.v - 0.5) < Vec3::TOL);
+
+        let r = Ray::new(Point3::new(2.5, 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(annulus.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_disk_bounding_box() {
+        let disk = Disk::new(Point3::new(1., 1., 1.), Vec3::new(1., 0., 1.), 2., grey());
+        let bbox = disk.bounding_box().unwrap();
+        let half_diag = f64::sqrt(2.);
+        assert_eq!(
+            *bbox,
+            AABB {
+                minimum: Vec3::new(1. - half_diag, -1., 1. - half_diag),
+                maximum: Vec3::new(1. + half_diag, 3., 1. + half_diag),
+            }
+        );
+    }
+}
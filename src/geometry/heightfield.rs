@@ -0,0 +1,431 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use stb_image::image;
+use stb_image::image::LoadResult;
+
+use super::{Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+use crate::texture::Perlin;
+
+/// Terrain from a grid of height samples, spread evenly over the rectangle from `corner` spanning
+/// `size.x` along x and `size.z` along z. Each grid cell is split into two triangles, with normals
+/// interpolated between the samples so the surface looks smooth.
+///
+/// Rays walk the grid cell by cell, so only the cells under the ray are tested. Texture
+/// coordinates cover the whole terrain, the way an image of it would: `u` runs along x, and `v`
+/// runs from the top of the image at the low z edge to the bottom at the high z edge.
+pub struct Heightfield {
+    // heights above corner.y, x varying fastest
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    nx: usize,
+    nz: usize,
+    corner: Point3,
+    size: Vec3,
+    dx: f64,
+    dz: f64,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Heightfield {
+    /// `heights` holds `nx` by `nz` samples between 0 and 1, x varying fastest, which are scaled
+    /// up to `size.y`.
+    pub fn new(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        corner: Point3,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> Heightfield {
+        assert!(nx >= 2 && nz >= 2 && heights.len() == nx * nz);
+        let heights: Vec<f64> = heights.iter().map(|h| h * size.y).collect();
+        let dx = size.x / (nx - 1) as f64;
+        let dz = size.z / (nz - 1) as f64;
+
+        // central differences, or one-sided ones along the edges
+        let mut normals = Vec::with_capacity(nx * nz);
+        for j in 0..nz {
+            for i in 0..nx {
+                let (i0, i1) = (i.saturating_sub(1), usize::min(i + 1, nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), usize::min(j + 1, nz - 1));
+                let slope_x =
+                    (heights[i1 + nx * j] - heights[i0 + nx * j]) / ((i1 - i0) as f64 * dx);
+                let slope_z =
+                    (heights[i + nx * j1] - heights[i + nx * j0]) / ((j1 - j0) as f64 * dz);
+                normals.push(Vec3::new(-slope_x, 1., -slope_z).unit_vector());
+            }
+        }
+
+        let lowest = heights.iter().cloned().fold(f64::INFINITY, f64::min);
+        let highest = heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let bbox = AABB {
+            minimum: Point3::new(corner.x, corner.y + lowest - 0.0001, corner.z),
+            maximum: Point3::new(
+                corner.x + size.x,
+                corner.y + highest + 0.0001,
+                corner.z + size.z,
+            ),
+        };
+
+        Heightfield {
+            heights,
+            normals,
+            nx,
+            nz,
+            corner,
+            size,
+            dx,
+            dz,
+            material,
+            bbox,
+        }
+    }
+
+    /// Heights from the brightness of a grayscale image, one sample per pixel, with the top row
+    /// of the image along the low z edge.
+    pub fn from_image<P: AsRef<Path>>(
+        path: P,
+        corner: Point3,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> Heightfield {
+        // see ImageTexture::from_file for why this loads from memory
+        let bytes = std::fs::read(path).expect("Error reading image");
+        match image::load_from_memory_with_depth(&bytes, 1, false) {
+            LoadResult::Error(s) => panic!("Error loading image: {}", s),
+            LoadResult::ImageF32(_) => panic!("Sorry, can't hangle f32 image format (yet)"),
+            LoadResult::ImageU8(img) => {
+                let heights = img.data.iter().map(|&h| h as f64 / 255.).collect();
+                Heightfield::new(heights, img.width, img.height, corner, size, material)
+            }
+        }
+    }
+
+    /// Heights from Perlin turbulence with `depth` octaves, sampled on a `resolution` by
+    /// `resolution` grid covering `frequency` noise cells each way, and scaled so the highest
+    /// point reaches `size.y`.
+    pub fn from_perlin(
+        perlin: &Perlin,
+        resolution: usize,
+        frequency: f64,
+        depth: u32,
+        corner: Point3,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> Heightfield {
+        let scale = frequency / (resolution - 1) as f64;
+        let mut heights = Vec::with_capacity(resolution * resolution);
+        for j in 0..resolution {
+            for i in 0..resolution {
+                let p = Point3::new(i as f64 * scale, 0., j as f64 * scale);
+                heights.push(perlin.turbulence(&p, depth));
+            }
+        }
+
+        let highest = heights.iter().cloned().fold(0., f64::max);
+        if highest > 0. {
+            heights.iter_mut().for_each(|h| *h /= highest);
+        }
+        Heightfield::new(heights, resolution, resolution, corner, size, material)
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> (Point3, &Vec3) {
+        let p = Point3::new(
+            self.corner.x + i as f64 * self.dx,
+            self.corner.y + self.heights[i + self.nx * j],
+            self.corner.z + j as f64 * self.dz,
+        );
+        (p, &self.normals[i + self.nx * j])
+    }
+
+    /// The closest hit on the two triangles of cell `(i, j)`, which the ray crosses between
+    /// `t_enter` and `t_exit`.
+    fn hit_cell(
+        &self,
+        ray: &Ray,
+        (i, j): (usize, usize),
+        (t_enter, t_exit): (f64, f64),
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord> {
+        let corners = [
+            self.vertex(i, j),
+            self.vertex(i + 1, j),
+            self.vertex(i + 1, j + 1),
+            self.vertex(i, j + 1),
+        ];
+
+        // skip the cell if the ray passes wholly above or below it
+        let y0 = ray.origin.y + t_enter * ray.direction.y;
+        let y1 = ray.origin.y + t_exit * ray.direction.y;
+        let lowest = corners.iter().map(|c| c.0.y).fold(f64::INFINITY, f64::min);
+        let highest = corners
+            .iter()
+            .map(|c| c.0.y)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if f64::min(y0, y1) > highest || f64::max(y0, y1) < lowest {
+            return None;
+        }
+
+        let mut closest: Option<(f64, Vec3)> = None;
+        let mut t_max = t_max;
+        for (a, b, c) in &[(0, 1, 2), (0, 2, 3)] {
+            let (a, b, c) = (&corners[*a], &corners[*b], &corners[*c]);
+            if let Some((t, beta, gamma)) = intersect_triangle(ray, &a.0, &b.0, &c.0, t_min, t_max)
+            {
+                let normal = (1. - beta - gamma) * a.1 + beta * b.1 + gamma * c.1;
+                closest = Some((t, normal.unit_vector()));
+                t_max = t;
+            }
+        }
+
+        let (t, normal) = closest?;
+        let p = ray.at(t);
+        let u = (p.x - self.corner.x) / self.size.x;
+        let v = 1. - (p.z - self.corner.z) / self.size.z;
+        Some(HitRecord::new(
+            ray,
+            t,
+            p,
+            normal,
+            self.material.clone(),
+            u,
+            v,
+        ))
+    }
+}
+
+/// Möller-Trumbore: the `t` where the ray hits the triangle `abc`, and the barycentric weights of
+/// `b` and `c` at that point.
+fn intersect_triangle(
+    ray: &Ray,
+    a: &Point3,
+    b: &Point3,
+    c: &Point3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let pvec = Vec3::cross(&ray.direction, &edge2);
+    let det = Vec3::dot(&edge1, &pvec);
+    if f64::abs(det) < 1e-12 {
+        return None;
+    }
+    let inv_det = 1. / det;
+
+    let tvec = &ray.origin - a;
+    let beta = Vec3::dot(&tvec, &pvec) * inv_det;
+    if !(0. ..=1.).contains(&beta) {
+        return None;
+    }
+    let qvec = Vec3::cross(&tvec, &edge1);
+    let gamma = Vec3::dot(&ray.direction, &qvec) * inv_det;
+    if gamma < 0. || beta + gamma > 1. {
+        return None;
+    }
+
+    let t = Vec3::dot(&edge2, &qvec) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some((t, beta, gamma))
+}
+
+/// Where a ray walking a grid along one axis is: the cell it's in, the step to the next cell, and
+/// the `t` where it crosses into the next cell, which grows by `t_delta` per cell.
+pub(super) struct GridWalk {
+    pub(super) cell: isize,
+    step: isize,
+    pub(super) t_next: f64,
+    t_delta: f64,
+}
+
+impl GridWalk {
+    pub(super) fn new(
+        origin: f64,
+        direction: f64,
+        start: f64,
+        cell_size: f64,
+        cells: usize,
+    ) -> GridWalk {
+        let cell = f64::floor(start / cell_size) as isize;
+        let cell = cell.clamp(0, cells as isize - 1);
+        if direction == 0. {
+            return GridWalk {
+                cell,
+                step: 0,
+                t_next: f64::INFINITY,
+                t_delta: f64::INFINITY,
+            };
+        }
+
+        let (step, next_line) = if direction > 0. {
+            (1, (cell + 1) as f64 * cell_size)
+        } else {
+            (-1, cell as f64 * cell_size)
+        };
+        GridWalk {
+            cell,
+            step,
+            t_next: (next_line - origin) / direction,
+            t_delta: cell_size / f64::abs(direction),
+        }
+    }
+
+    pub(super) fn advance(&mut self) {
+        self.cell += self.step;
+        self.t_next += self.t_delta;
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_start, t_end) = self.bbox.intersect(ray, t_min, t_max)?;
+
+        // walk the cells in grid coordinates, relative to the corner
+        let start = ray.at(t_start);
+        let mut walk_x = GridWalk::new(
+            ray.origin.x - self.corner.x,
+            ray.direction.x,
+            start.x - self.corner.x,
+            self.dx,
+            self.nx - 1,
+        );
+        let mut walk_z = GridWalk::new(
+            ray.origin.z - self.corner.z,
+            ray.direction.z,
+            start.z - self.corner.z,
+            self.dz,
+            self.nz - 1,
+        );
+
+        let mut t_enter = t_start;
+        loop {
+            let t_exit = f64::min(f64::min(walk_x.t_next, walk_z.t_next), t_end);
+            let cell = (walk_x.cell as usize, walk_z.cell as usize);
+            let rec = self.hit_cell(ray, cell, (t_enter, t_exit), t_min, t_max);
+            if rec.is_some() {
+                return rec;
+            }
+            if t_exit >= t_end {
+                return None;
+            }
+
+            if walk_x.t_next < walk_z.t_next {
+                walk_x.advance();
+            } else {
+                walk_z.advance();
+            }
+            if walk_x.cell < 0
+                || walk_x.cell >= self.nx as isize - 1
+                || walk_z.cell < 0
+                || walk_z.cell >= self.nz as isize - 1
+            {
+                return None;
+            }
+            t_enter = t_exit;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+    use crate::texture::NoiseStrategy;
+
+    // a 4 by 4 terrain rising along x, so that y = x
+    fn get_ramp() -> Heightfield {
+        let heights = (0..25).map(|n| (n % 5) as f64 / 4.).collect();
+        Heightfield::new(
+            heights,
+            5,
+            5,
+            Point3::new(0., 0., 0.),
+            Vec3::new(4., 4., 4.),
+            grey(),
+        )
+    }
+
+    #[test]
+    fn test_heightfield_hit() {
+        let ramp = get_ramp();
+
+        let r = Ray::new(Point3::new(2.5, 10., 1.3), Vec3::new(0., -1., 0.), 0.);
+        let rec = ramp.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 7.5) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(-1., 1., 0.).unit_vector());
+        assert!(f64::abs(rec.u - 2.5 / 4.) < Vec3::TOL);
+        assert!(f64::abs(rec.v - (1. - 1.3 / 4.)) < Vec3::TOL);
+
+        // walking across several cells before the ramp rises to meet the ray
+        let r = Ray::new(Point3::new(-5., 1.5, 0.7), Vec3::new(1., 0., 0.2), 0.);
+        let rec = ramp.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 6.5) < Vec3::TOL);
+        let r = Ray::new(Point3::new(10., 1.5, 3.7), Vec3::new(-1., 0., -0.2), 0.);
+        let rec = ramp.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 8.5) < Vec3::TOL);
+
+        // over the top, under the bottom, and cut short
+        let r = Ray::new(Point3::new(-5., 4.5, 2.), Vec3::new(1., 0., 0.), 0.);
+        assert!(ramp.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(2., -1., 2.), Vec3::new(1., 0., 0.), 0.);
+        assert!(ramp.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(-5., 1.5, 0.7), Vec3::new(1., 0., 0.), 0.);
+        assert!(ramp.hit(&r, 0., 6.).is_none());
+    }
+
+    #[test]
+    fn test_heightfield_peak_hit() {
+        // flat, except for a single peak in the middle
+        let mut heights = vec![0.; 25];
+        heights[12] = 1.;
+        let peak = Heightfield::new(
+            heights,
+            5,
+            5,
+            Point3::new(0., 0., 0.),
+            Vec3::new(4., 4., 4.),
+            grey(),
+        );
+
+        let r = Ray::new(Point3::new(-5., 0.5, 2.25), Vec3::new(1., 0., 0.), 0.);
+        let rec = peak.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 6.375) < Vec3::TOL);
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn test_heightfield_from_perlin() {
+        let perlin = Perlin::new(NoiseStrategy::PerlinInterpolation);
+        let terrain = Heightfield::from_perlin(
+            &perlin,
+            16,
+            4.,
+            5,
+            Point3::new(-8., 1., -8.),
+            Vec3::new(16., 3., 16.),
+            grey(),
+        );
+        let bbox = terrain.bounding_box().unwrap();
+        assert!(f64::abs(bbox.maximum.y - 4.) < 0.001);
+        assert!(bbox.minimum.y >= 1. - 0.001);
+
+        // straight down, every ray hits
+        for &(x, z) in &[(-7.9, -7.9), (0., 0.), (3.3, -5.1), (7.9, 7.9)] {
+            let r = Ray::new(Point3::new(x, 10., z), Vec3::new(0., -1., 0.), 0.);
+            let rec = terrain.hit(&r, 0., f64::INFINITY).unwrap();
+            assert!(rec.front_face);
+        }
+    }
+}
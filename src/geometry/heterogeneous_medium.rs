@@ -0,0 +1,167 @@
+use std::rc::Rc;
+
+use rand::Rng;
+
+use super::DensityField;
+use crate::color::Color;
+use crate::geometry::{Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::types::{Anisotropic, PhaseFunction};
+use crate::material::Material;
+
+/// A participating medium inside `boundary` whose density varies from point to point, for clouds
+/// and smoke.
+///
+/// Scattering is found by delta tracking: the ray takes random steps as if the medium were as
+/// dense as the field's maximum everywhere, and at each step really scatters with probability
+/// `density / max_density`. The other steps are null collisions, and the ray carries on.
+pub struct HeterogeneousMedium {
+    boundary: Box<dyn Hittable>,
+    density: Box<dyn DensityField>,
+    phase_function: Rc<dyn Material>,
+}
+
+impl HeterogeneousMedium {
+    /// A medium that scatters with the given material, like an `Anisotropic` one.
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: Box<dyn DensityField>,
+        phase_function: Rc<dyn Material>,
+    ) -> HeterogeneousMedium {
+        HeterogeneousMedium {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    /// A medium that scatters equally in all directions.
+    pub fn from_color(
+        boundary: Box<dyn Hittable>,
+        density: Box<dyn DensityField>,
+        color: Color,
+    ) -> HeterogeneousMedium {
+        HeterogeneousMedium::new(
+            boundary,
+            density,
+            Rc::new(Anisotropic::from_color(color, PhaseFunction::Isotropic)),
+        )
+    }
+
+    /// The stretches of the ray between `t_min` and `t_max` that are inside the boundary, which
+    /// may be entered and left several times.
+    fn segments(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<(f64, f64)> {
+        let mut segments = Vec::new();
+        let mut t_start = f64::NEG_INFINITY;
+        while let Some(rec1) = self.boundary.hit(ray, t_start, f64::INFINITY) {
+            let rec2 = match self.boundary.hit(ray, rec1.t + 0.0001, f64::INFINITY) {
+                Some(rec) => rec,
+                None => break,
+            };
+            let (t0, t1) = (f64::max(rec1.t, t_min), f64::min(rec2.t, t_max));
+            if t0 < t1 {
+                segments.push((t0, t1));
+            }
+            if rec2.t >= t_max {
+                break;
+            }
+            t_start = rec2.t + 0.0001;
+        }
+        segments
+    }
+
+    /// The `t` to the next collision with the maximum density, given a uniform random number.
+    fn free_path(&self, ray: &Ray, random: f64) -> f64 {
+        -f64::ln(1. - random) / (self.density.max_density() * ray.direction.length())
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let max_density = self.density.max_density();
+        if max_density <= 0. {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        for (t0, t1) in self.segments(ray, t_min, t_max) {
+            let mut t = t0;
+            loop {
+                t += self.free_path(ray, rng.gen());
+                if t >= t1 {
+                    break;
+                }
+                let p = ray.at(t);
+                if rng.gen::<f64>() * max_density < self.density.density(&p) {
+                    return Some(HitRecord::new(
+                        ray,
+                        t,
+                        p,
+                        Vec3::new(0., 0., 0.),
+                        self.phase_function.clone(),
+                        0.,
+                        0.,
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Block, DensityGrid, Point3};
+    use crate::material::types::Lambertian;
+
+    // a 2 by 2 by 2 block whose density rises from 0 to 1 along x, and a ray through it along x,
+    // so the optical depth is 1
+    fn get_medium() -> (HeterogeneousMedium, Ray) {
+        let bounds = AABB {
+            minimum: Point3::new(0., 0., 0.),
+            maximum: Point3::new(2., 2., 2.),
+        };
+        let material = Rc::new(Lambertian::from_color(Color::new(1., 1., 1.)));
+        let boundary = Block::new(bounds.minimum.clone(), bounds.maximum.clone(), material);
+        let density = DensityGrid::from_fn(3, 2, 2, bounds, |p| p.x / 2.);
+        let medium = HeterogeneousMedium::from_color(
+            Box::new(boundary),
+            Box::new(density),
+            Color::new(1., 1., 1.),
+        );
+        let ray = Ray::new(Point3::new(-1., 1., 1.), Vec3::new(1., 0., 0.), 0.);
+        (medium, ray)
+    }
+
+    #[test]
+    fn test_heterogeneous_medium_hit() {
+        let (medium, ray) = get_medium();
+
+        let n = 20000;
+        let mut scattered = 0;
+        for _ in 0..n {
+            if let Some(rec) = medium.hit(&ray, 0.001, f64::INFINITY) {
+                assert!(1. <= rec.t && rec.t <= 3.);
+                scattered += 1;
+            }
+        }
+        let expected = 1. - f64::exp(-1.);
+        assert!(f64::abs(scattered as f64 / n as f64 - expected) < 0.015);
+
+        // only half way in, where the density has only reached 0.5
+        let scattered = (0..n)
+            .filter(|_| medium.hit(&ray, 0.001, 2.).is_some())
+            .count();
+        let expected = 1. - f64::exp(-0.25);
+        assert!(f64::abs(scattered as f64 / n as f64 - expected) < 0.015);
+
+        // there's nothing to scatter off before the ray reaches the medium
+        assert!(medium.hit(&ray, 0.001, 1.).is_none());
+    }
+}
@@ -0,0 +1,279 @@
+use std::f64::consts::PI;
+use std::ops::Mul;
+
+use super::{Point3, Vec3};
+
+/// A 4x4 matrix in row-major order, used to represent affine transforms of points and vectors in
+/// homogeneous coordinates.
+#[derive(Clone, Debug)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m }
+    }
+
+    pub fn identity() -> Mat4 {
+        Mat4::new([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn translation(offset: &Vec3) -> Mat4 {
+        Mat4::new([
+            [1., 0., 0., offset.x],
+            [0., 1., 0., offset.y],
+            [0., 0., 1., offset.z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Non-uniform scaling along the coordinate axes.
+    pub fn scaling(factors: &Vec3) -> Mat4 {
+        Mat4::new([
+            [factors.x, 0., 0., 0.],
+            [0., factors.y, 0., 0.],
+            [0., 0., factors.z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Rotation by `angle` degrees about `axis` (which need not be normalized), counter-clockwise
+    /// when looking down the axis towards the origin.
+    pub fn rotation(axis: &Vec3, angle: f64) -> Mat4 {
+        let a = axis.unit_vector();
+        let theta = angle * PI / 180.;
+        let (s, c) = (f64::sin(theta), f64::cos(theta));
+        let t = 1. - c;
+
+        // Rodrigues' rotation formula in matrix form
+        Mat4::new([
+            [
+                t * a.x * a.x + c,
+                t * a.x * a.y - s * a.z,
+                t * a.x * a.z + s * a.y,
+                0.,
+            ],
+            [
+                t * a.x * a.y + s * a.z,
+                t * a.y * a.y + c,
+                t * a.y * a.z - s * a.x,
+                0.,
+            ],
+            [
+                t * a.x * a.z - s * a.y,
+                t * a.y * a.z + s * a.x,
+                t * a.z * a.z + c,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Places an object at `from`, with its local z-axis pointing towards `at` and its local
+    /// y-axis as close to `up` as possible.
+    pub fn look_at(from: &Point3, at: &Point3, up: &Vec3) -> Mat4 {
+        let w = (at - from).unit_vector();
+        let u = Vec3::cross(up, &w).unit_vector();
+        let v = Vec3::cross(&w, &u);
+
+        Mat4::new([
+            [u.x, v.x, w.x, from.x],
+            [u.y, v.y, w.y, from.y],
+            [u.z, v.z, w.z, from.z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Apply a rotation after this transform.
+    pub fn rotate(self, axis: &Vec3, angle: f64) -> Mat4 {
+        &Mat4::rotation(axis, angle) * &self
+    }
+
+    /// Apply a scaling after this transform.
+    pub fn scale(self, factors: &Vec3) -> Mat4 {
+        &Mat4::scaling(factors) * &self
+    }
+
+    /// Apply a translation after this transform.
+    pub fn translate(self, offset: &Vec3) -> Mat4 {
+        &Mat4::translation(offset) * &self
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, val) in row.iter_mut().enumerate() {
+                *val = self.m[j][i];
+            }
+        }
+        Mat4::new(m)
+    }
+
+    /// Invert the matrix using Gauss-Jordan elimination with partial pivoting. Returns `None` if
+    /// the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| {
+                    f64::abs(a[i][col])
+                        .partial_cmp(&f64::abs(a[j][col]))
+                        .unwrap()
+                })
+                .unwrap();
+            if f64::abs(a[pivot][col]) < Vec3::TOL {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1. / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+
+        Some(Mat4::new(inv))
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        Point3::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    /// Transform a direction, ignoring the translational part of the matrix.
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl PartialEq for Mat4 {
+    fn eq(&self, other: &Self) -> bool {
+        (0..4).all(|i| (0..4).all(|j| f64::abs(self.m[i][j] - other.m[i][j]) < Vec3::TOL))
+    }
+}
+
+impl Mul<&Mat4> for &Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: &Mat4) -> Mat4 {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, val) in row.iter_mut().enumerate() {
+                *val = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4::new(m)
+    }
+}
+
+impl Mul<Mat4> for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        &self * &rhs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mat4_rotation() {
+        let rot_y = Mat4::rotation(&Vec3::new(0., 1., 0.), 90.);
+        assert_eq!(
+            rot_y.transform_vector(&Vec3::new(1., 0., 0.)),
+            Vec3::new(0., 0., -1.)
+        );
+
+        let rot_z = Mat4::rotation(&Vec3::new(0., 0., 2.), 90.);
+        assert_eq!(
+            rot_z.transform_vector(&Vec3::new(1., 0., 0.)),
+            Vec3::new(0., 1., 0.)
+        );
+
+        // rotating about (1, 1, 1) by 120 degrees cycles the axes
+        let rot_diag = Mat4::rotation(&Vec3::new(1., 1., 1.), 120.);
+        assert_eq!(
+            rot_diag.transform_vector(&Vec3::new(1., 0., 0.)),
+            Vec3::new(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    fn test_mat4_compose() {
+        let m = Mat4::identity()
+            .scale(&Vec3::new(2., 1., 1.))
+            .rotate(&Vec3::new(0., 0., 1.), 90.)
+            .translate(&Vec3::new(0., 0., 5.));
+
+        assert_eq!(
+            m.transform_point(&Point3::new(1., 0., 0.)),
+            Point3::new(0., 2., 5.)
+        );
+        assert_eq!(
+            m.transform_vector(&Vec3::new(1., 0., 0.)),
+            Vec3::new(0., 2., 0.)
+        );
+    }
+
+    #[test]
+    fn test_mat4_inverse() {
+        let m = Mat4::identity()
+            .scale(&Vec3::new(2., 3., 0.5))
+            .rotate(&Vec3::new(1., 2., 3.), 33.)
+            .translate(&Vec3::new(-4., 1., 7.));
+        let inv = m.inverse().unwrap();
+
+        assert_eq!(&m * &inv, Mat4::identity());
+        assert_eq!(&inv * &m, Mat4::identity());
+
+        assert!(Mat4::scaling(&Vec3::new(1., 0., 1.)).inverse().is_none());
+    }
+
+    #[test]
+    fn test_mat4_look_at() {
+        let from = Point3::new(1., 2., 3.);
+        let m = Mat4::look_at(&from, &Point3::new(1., 2., 10.), &Vec3::new(0., 1., 0.));
+
+        assert_eq!(m.transform_point(&Point3::new(0., 0., 0.)), from);
+        assert_eq!(
+            m.transform_vector(&Vec3::new(0., 0., 1.)),
+            Vec3::new(0., 0., 1.)
+        );
+        assert_eq!(
+            m.transform_vector(&Vec3::new(0., 1., 0.)),
+            Vec3::new(0., 1., 0.)
+        );
+    }
+}
@@ -1,17 +1,79 @@
+mod atmosphere;
+pub use atmosphere::Atmosphere;
+
 mod block;
 pub use block::Block;
 
+mod capsule;
+pub use capsule::Capsule;
+
+mod cone;
+pub use cone::{Cone, Cylinder};
+
+mod density;
+pub use density::{DensityField, DensityGrid, NoiseDensity, TextureDensity};
+
+mod disk;
+pub use disk::{Annulus, Disk};
+
 mod constant_medium;
 pub use constant_medium::ConstantMedium;
 
+mod csg;
+pub use csg::Csg;
+
+mod curve;
+pub use curve::{Curve, CurveShape};
+
+mod heightfield;
+pub use heightfield::Heightfield;
+
+mod heterogeneous_medium;
+pub use heterogeneous_medium::HeterogeneousMedium;
+
+mod mat4;
+pub use mat4::Mat4;
+
+mod onb;
+pub use onb::Onb;
+
+mod moving_sphere;
+pub use moving_sphere::MovingSphere;
+
+mod planar;
+
+mod plane;
+pub use plane::Plane;
+
+mod polynomial;
+
+mod quad;
+pub use quad::Quad;
+
 mod sphere;
 pub use sphere::Sphere;
 
+mod torus;
+pub use torus::Torus;
+
+mod triangle;
+pub use triangle::Triangle;
+
 mod ray;
 pub use ray::Ray;
 
 mod vec3;
 pub use vec3::{Point3, RandomVectorType, Vec3};
 
+mod volume_file;
+pub use volume_file::VolumeFile;
+
+mod vox;
+
+mod voxel_grid;
+pub use voxel_grid::VoxelGrid;
+
+pub mod sdf;
+
 mod rect;
 pub use rect::{AxisAlignment, Rect, RectCorner};
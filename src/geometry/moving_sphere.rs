@@ -0,0 +1,153 @@
+use std::rc::Rc;
+
+use super::{Point3, Ray, Sphere, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A sphere whose center moves linearly from `center0` at `time0` to `center1` at `time1`.
+pub struct MovingSphere {
+    center0: Point3,
+    center1: Point3,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    material_rc: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material_rc: Rc<dyn Material>,
+    ) -> Self {
+        let r = Vec3::new(radius, radius, radius);
+        let box0 = AABB {
+            minimum: &center0 - &r,
+            maximum: &center0 + &r,
+        };
+        let box1 = AABB {
+            minimum: &center1 - &r,
+            maximum: &center1 + &r,
+        };
+        let bbox = AABB::surrounding_box(&box0, &box1);
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material_rc,
+            bbox,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        if self.time1 == self.time0 {
+            return self.center0.clone();
+        }
+        let fraction = (time - self.time0) / (self.time1 - self.time0);
+        &self.center0 + fraction * (&self.center1 - &self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let center = self.center(ray.time);
+        let origin_to_center = &ray.origin - &center;
+        let a = ray.direction.length_squared();
+        let half_b = Vec3::dot(&origin_to_center, &ray.direction);
+        let c = origin_to_center.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0. {
+            return None;
+        }
+        let sqrt_discrm = f64::sqrt(discriminant);
+
+        // Find the nearest root that lies in the acceptable range.
+        let mut root = (-half_b - sqrt_discrm) / a;
+        if root < t_min || t_max < root {
+            root = (-half_b + sqrt_discrm) / a;
+            if root < t_min || t_max < root {
+                return None;
+            }
+        }
+
+        let outward_normal = (&ray.at(root) - &center) / self.radius;
+        let (u, v) = Sphere::get_sphere_uv(&outward_normal);
+        let rec = HitRecord::new(
+            ray,
+            root,
+            ray.at(root),
+            outward_normal,
+            self.material_rc.clone(),
+            u,
+            v,
+        );
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    fn get_moving_sphere() -> MovingSphere {
+        let material_rc = grey();
+        MovingSphere::new(
+            Point3::new(0., 0., -1.),
+            Point3::new(2., 0., -1.),
+            0.,
+            1.,
+            0.5,
+            material_rc,
+        )
+    }
+
+    #[test]
+    fn test_moving_sphere_center() {
+        let sphere = get_moving_sphere();
+        assert_eq!(sphere.center(0.), Point3::new(0., 0., -1.));
+        assert_eq!(sphere.center(0.5), Point3::new(1., 0., -1.));
+        assert_eq!(sphere.center(1.), Point3::new(2., 0., -1.));
+    }
+
+    #[test]
+    fn test_moving_sphere_hit() {
+        let sphere = get_moving_sphere();
+        let origin = Point3::new(0., 0., 0.);
+
+        let r = Ray::new(origin.clone(), Vec3::new(0., 0., -1.), 0.);
+        assert!(sphere.hit(&r, 0., f64::INFINITY).is_some());
+
+        // by the end of the shutter interval, the sphere has moved out of the ray's path
+        let r = Ray::new(origin.clone(), Vec3::new(0., 0., -1.), 1.);
+        assert!(sphere.hit(&r, 0., f64::INFINITY).is_none());
+
+        let r = Ray::new(Point3::new(2., 0., 0.), Vec3::new(0., 0., -1.), 1.);
+        let rec = sphere.hit(&r, 0., f64::INFINITY).unwrap();
+        assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_moving_sphere_bounding_box() {
+        let sphere = get_moving_sphere();
+        assert_eq!(
+            *sphere.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-0.5, -0.5, -1.5),
+                maximum: Vec3::new(2.5, 0.5, -0.5)
+            }
+        )
+    }
+}
@@ -0,0 +1,62 @@
+use super::Vec3;
+
+/// An orthonormal basis, with `w` along a given direction.
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: &Vec3) -> Onb {
+        let w = n.unit_vector();
+        // any vector not parallel to w will do to start the cross products
+        let a = if f64::abs(w.x) > 0.9 {
+            Vec3::new(0., 1., 0.)
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let v = Vec3::cross(&w, &a).unit_vector();
+        let u = Vec3::cross(&v, &w);
+        Onb { u, v, w }
+    }
+
+    /// Convert coordinates in this basis to world coordinates.
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x * &self.u + a.y * &self.v + a.z * &self.w
+    }
+
+    /// Convert world coordinates to coordinates in this basis.
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot(a, &self.u),
+            Vec3::dot(a, &self.v),
+            Vec3::dot(a, &self.w),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_onb_from_w() {
+        for n in &[
+            Vec3::new(0., 0., 1.),
+            Vec3::new(1., 0., 0.),
+            Vec3::new(-3., 2., 0.5),
+        ] {
+            let onb = Onb::from_w(n);
+            assert_eq!(onb.w, n.unit_vector());
+            assert!(f64::abs(Vec3::dot(&onb.u, &onb.v)) < Vec3::TOL);
+            assert!(f64::abs(Vec3::dot(&onb.u, &onb.w)) < Vec3::TOL);
+            assert!(f64::abs(Vec3::dot(&onb.v, &onb.w)) < Vec3::TOL);
+            // right-handed
+            assert_eq!(Vec3::cross(&onb.u, &onb.v), onb.w);
+
+            let a = Vec3::new(0.1, -2., 3.);
+            assert_eq!(onb.to_local(&onb.local(&a)), a);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use super::{Point3, Ray, Vec3};
+use crate::hittable::AABB;
+
+/// The plane through `origin` spanned by the edge vectors `u` and `v`. Points on the plane are
+/// `origin + alpha * u + beta * v`, and each planar primitive decides which `(alpha, beta)` lie
+/// inside it.
+pub(super) struct PlanarFrame {
+    pub origin: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub normal: Vec3,
+    d: f64,
+    w: Vec3,
+}
+
+impl PlanarFrame {
+    pub fn new(origin: Point3, u: Vec3, v: Vec3) -> PlanarFrame {
+        let n = Vec3::cross(&u, &v);
+        let normal = n.unit_vector();
+        let d = Vec3::dot(&normal, &origin);
+        let w = &n / Vec3::dot(&n, &n);
+        PlanarFrame {
+            origin,
+            u,
+            v,
+            normal,
+            d,
+            w,
+        }
+    }
+
+    /// Where `ray` crosses the plane, as `(t, p, alpha, beta)`.
+    pub fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Point3, f64, f64)> {
+        let denom = Vec3::dot(&self.normal, &ray.direction);
+
+        // No hit if the ray is parallel to the plane.
+        if f64::abs(denom) < Vec3::TOL {
+            return None;
+        }
+
+        let t = (self.d - Vec3::dot(&self.normal, &ray.origin)) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let planar_hitpt = &p - &self.origin;
+        let alpha = Vec3::dot(&self.w, &Vec3::cross(&planar_hitpt, &self.v));
+        let beta = Vec3::dot(&self.w, &Vec3::cross(&self.u, &planar_hitpt));
+
+        Some((t, p, alpha, beta))
+    }
+
+    pub fn at(&self, alpha: f64, beta: f64) -> Point3 {
+        &self.origin + alpha * &self.u + beta * &self.v
+    }
+}
+
+/// Pad a bounding box so it has some thickness along every axis, since a flat primitive aligned
+/// with an axis would otherwise produce a box with no volume.
+pub(super) fn padded(bbox: AABB) -> AABB {
+    const DELTA: f64 = 0.0001;
+    let mut bbox = bbox;
+    for i in 0..3 {
+        if bbox.maximum[i] - bbox.minimum[i] < DELTA {
+            bbox.minimum[i] -= DELTA / 2.;
+            bbox.maximum[i] += DELTA / 2.;
+        }
+    }
+    bbox
+}
+
+/// The smallest box containing all of `points`.
+pub(super) fn bounding_points(points: &[Point3]) -> AABB {
+    let mut bbox = AABB {
+        minimum: points[0].clone(),
+        maximum: points[0].clone(),
+    };
+    for p in &points[1..] {
+        for i in 0..3 {
+            bbox.minimum[i] = f64::min(bbox.minimum[i], p[i]);
+            bbox.maximum[i] = f64::max(bbox.maximum[i], p[i]);
+        }
+    }
+    padded(bbox)
+}
@@ -0,0 +1,101 @@
+use std::rc::Rc;
+
+use super::{Onb, Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// An infinite plane through `point`, facing along `normal`.
+///
+/// It has no bounding box, so it can't go in a `BVHNode`; put it in a `World` instead, which
+/// keeps unbounded objects out of its BVH. Texture coordinates repeat every unit along two
+/// perpendicular directions in the plane.
+pub struct Plane {
+    point: Point3,
+    onb: Onb,
+    material: Rc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Rc<dyn Material>) -> Plane {
+        Plane {
+            point,
+            onb: Onb::from_w(&normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denom = Vec3::dot(&self.onb.w, &ray.direction);
+        // a ray parallel to the plane never hits it
+        if f64::abs(denom) < 1e-8 {
+            return None;
+        }
+
+        let t = Vec3::dot(&self.onb.w, &(&self.point - &ray.origin)) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let offset = &p - &self.point;
+        Some(HitRecord::new(
+            ray,
+            t,
+            p,
+            self.onb.w.clone(),
+            self.material.clone(),
+            Vec3::dot(&offset, &self.onb.u).rem_euclid(1.),
+            Vec3::dot(&offset, &self.onb.v).rem_euclid(1.),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    fn get_plane() -> Plane {
+        let material = grey();
+        Plane::new(Point3::new(0., -1., 0.), Vec3::new(0., 1., 0.), material)
+    }
+
+    #[test]
+    fn test_plane_hit() {
+        let plane = get_plane();
+
+        let r = Ray::new(Point3::new(100., 1., -50.), Vec3::new(0., -1., 0.), 0.);
+        let rec = plane.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.) < Vec3::TOL);
+        assert_eq!(rec.p, Point3::new(100., -1., -50.));
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+        assert!(rec.front_face);
+        assert!((0. ..1.).contains(&rec.u) && (0. ..1.).contains(&rec.v));
+
+        // from below
+        let r = Ray::new(Point3::new(0., -3., 0.), Vec3::new(1., 1., 0.), 0.);
+        let rec = plane.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.) < Vec3::TOL);
+        assert!(!rec.front_face);
+
+        // parallel, facing away, and out of range
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(1., 0., 1.), 0.);
+        assert!(plane.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.), 0.);
+        assert!(plane.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(plane.hit(&r, 0., 0.5).is_none());
+    }
+
+    #[test]
+    fn test_plane_bounding_box() {
+        assert!(get_plane().bounding_box().is_none());
+    }
+}
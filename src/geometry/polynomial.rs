@@ -0,0 +1,189 @@
+// Real roots of low-order polynomials, with coefficients given from the constant term up, after
+// Jochen Schwarze's "Cubic and Quartic Roots" in Graphics Gems.
+
+use std::f64::consts::PI;
+
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    f64::abs(x) < EPSILON
+}
+
+/// Roots of `c[2] x^2 + c[1] x + c[0]`, in ascending order.
+pub fn solve_quadratic(c: [f64; 3]) -> Vec<f64> {
+    if c[2] == 0. {
+        return if c[1] == 0. {
+            vec![]
+        } else {
+            vec![-c[0] / c[1]]
+        };
+    }
+
+    let p = c[1] / (2. * c[2]);
+    let q = c[0] / c[2];
+    let discriminant = p * p - q;
+
+    if discriminant < 0. {
+        vec![]
+    } else if discriminant == 0. {
+        vec![-p]
+    } else {
+        let sqrt_d = f64::sqrt(discriminant);
+        vec![-p - sqrt_d, -p + sqrt_d]
+    }
+}
+
+/// Roots of `c[3] x^3 + c[2] x^2 + c[1] x + c[0]`, with `c[3]` non-zero.
+pub fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    // normal form: x^3 + Ax^2 + Bx + C = 0
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let c = c[0] / c[3];
+
+    // substitute x = y - A/3 to eliminate the quadric term: y^3 + py + q = 0
+    let sq_a = a * a;
+    let p = (-sq_a / 3. + b) / 3.;
+    let q = (2. / 27. * a * sq_a - a * b / 3. + c) / 2.;
+
+    // use Cardano's formula
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+
+    let mut roots = if is_zero(d) {
+        if is_zero(q) {
+            // one triple solution
+            vec![0.]
+        } else {
+            // one single and one double solution
+            let u = f64::cbrt(-q);
+            vec![2. * u, -u]
+        }
+    } else if d < 0. {
+        // three real solutions
+        let phi = f64::acos(-q / f64::sqrt(-cb_p)) / 3.;
+        let t = 2. * f64::sqrt(-p);
+        vec![
+            t * f64::cos(phi),
+            -t * f64::cos(phi + PI / 3.),
+            -t * f64::cos(phi - PI / 3.),
+        ]
+    } else {
+        // one real solution
+        let sqrt_d = f64::sqrt(d);
+        vec![f64::cbrt(sqrt_d - q) - f64::cbrt(sqrt_d + q)]
+    };
+
+    for root in roots.iter_mut() {
+        *root -= a / 3.;
+    }
+    roots
+}
+
+/// Roots of `c[4] x^4 + c[3] x^3 + c[2] x^2 + c[1] x + c[0]`, with `c[4]` non-zero, refined with
+/// a couple of Newton steps since Ferrari's method loses some precision along the way.
+pub fn solve_quartic(coeffs: [f64; 5]) -> Vec<f64> {
+    // normal form: x^4 + Ax^3 + Bx^2 + Cx + D = 0
+    let a = coeffs[3] / coeffs[4];
+    let b = coeffs[2] / coeffs[4];
+    let c = coeffs[1] / coeffs[4];
+    let d = coeffs[0] / coeffs[4];
+
+    // substitute x = y - A/4 to eliminate the cubic term: y^4 + py^2 + qy + r = 0
+    let sq_a = a * a;
+    let p = -3. / 8. * sq_a + b;
+    let q = sq_a * a / 8. - a * b / 2. + c;
+    let r = -3. / 256. * sq_a * sq_a + sq_a * b / 16. - a * c / 4. + d;
+
+    let mut roots = if is_zero(r) {
+        // no absolute term: y(y^3 + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0., 1.]);
+        roots.push(0.);
+        roots
+    } else {
+        // solve the resolvent cubic, and use one of its roots to split the quartic into two
+        // quadratics
+        let z = solve_cubic([r * p / 2. - q * q / 8., -r, -p / 2., 1.])[0];
+
+        let mut u = z * z - r;
+        let mut v = 2. * z - p;
+        if is_zero(u) {
+            u = 0.;
+        } else if u > 0. {
+            u = f64::sqrt(u);
+        } else {
+            return vec![];
+        }
+        if is_zero(v) {
+            v = 0.;
+        } else if v > 0. {
+            v = f64::sqrt(v);
+        } else {
+            return vec![];
+        }
+
+        let mut roots = solve_quadratic([z - u, if q < 0. { -v } else { v }, 1.]);
+        roots.extend(solve_quadratic([z + u, if q < 0. { v } else { -v }, 1.]));
+        roots
+    };
+
+    let eval = |x: f64| (((x + a) * x + b) * x + c) * x + d;
+    let derivative = |x: f64| ((4. * x + 3. * a) * x + 2. * b) * x + c;
+    for root in roots.iter_mut() {
+        *root -= a / 4.;
+        for _ in 0..2 {
+            let slope = derivative(*root);
+            if slope != 0. {
+                *root -= eval(*root) / slope;
+            }
+        }
+    }
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_roots(mut actual: Vec<f64>, expected: &[f64]) {
+        actual.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(f64::abs(a - e) < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_solve_quadratic() {
+        // (x - 1)(x + 2)
+        assert_roots(solve_quadratic([-2., 1., 1.]), &[-2., 1.]);
+        assert_roots(solve_quadratic([1., 0., 1.]), &[]);
+        assert_roots(solve_quadratic([-4., 2., 0.]), &[2.]);
+    }
+
+    #[test]
+    fn test_solve_cubic() {
+        // (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_cubic([-6., 11., -6., 1.]), &[1., 2., 3.]);
+        // (x - 1)(x^2 + 1)
+        assert_roots(solve_cubic([-1., 1., -1., 1.]), &[1.]);
+    }
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(solve_quartic([24., -50., 35., -10., 1.]), &[1., 2., 3., 4.]);
+        // 2(x + 0.5)(x - 1.5)(x^2 + 1)
+        assert_roots(solve_quartic([-1.5, -2., 0.5, -2., 2.]), &[-0.5, 1.5]);
+        // x^4 + 1
+        assert_roots(solve_quartic([1., 0., 0., 0., 1.]), &[]);
+        // x(x - 1)(x + 2)(x - 3)
+        assert_roots(solve_quartic([0., 6., -5., -2., 1.]), &[-2., 0., 1., 3.]);
+    }
+}
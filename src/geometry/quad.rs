@@ -0,0 +1,103 @@
+use std::rc::Rc;
+
+use super::planar::{bounding_points, PlanarFrame};
+use super::{Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A parallelogram with one corner at `q` and edges `u` and `v`, which may point in any
+/// direction.
+pub struct Quad {
+    frame: PlanarFrame,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Rc<dyn Material>) -> Quad {
+        let frame = PlanarFrame::new(q, u, v);
+        let bbox = bounding_points(&[
+            frame.at(0., 0.),
+            frame.at(1., 0.),
+            frame.at(0., 1.),
+            frame.at(1., 1.),
+        ]);
+        Quad {
+            frame,
+            material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, p, alpha, beta) = self.frame.intersect(ray, t_min, t_max)?;
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            ray,
+            t,
+            p,
+            self.frame.normal.clone(),
+            self.material.clone(),
+            alpha,
+            beta,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    fn get_quad() -> Quad {
+        let material = grey();
+        // a 2x1 rectangle tilted 45 degrees away from the viewer, about the x-axis
+        Quad::new(
+            Point3::new(-1., 0., -1.),
+            Vec3::new(2., 0., 0.),
+            Vec3::new(0., 1., -1.),
+            material,
+        )
+    }
+
+    #[test]
+    fn test_quad_hit() {
+        let quad = get_quad();
+
+        let r = Ray::new(Point3::new(0.5, 0.5, 0.), Vec3::new(0., 0., -1.), 0.);
+        let rec = quad.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.5) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 1.).unit_vector());
+        assert!(f64::abs(rec.u - 0.75) < Vec3::TOL);
+        assert!(f64::abs(rec.v - 0.5) < Vec3::TOL);
+
+        let r = Ray::new(Point3::new(1.5, 0.5, 0.), Vec3::new(0., 0., -1.), 0.);
+        assert!(quad.hit(&r, 0., f64::INFINITY).is_none());
+
+        // parallel to the plane
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(quad.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_quad_bounding_box() {
+        let quad = get_quad();
+        assert_eq!(
+            *quad.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-1., 0., -2.),
+                maximum: Vec3::new(1., 1., -1.),
+            }
+        );
+    }
+}
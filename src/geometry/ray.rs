@@ -1,4 +1,4 @@
-use super::{Point3, Vec3};
+use super::{Atmosphere, Point3, RandomVectorType, Vec3};
 
 use crate::color::Color;
 use crate::hittable::Hittable;
@@ -7,30 +7,54 @@ use crate::hittable::Hittable;
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    pub time: f64,
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn at(&self, t: f64) -> Point3 {
         &self.origin + t * &self.direction
     }
 
-    pub fn color(&self, world: &dyn Hittable, depth: i32, background: &Color) -> Color {
+    /// The light arriving back along this ray, having passed through the `atmosphere` filling
+    /// the scene, if there is one.
+    pub fn color(
+        &self,
+        world: &dyn Hittable,
+        depth: i32,
+        background: &Color,
+        atmosphere: Option<&Atmosphere>,
+    ) -> Color {
         // If we've exceeded the ray bounce lmit, no more light is gathered.
         if depth <= 0 {
             return Color::new(0., 0., 0.);
         }
 
-        match world.hit(&self, 0.001, std::f64::INFINITY) {
+        let hit = world.hit(&self, 0.001, f64::INFINITY);
+
+        // The atmosphere may absorb or scatter the ray before it gets to whatever it hits.
+        if let Some(atmosphere) = atmosphere {
+            let t_max = hit.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+            if let Some(scattered) = atmosphere.interact(self, t_max) {
+                return atmosphere.albedo()
+                    * scattered.color(world, depth - 1, background, Some(atmosphere));
+            }
+        }
+
+        match hit {
             Some(rec) => match rec.material_rc.scatter(&self, &rec) {
                 Some(scatter) => {
                     let emitted = rec.material_rc.emit(rec.u, rec.v, &rec.p);
                     return emitted
                         + scatter.attenuation.value(rec.u, rec.v, &rec.p)
-                            * scatter.ray.color(world, depth - 1, background);
+                            * scatter.ray.color(world, depth - 1, background, atmosphere);
                 }
                 None => {
                     return rec.material_rc.emit(rec.u, rec.v, &rec.p);
@@ -40,6 +64,28 @@ impl Ray {
             None => return background.clone(),
         }
     }
+
+    /// How open to the sky the surface this ray hits is, from one random probe: white if a ray
+    /// leaving it in a cosine-weighted direction about the normal gets `distance` away without
+    /// hitting anything, and black if not. Averaged over many samples, this gives a grey-scale
+    /// image of the scene's shape lit evenly from all around. Rays that hit nothing are white.
+    pub fn ambient_occlusion(&self, world: &dyn Hittable, distance: f64) -> Color {
+        let rec = match world.hit(self, 0.001, f64::INFINITY) {
+            Some(rec) => rec,
+            None => return Color::new(1., 1., 1.),
+        };
+
+        let mut direction = &rec.normal + Vec3::random(RandomVectorType::Unit);
+        if direction.near_zero() {
+            direction = rec.normal.clone();
+        }
+        let probe = Ray::new(rec.p, direction, self.time);
+        if world.occluded(&probe, 0.001, distance / probe.direction.length()) {
+            Color::new(0., 0., 0.)
+        } else {
+            Color::new(1., 1., 1.)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -48,26 +94,28 @@ mod test {
 
     use super::*;
 
-    use crate::geometry::Sphere;
-    use crate::hittable::HitRecord;
-    use crate::material::types::Lambertian;
+    use crate::geometry::{Plane, Sphere};
+    use crate::hittable::test_util::grey;
+    use crate::hittable::{HitRecord, HittableList};
+    use crate::material::types::{DiffuseLight, Lambertian, PhaseFunction};
     use crate::texture::SolidColor;
 
     #[test]
     fn test_ray_new() {
         let p = Point3::new(1.1, 2.2, 3.3);
         let v = Vec3::new(4.4, 5.5, 6.6);
-        let r = Ray::new(p, v);
+        let r = Ray::new(p, v, 0.5);
 
         assert_eq!(r.origin, Vec3::new(1.1, 2.2, 3.3));
         assert_eq!(r.direction, Vec3::new(4.4, 5.5, 6.6));
+        assert_eq!(r.time, 0.5);
     }
 
     #[test]
     fn test_ray_at() {
         let p = Point3::new(0.0, 0.0, 0.0);
         let v = Vec3::new(2.0, 4.0, 8.0);
-        let r = Ray::new(p, v);
+        let r = Ray::new(p, v, 0.);
 
         let point3_at = r.at(3.0);
         assert_eq!(point3_at, Point3::new(6.0, 12.0, 24.0));
@@ -85,17 +133,40 @@ mod test {
         let depth = 10;
         let background = Color::new(0.5, 0.7, 1.0);
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, -1.0, 0.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, -1.0, 0.0), 0.);
         assert!(sphere.hit(&r, 0.001, std::f64::INFINITY).is_none());
-        let c_bg = r.color(&sphere, depth, &background);
+        let c_bg = r.color(&sphere, depth, &background, None);
         assert_eq!(c_bg, background);
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         assert!(sphere.hit(&r, 0.001, std::f64::INFINITY).is_some());
-        let c_fg = r.color(&sphere, depth, &background);
+        let c_fg = r.color(&sphere, depth, &background, None);
         assert_eq!(c_fg, Color::new(0.25, 0.35, 0.5));
     }
 
+    #[test]
+    fn test_ray_color_atmosphere() {
+        let light = Rc::new(DiffuseLight::new(Color::new(1., 1., 1.)));
+        let sphere = Sphere::new(Point3::new(0., 0., -3.), 1., light);
+        let background = Color::new(0., 0., 0.);
+
+        // light from the sphere, 2 away, has to get through a purely absorbing atmosphere
+        let atmosphere = Atmosphere::new(0.5, 0., PhaseFunction::Isotropic);
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.), 0.);
+        let n = 20000;
+        let mut total = Color::new(0., 0., 0.);
+        for _ in 0..n {
+            total += r.color(&sphere, 10, &background, Some(&atmosphere));
+        }
+        assert!(f64::abs(total.luminance() / n as f64 - f64::exp(-1.)) < 0.015);
+
+        // without it all the light gets through
+        assert_eq!(
+            r.color(&sphere, 10, &background, None),
+            Color::new(1., 1., 1.)
+        );
+    }
+
     #[test]
     fn test_ray_hit_sphere() {
         let mut rec: Option<HitRecord>;
@@ -110,14 +181,49 @@ mod test {
         let t_min = 0.;
         let t_max = std::f64::INFINITY;
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         rec = sphere.hit(&r, t_min, t_max);
         let hit = rec.is_some();
         assert!(hit);
 
-        let r = Ray::new(origin.clone(), Vec3::new(1.0, 1.0, 1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(1.0, 1.0, 1.0), 0.);
         rec = sphere.hit(&r, t_min, t_max);
         let miss = rec.is_none();
         assert!(miss);
     }
+
+    #[test]
+    fn test_ray_ambient_occlusion() {
+        let material_rc = grey();
+        let mut world = HittableList::new();
+        world.add(Box::new(Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            material_rc.clone(),
+        )));
+        world.add(Box::new(Sphere::new(
+            Point3::new(0., 1.1, 0.),
+            1.,
+            material_rc,
+        )));
+
+        let openness = |x: f64| {
+            let r = Ray::new(Point3::new(x, 5., 0.5), Vec3::new(0., -1., 0.), 0.);
+            let n = 2000;
+            (0..n)
+                .map(|_| r.ambient_occlusion(&world, 2.).luminance())
+                .sum::<f64>()
+                / n as f64
+        };
+
+        // the top of the sphere sees all of the sky, and the ground far from it nearly all, but
+        // the ground tucked in under it much less
+        assert_eq!(openness(0.), 1.);
+        assert!(openness(20.) > 0.99);
+        assert!(openness(1.) < 0.8);
+
+        // and a ray that misses everything is open
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., 1., 0.), 0.);
+        assert_eq!(r.ambient_occlusion(&world, 2.), Color::new(1., 1., 1.));
+    }
 }
@@ -0,0 +1,134 @@
+use super::Sdf;
+use crate::geometry::{Point3, Vec3};
+
+/// The union of several fields, with the seams between them blended over a distance of about
+/// `smoothness`, like metaballs. It uses the polynomial smooth minimum, which never overestimates
+/// the distance.
+pub struct SmoothUnion {
+    pub sdfs: Vec<Box<dyn Sdf>>,
+    pub smoothness: f64,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &Point3) -> f64 {
+        let k = self.smoothness;
+        self.sdfs
+            .iter()
+            .map(|sdf| sdf.distance(p))
+            .fold(f64::INFINITY, |a, b| {
+                if k <= 0. || a == f64::INFINITY {
+                    return f64::min(a, b);
+                }
+                let h = f64::max(k - f64::abs(a - b), 0.) / k;
+                f64::min(a, b) - h * h * k / 4.
+            })
+    }
+}
+
+/// Copies of `sdf` repeated forever with the given `period` along each axis, centered on the
+/// origin. A period of zero leaves that axis alone. The copies must fit in their cells, or the
+/// distance will be overestimated where they cross into the next one.
+pub struct Repeat {
+    pub sdf: Box<dyn Sdf>,
+    pub period: Vec3,
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: &Point3) -> f64 {
+        let mut q = p.clone();
+        for i in 0..3 {
+            let period = self.period[i];
+            if period > 0. {
+                q[i] -= period * f64::round(q[i] / period);
+            }
+        }
+        self.sdf.distance(&q)
+    }
+}
+
+/// `sdf` twisted about the y-axis by `rate` radians per unit of height.
+///
+/// Twisting stretches space more the further it is from the axis, so the distance is scaled down
+/// by how much the twist stretches it at that point.
+pub struct Twist {
+    pub sdf: Box<dyn Sdf>,
+    pub rate: f64,
+}
+
+impl Sdf for Twist {
+    fn distance(&self, p: &Point3) -> f64 {
+        let angle = self.rate * p.y;
+        let (sin, cos) = (f64::sin(angle), f64::cos(angle));
+        let q = Point3::new(cos * p.x + sin * p.z, p.y, -sin * p.x + cos * p.z);
+
+        let stretch = self.rate * f64::sqrt(p.x * p.x + p.z * p.z);
+        self.sdf.distance(&q) / f64::sqrt(1. + stretch * stretch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::sdf::{RoundBox, Sphere};
+
+    fn unit_sphere(x: f64) -> Box<dyn Sdf> {
+        Box::new(Sphere {
+            center: Point3::new(x, 0., 0.),
+            radius: 1.,
+        })
+    }
+
+    #[test]
+    fn test_smooth_union_distance() {
+        let hard = SmoothUnion {
+            sdfs: vec![unit_sphere(-1.5), unit_sphere(1.5)],
+            smoothness: 0.,
+        };
+        let soft = SmoothUnion {
+            sdfs: vec![unit_sphere(-1.5), unit_sphere(1.5)],
+            smoothness: 1.,
+        };
+
+        // between the spheres, the blend pulls the surface in towards the middle
+        let middle = Point3::new(0., 0., 0.);
+        assert!(f64::abs(hard.distance(&middle) - 0.5) < Vec3::TOL);
+        assert!(f64::abs(soft.distance(&middle) - 0.25) < Vec3::TOL);
+
+        // far from the seam, it's a plain union
+        let p = Point3::new(3.5, 0., 0.);
+        assert!(f64::abs(soft.distance(&p) - 1.) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_repeat_distance() {
+        let grid = Repeat {
+            sdf: Box::new(Sphere {
+                center: Point3::new(0., 0., 0.),
+                radius: 0.5,
+            }),
+            period: Vec3::new(2., 0., 2.),
+        };
+        assert!(f64::abs(grid.distance(&Point3::new(4., 0., -6.)) + 0.5) < Vec3::TOL);
+        assert!(f64::abs(grid.distance(&Point3::new(4.5, 0., 0.))) < Vec3::TOL);
+        // not repeated along y
+        assert!(f64::abs(grid.distance(&Point3::new(0., 4., 0.)) - 3.5) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_twist_distance() {
+        let twisted = Twist {
+            sdf: Box::new(RoundBox {
+                center: Point3::new(0., 0., 0.),
+                half_extents: Vec3::new(1., 2., 0.2),
+                radius: 0.,
+            }),
+            rate: std::f64::consts::FRAC_PI_4,
+        };
+
+        // unchanged at y = 0, and a quarter of the way around at y = 2
+        assert!(twisted.distance(&Point3::new(0.9, 0., 0.)) < 0.);
+        assert!(twisted.distance(&Point3::new(0.9, 1.99, 0.)) > 0.);
+        assert!(twisted.distance(&Point3::new(0., 1.99, 0.9)) < 0.);
+    }
+}
@@ -0,0 +1,65 @@
+use super::Sdf;
+use crate::geometry::{Point3, Vec3};
+
+/// The Mandelbulb fractal, with the usual `power` of 8 and 8 to 12 `iterations` of detail. It's
+/// centered on the origin and fits in a radius of about 1.2, so place it with the hittable
+/// instances.
+///
+/// The distance is estimated from the derivative of the iteration, the way it is for the 2D
+/// Mandelbrot set.
+pub struct Mandelbulb {
+    pub power: f64,
+    pub iterations: u32,
+}
+
+impl Sdf for Mandelbulb {
+    fn distance(&self, p: &Point3) -> f64 {
+        let n = self.power;
+        let mut z = p.clone();
+        let mut dr = 1.;
+        let mut r = z.length();
+
+        for _ in 0..self.iterations {
+            if r > 2. || r == 0. {
+                break;
+            }
+
+            // raise z to the nth power in spherical coordinates, and add p
+            let theta = f64::acos(z.z / r) * n;
+            let phi = f64::atan2(z.y, z.x) * n;
+            dr = r.powf(n - 1.) * n * dr + 1.;
+            let zr = r.powf(n);
+            z =
+                zr * Vec3::new(
+                    f64::sin(theta) * f64::cos(phi),
+                    f64::sin(theta) * f64::sin(phi),
+                    f64::cos(theta),
+                ) + p;
+            r = z.length();
+        }
+
+        if r == 0. {
+            return 0.;
+        }
+        0.5 * f64::ln(r) * r / dr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mandelbulb_distance() {
+        let bulb = Mandelbulb {
+            power: 8.,
+            iterations: 10,
+        };
+
+        // well outside, the estimate is safely positive
+        assert!(bulb.distance(&Point3::new(3., 0., 0.)) > 1.);
+
+        // near the center, the point stays bounded and is inside
+        assert!(bulb.distance(&Point3::new(0.1, 0.1, 0.1)) <= 0.);
+    }
+}
@@ -0,0 +1,21 @@
+use super::Point3;
+
+mod combinators;
+pub use combinators::{Repeat, SmoothUnion, Twist};
+
+mod mandelbulb;
+pub use mandelbulb::Mandelbulb;
+
+mod object;
+pub use object::SdfObject;
+
+mod primitives;
+pub use primitives::{RoundBox, Sphere, Torus};
+
+/// A signed distance field: the distance from a point to the nearest surface, negative inside.
+///
+/// The distance may be an underestimate, which only makes sphere tracing take more steps, but it
+/// must never be an overestimate, or the tracing can step right through a surface.
+pub trait Sdf {
+    fn distance(&self, p: &Point3) -> f64;
+}
@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use super::Sdf;
+use crate::geometry::{Ray, Sphere, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+// how close to the surface counts as a hit
+const EPSILON: f64 = 0.0001;
+const MAX_STEPS: u32 = 512;
+
+/// An object whose surface is where `sdf` is zero, found by sphere tracing: stepping along the
+/// ray by the distance to the nearest surface until it's close enough.
+///
+/// The tracing only happens inside `bbox`, which must contain the whole surface. Normals are the
+/// gradient of the field, estimated from four nearby samples, and texture coordinates are
+/// spherical, from the normal.
+pub struct SdfObject {
+    sdf: Box<dyn Sdf>,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl SdfObject {
+    pub fn new(sdf: Box<dyn Sdf>, bbox: AABB, material: Rc<dyn Material>) -> SdfObject {
+        SdfObject {
+            sdf,
+            material,
+            bbox,
+        }
+    }
+
+    fn normal(&self, p: &Vec3) -> Vec3 {
+        // the tetrahedron technique: the gradient from the corners of a small tetrahedron
+        let h = EPSILON / 2.;
+        let corners = [
+            Vec3::new(1., -1., -1.),
+            Vec3::new(-1., -1., 1.),
+            Vec3::new(-1., 1., -1.),
+            Vec3::new(1., 1., 1.),
+        ];
+        let mut gradient = Vec3::new(0., 0., 0.);
+        for corner in &corners {
+            gradient = gradient + self.sdf.distance(&(p + h * corner)) * corner;
+        }
+        gradient.unit_vector()
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_start, t_end) = self.bbox.intersect(ray, t_min, t_max)?;
+        let inv_length = 1. / ray.direction.length();
+
+        // Trace from whichever side of the surface the ray starts on. A ray leaving the surface
+        // starts right on it, so its side is decided a little further along.
+        let mut t = t_start;
+        let mut side = self.sdf.distance(&ray.at(t));
+        if f64::abs(side) < EPSILON {
+            side = self.sdf.distance(&ray.at(t + 2. * EPSILON * inv_length));
+        }
+        let side = if side < 0. { -1. } else { 1. };
+
+        for _ in 0..MAX_STEPS {
+            let distance = side * self.sdf.distance(&ray.at(t));
+            if distance < EPSILON {
+                let p = ray.at(t);
+                let normal = self.normal(&p);
+                let (u, v) = Sphere::get_sphere_uv(&normal);
+                return Some(HitRecord::new(
+                    ray,
+                    t,
+                    p,
+                    normal,
+                    self.material.clone(),
+                    u,
+                    v,
+                ));
+            }
+            t += distance * inv_length;
+            if t > t_end {
+                return None;
+            }
+        }
+
+        // gave up, probably grazing the surface
+        None
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::sdf;
+    use crate::geometry::Point3;
+    use crate::hittable::test_util::grey;
+
+    fn get_sphere() -> SdfObject {
+        let material = grey();
+        SdfObject::new(
+            Box::new(sdf::Sphere {
+                center: Point3::new(0., 0., 0.),
+                radius: 1.,
+            }),
+            AABB {
+                minimum: Point3::new(-1., -1., -1.),
+                maximum: Point3::new(1., 1., 1.),
+            },
+            material,
+        )
+    }
+
+    #[test]
+    fn test_sdf_object_hit() {
+        let sphere = get_sphere();
+
+        let r = Ray::new(Point3::new(0., 0.5, -5.), Vec3::new(0., 0., 2.), 0.);
+        let rec = sphere.hit(&r, 0., f64::INFINITY).unwrap();
+        let expected_t = (5. - f64::sqrt(0.75)) / 2.;
+        assert!(f64::abs(rec.t - expected_t) < EPSILON);
+        assert!(rec.front_face);
+        let expected_normal = Vec3::new(0., 0.5, -f64::sqrt(0.75));
+        assert!((&rec.normal - &expected_normal).length() < 0.001);
+
+        // starting on the surface and going in, the ray leaves through the other side
+        let r = Ray::new(rec.p.clone(), Vec3::new(0., 0., 1.), 0.);
+        let rec = sphere.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2. * f64::sqrt(0.75)) < EPSILON);
+        assert!(!rec.front_face);
+
+        let r = Ray::new(Point3::new(0., 1.5, -5.), Vec3::new(0., 0., 1.), 0.);
+        assert!(sphere.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.), 0.);
+        assert!(sphere.hit(&r, 0., 3.).is_none());
+    }
+}
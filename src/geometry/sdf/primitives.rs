@@ -0,0 +1,91 @@
+use super::Sdf;
+use crate::geometry::{Point3, Vec3};
+
+pub struct Sphere {
+    pub center: Point3,
+    pub radius: f64,
+}
+
+impl Sdf for Sphere {
+    fn distance(&self, p: &Point3) -> f64 {
+        (p - &self.center).length() - self.radius
+    }
+}
+
+/// A box with its edges and corners rounded off by `radius`, on top of `half_extents`.
+pub struct RoundBox {
+    pub center: Point3,
+    pub half_extents: Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for RoundBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let p = p - &self.center;
+        let q = Vec3::new(
+            f64::abs(p.x) - self.half_extents.x,
+            f64::abs(p.y) - self.half_extents.y,
+            f64::abs(p.z) - self.half_extents.z,
+        );
+        let outside = Vec3::new(f64::max(q.x, 0.), f64::max(q.y, 0.), f64::max(q.z, 0.));
+        let inside = f64::min(f64::max(q.x, f64::max(q.y, q.z)), 0.);
+        outside.length() + inside - self.radius
+    }
+}
+
+/// A torus lying flat in the xz-plane.
+pub struct Torus {
+    pub center: Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Sdf for Torus {
+    fn distance(&self, p: &Point3) -> f64 {
+        let p = p - &self.center;
+        let ring = f64::sqrt(p.x * p.x + p.z * p.z) - self.major_radius;
+        f64::sqrt(ring * ring + p.y * p.y) - self.minor_radius
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sphere_distance() {
+        let sphere = Sphere {
+            center: Point3::new(1., 0., 0.),
+            radius: 0.5,
+        };
+        assert_eq!(sphere.distance(&Point3::new(3., 0., 0.)), 1.5);
+        assert_eq!(sphere.distance(&Point3::new(1., 0., 0.)), -0.5);
+    }
+
+    #[test]
+    fn test_round_box_distance() {
+        let round_box = RoundBox {
+            center: Point3::new(0., 0., 0.),
+            half_extents: Vec3::new(1., 2., 3.),
+            radius: 0.5,
+        };
+        assert!(f64::abs(round_box.distance(&Point3::new(2., 0., 0.)) - 0.5) < Vec3::TOL);
+        assert!(f64::abs(round_box.distance(&Point3::new(0., 0., 0.)) + 1.5) < Vec3::TOL);
+        // off the corner, the distance is to the rounded corner
+        let corner = Point3::new(2., 3., 4.);
+        let expected = f64::sqrt(3.) - 0.5;
+        assert!(f64::abs(round_box.distance(&corner) - expected) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_torus_distance() {
+        let torus = Torus {
+            center: Point3::new(0., 1., 0.),
+            major_radius: 2.,
+            minor_radius: 0.5,
+        };
+        assert!(f64::abs(torus.distance(&Point3::new(0., 1., 0.)) - 1.5) < Vec3::TOL);
+        assert!(f64::abs(torus.distance(&Point3::new(0., 1., 2.)) + 0.5) < Vec3::TOL);
+        assert!(f64::abs(torus.distance(&Point3::new(2., 2., 0.)) - 0.5) < Vec3::TOL);
+    }
+}
@@ -27,7 +27,7 @@ impl Sphere {
         }
     }
 
-    fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+    pub(super) fn get_sphere_uv(p: &Point3) -> (f64, f64) {
         let theta = f64::acos(-p.y);
         let phi = f64::atan2(-p.z, p.x) + PI;
 
@@ -99,12 +99,12 @@ mod test {
         let t_min = 0.;
         let t_max = std::f64::INFINITY;
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         rec = sphere.hit(&r, t_min, t_max);
         let hit = rec.is_some();
         assert!(hit);
 
-        let r = Ray::new(origin.clone(), Vec3::new(1.0, 1.0, 1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(1.0, 1.0, 1.0), 0.);
         rec = sphere.hit(&r, t_min, t_max);
         let miss = rec.is_none();
         assert!(miss);
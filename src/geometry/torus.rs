@@ -0,0 +1,162 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use super::cone::azimuth;
+use super::disk::circle_bbox;
+use super::polynomial::solve_quartic;
+use super::{Onb, Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A ring torus around `center`, with its hole along `axis`. The tube of radius `minor_radius`
+/// sweeps a circle of radius `major_radius`.
+///
+/// `u` goes around the axis and `v` goes around the tube, starting on the inside of the ring.
+pub struct Torus {
+    center: Point3,
+    onb: Onb,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Rc<dyn Material>,
+    ) -> Torus {
+        assert!(0. < minor_radius && minor_radius < major_radius);
+        let onb = Onb::from_w(&axis);
+
+        // the swept circle's bounds, grown by the tube in every direction
+        let mut bbox = circle_bbox(&center, &onb.w, major_radius);
+        for i in 0..3 {
+            bbox.minimum[i] -= minor_radius;
+            bbox.maximum[i] += minor_radius;
+        }
+
+        Torus {
+            center,
+            onb,
+            major_radius,
+            minor_radius,
+            material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let o = self.onb.to_local(&(&ray.origin - &self.center));
+        let d = self.onb.to_local(&ray.direction);
+
+        // Solve along a unit direction, starting from the point of closest approach to the
+        // center. The quartic's coefficients lose a lot of precision when its roots are far from
+        // zero, which they are for rays starting far away.
+        let len = d.length();
+        let d = d / len;
+        let s0 = -Vec3::dot(&o, &d);
+        let o = &o + s0 * &d;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2)
+        let rr = self.major_radius * self.major_radius;
+        let n = Vec3::dot(&o, &d);
+        let k = o.length_squared() + rr - self.minor_radius * self.minor_radius;
+        let roots = solve_quartic([
+            k * k - 4. * rr * (o.x * o.x + o.y * o.y),
+            4. * n * k - 8. * rr * (o.x * d.x + o.y * d.y),
+            4. * n * n + 2. * k - 4. * rr * (d.x * d.x + d.y * d.y),
+            4. * n,
+            1.,
+        ]);
+        let s = roots.into_iter().find(|s| {
+            let t = (s0 + s) / len;
+            t_min <= t && t <= t_max
+        })?;
+        let t = (s0 + s) / len;
+
+        // the normal points away from the nearest point on the tube's center circle
+        let p = &o + s * &d;
+        let rho = f64::sqrt(p.x * p.x + p.y * p.y);
+        let ring = Vec3::new(p.x, p.y, 0.) * (self.major_radius / rho);
+        let normal = (&p - &ring) / self.minor_radius;
+        let v =
+            (f64::atan2(normal.z, -Vec3::dot(&normal, &ring) / self.major_radius) + PI) / (2. * PI);
+
+        Some(HitRecord::new(
+            ray,
+            t,
+            ray.at(t),
+            self.onb.local(&normal),
+            self.material.clone(),
+            azimuth(&p),
+            v,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::hittable::test_util::grey;
+
+    fn get_torus() -> Torus {
+        let material = grey();
+        Torus::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            2.,
+            0.5,
+            material,
+        )
+    }
+
+    #[test]
+    fn test_torus_hit() {
+        let torus = get_torus();
+
+        // across the ring, through the hole
+        let r = Ray::new(Point3::new(10., 0., 0.), Vec3::new(-1., 0., 0.), 0.);
+        let rec = torus.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 7.5) < 1e-6);
+        assert_eq!(rec.normal, Vec3::new(1., 0., 0.));
+
+        // the far side of the tube, from inside the hole
+        let rec = torus.hit(&r, 9., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 11.5) < 1e-6);
+        assert!(rec.front_face);
+
+        // from inside the tube
+        let r = Ray::new(Point3::new(0., 0., 2.), Vec3::new(0., 2., 0.), 0.);
+        let rec = torus.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 0.25) < 1e-6);
+        assert!(!rec.front_face);
+
+        // straight down the hole, and past the outside
+        let r = Ray::new(Point3::new(0., 10., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(torus.hit(&r, 0., f64::INFINITY).is_none());
+        let r = Ray::new(Point3::new(10., 0., 2.6), Vec3::new(-1., 0., 0.), 0.);
+        assert!(torus.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_torus_bounding_box() {
+        assert_eq!(
+            *get_torus().bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-2.5, -0.5, -2.5),
+                maximum: Vec3::new(2.5, 0.5, 2.5),
+            }
+        );
+    }
+}
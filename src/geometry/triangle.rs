@@ -0,0 +1,95 @@
+use std::rc::Rc;
+
+use super::planar::{bounding_points, PlanarFrame};
+use super::{Point3, Ray};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A triangle with vertices `a`, `b` and `c`. The outward normal follows the right-hand rule
+/// around `a -> b -> c`, and the texture coordinates are the barycentric weights of `b` and `c`.
+pub struct Triangle {
+    frame: PlanarFrame,
+    material: Rc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, material: Rc<dyn Material>) -> Triangle {
+        let bbox = bounding_points(&[a.clone(), b.clone(), c.clone()]);
+        let u = &b - &a;
+        let v = &c - &a;
+        Triangle {
+            frame: PlanarFrame::new(a, u, v),
+            material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, p, alpha, beta) = self.frame.intersect(ray, t_min, t_max)?;
+        if alpha < 0. || beta < 0. || alpha + beta > 1. {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            ray,
+            t,
+            p,
+            self.frame.normal.clone(),
+            self.material.clone(),
+            alpha,
+            beta,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Vec3;
+    use crate::hittable::test_util::grey;
+
+    fn get_triangle() -> Triangle {
+        let material = grey();
+        Triangle::new(
+            Point3::new(0., 0., -1.),
+            Point3::new(2., 0., -1.),
+            Point3::new(0., 2., -3.),
+            material,
+        )
+    }
+
+    #[test]
+    fn test_triangle_hit() {
+        let triangle = get_triangle();
+
+        let r = Ray::new(Point3::new(0.5, 0.5, 0.), Vec3::new(0., 0., -1.), 0.);
+        let rec = triangle.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.5) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 1.).unit_vector());
+        assert!(f64::abs(rec.u - 0.25) < Vec3::TOL);
+        assert!(f64::abs(rec.v - 0.25) < Vec3::TOL);
+
+        // inside the bounding parallelogram, but past the hypotenuse
+        let r = Ray::new(Point3::new(1.5, 1., 0.), Vec3::new(0., 0., -1.), 0.);
+        assert!(triangle.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_triangle_bounding_box() {
+        assert_eq!(
+            *get_triangle().bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(0., 0., -3.),
+                maximum: Vec3::new(2., 2., -1.),
+            }
+        );
+    }
+}
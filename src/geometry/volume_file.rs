@@ -0,0 +1,244 @@
+//! A simple file format for dense voxel grids, like the output of a smoke or fire simulation.
+//!
+//! Everything is little-endian:
+//!
+//! | offset | size         | contents                                                     |
+//! |--------|--------------|--------------------------------------------------------------|
+//! | 0      | 4            | the magic bytes `RTVX`                                       |
+//! | 4      | 4            | the version, a `u32`, which is 1                             |
+//! | 8      | 12           | the number of samples along x, y and z, `u32`s of at least 2 |
+//! | 20     | 4            | the number of channels, a `u32` of at most 64                |
+//! | 24     | 24           | the bounds, `f32`s: minimum x, y and z, then maximum x, y, z |
+//! | 48     | 4·n·channels | the n samples of each channel, `f32`s, one after another     |
+//!
+//! Within a channel, x varies fastest, then y, then z, and the samples are spread over the bounds
+//! the same way as in a `DensityGrid`. By convention, channel 0 is the density and channel 1, if
+//! there is one, the temperature in kelvin.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use super::{DensityGrid, Point3};
+use crate::hittable::AABB;
+
+const MAGIC: &[u8; 4] = b"RTVX";
+const VERSION: u32 = 1;
+/// The most channels a file can have, well beyond the density, temperature and the like that
+/// any simulation writes.
+const MAX_CHANNELS: usize = 64;
+
+/// The contents of a volume file, with each channel in the order it is stored.
+pub struct VolumeFile {
+    pub dims: [usize; 3],
+    pub bounds: AABB,
+    pub channels: Vec<Vec<f64>>,
+}
+
+impl VolumeFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<VolumeFile> {
+        VolumeFile::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<VolumeFile> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a volume file"));
+        }
+        let version = read_u32(reader)?;
+        if version != VERSION {
+            return Err(invalid_data(&format!("unsupported version {}", version)));
+        }
+
+        let mut dims = [0; 3];
+        for dim in dims.iter_mut() {
+            *dim = read_u32(reader)? as usize;
+            if *dim < 2 {
+                return Err(invalid_data("fewer than 2 samples along an axis"));
+            }
+        }
+        let channel_count = read_u32(reader)? as usize;
+        if channel_count > MAX_CHANNELS {
+            return Err(invalid_data(&format!(
+                "{} channels, more than the {} allowed",
+                channel_count, MAX_CHANNELS
+            )));
+        }
+        // the size of a channel and of all of them together, in bytes, which mustn't overflow
+        let channel_size = dims
+            .iter()
+            .try_fold(4usize, |size, &dim| size.checked_mul(dim))
+            .ok_or_else(|| invalid_data("too many samples"))?;
+        if channel_size.checked_mul(channel_count).is_none() {
+            return Err(invalid_data("too many samples"));
+        }
+
+        let mut corners = [0.; 6];
+        for corner in corners.iter_mut() {
+            *corner = read_f32(reader)?;
+        }
+        let bounds = AABB {
+            minimum: Point3::new(corners[0], corners[1], corners[2]),
+            maximum: Point3::new(corners[3], corners[4], corners[5]),
+        };
+
+        let mut channels = Vec::with_capacity(channel_count);
+        for _ in 0..channel_count {
+            // read as far as the data goes, rather than trusting the header with the size of the
+            // buffer up front
+            let mut bytes = Vec::new();
+            reader
+                .by_ref()
+                .take(channel_size as u64)
+                .read_to_end(&mut bytes)?;
+            if bytes.len() < channel_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the file ends part way through the samples",
+                ));
+            }
+            let channel = bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+                .collect();
+            channels.push(channel);
+        }
+
+        Ok(VolumeFile {
+            dims,
+            bounds,
+            channels,
+        })
+    }
+
+    /// One of the channels as a grid to sample, if there is such a channel.
+    pub fn grid(&self, channel: usize) -> Option<DensityGrid> {
+        let [nx, ny, nz] = self.dims;
+        let data = self.channels.get(channel)?.clone();
+        Some(DensityGrid::new(nx, ny, nz, data, self.bounds.clone()))
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> io::Result<f64> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes) as f64)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Write;
+
+    use crate::geometry::DensityField;
+
+    // the other way round from read, to make files to test it with
+    fn write<W: Write>(volume: &VolumeFile, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        for &dim in volume.dims.iter() {
+            writer.write_all(&(dim as u32).to_le_bytes())?;
+        }
+        writer.write_all(&(volume.channels.len() as u32).to_le_bytes())?;
+        for corner in [&volume.bounds.minimum, &volume.bounds.maximum].iter() {
+            for axis in 0..3 {
+                writer.write_all(&(corner[axis] as f32).to_le_bytes())?;
+            }
+        }
+        for channel in volume.channels.iter() {
+            for &sample in channel.iter() {
+                writer.write_all(&(sample as f32).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn get_volume() -> VolumeFile {
+        VolumeFile {
+            dims: [2, 3, 2],
+            bounds: AABB {
+                minimum: Point3::new(-1., 0., -1.),
+                maximum: Point3::new(1., 2., 1.),
+            },
+            channels: vec![
+                (0..12).map(|i| i as f64 / 4.).collect(),
+                (0..12).map(|i| 1000. + 100. * i as f64).collect(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_volume_file_round_trip() {
+        let volume = get_volume();
+        let path = std::env::temp_dir().join("raytracer_test_volume_file.rtvx");
+        let mut bytes = Vec::new();
+        write(&volume, &mut bytes).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        let loaded = VolumeFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.dims, volume.dims);
+        assert_eq!(loaded.bounds.minimum, volume.bounds.minimum);
+        assert_eq!(loaded.bounds.maximum, volume.bounds.maximum);
+        assert_eq!(loaded.channels, volume.channels);
+
+        // the sample at i = 1, j = 2, k = 0
+        let density = loaded.grid(0).unwrap();
+        assert_eq!(density.density(&Point3::new(1., 2., -1.)), 5. / 4.);
+        let temperature = loaded.grid(1).unwrap();
+        assert_eq!(temperature.density(&Point3::new(1., 2., -1.)), 1500.);
+        assert!(loaded.grid(2).is_none());
+    }
+
+    #[test]
+    fn test_volume_file_errors() {
+        let mut bytes = Vec::new();
+        write(&get_volume(), &mut bytes).unwrap();
+        assert_eq!(bytes.len(), 48 + 2 * 4 * 12);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let error = VolumeFile::read(&mut &bad_magic[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        let error = VolumeFile::read(&mut &bad_version[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // more channels than allowed, and more samples than could fit in memory
+        let mut many_channels = bytes.clone();
+        many_channels[20..24].copy_from_slice(&1000u32.to_le_bytes());
+        let error = VolumeFile::read(&mut &many_channels[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let mut huge = bytes.clone();
+        for axis in 0..3 {
+            huge[8 + 4 * axis..12 + 4 * axis].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        let error = VolumeFile::read(&mut &huge[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // samples that would fit, but aren't there
+        let mut large = bytes.clone();
+        large[8..12].copy_from_slice(&100_000u32.to_le_bytes());
+        large[12..16].copy_from_slice(&100_000u32.to_le_bytes());
+        let error = VolumeFile::read(&mut &large[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let truncated = &bytes[..bytes.len() - 1];
+        let error = VolumeFile::read(&mut &truncated[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
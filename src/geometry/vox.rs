@@ -0,0 +1,383 @@
+//! Importing models from MagicaVoxel's `.vox` files.
+//!
+//! A file is a tree of chunks, each an id, the sizes of its contents and children, then the
+//! contents and children themselves. Everything a `VoxelGrid` needs is in the children of the
+//! `MAIN` chunk:
+//!
+//! - `SIZE`: the model's size along x, y and z;
+//! - `XYZI`: the filled voxels, as x, y, z and a colour index from 1 to 255;
+//! - `RGBA`: the palette, whose `i`th entry is colour index `i + 1`, or the default palette if
+//!   there isn't one;
+//! - `MATL`: the material for a colour index, as a dictionary of settings.
+//!
+//! Only the first model in a file is imported, and the scene graph chunks are ignored.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{Point3, VoxelGrid};
+use crate::color::Color;
+use crate::material::types::{DiffuseLight, Lambertian, Metal};
+use crate::material::Material;
+use crate::texture::SolidColor;
+
+/// The most voxels a model can have along each axis, as in MagicaVoxel itself.
+const MAX_SIZE: usize = 256;
+
+struct VoxModel {
+    size: [usize; 3],
+    voxels: Vec<[u8; 4]>,
+    // indexed by colour index, so entry 0 is unused
+    palette: Vec<[u8; 4]>,
+    materials: HashMap<u8, HashMap<String, String>>,
+}
+
+impl VoxelGrid {
+    /// The first model in the `.vox` file at `path`, with voxels `voxel_size` across from
+    /// `corner`.
+    ///
+    /// MagicaVoxel's z axis points up, so it becomes y here. Each colour in the palette becomes a
+    /// `Lambertian`, unless its material is metal, which becomes a `Metal` as rough as its
+    /// `_rough`, or emissive, which becomes a `DiffuseLight` of its colour times `_emit` and
+    /// doubled for each step of `_flux`.
+    pub fn from_vox<P: AsRef<Path>>(
+        path: P,
+        corner: Point3,
+        voxel_size: f64,
+    ) -> io::Result<VoxelGrid> {
+        VoxelGrid::from_vox_bytes(&std::fs::read(path)?, corner, voxel_size)
+    }
+
+    /// Like `from_vox`, with the contents of the file already read.
+    pub fn from_vox_bytes(bytes: &[u8], corner: Point3, voxel_size: f64) -> io::Result<VoxelGrid> {
+        let model = parse(bytes)?;
+
+        // z up becomes y up, and y becomes -z to keep the axes right handed
+        let [sx, sy, sz] = model.size;
+        let dims = [sx, sz, sy];
+        let cell_count = sx
+            .checked_mul(sy)
+            .and_then(|n| n.checked_mul(sz))
+            .ok_or_else(|| invalid_data("model too big"))?;
+        let mut cells = vec![0; cell_count];
+        for &[x, y, z, index] in model.voxels.iter() {
+            let (x, y, z) = (x as usize, y as usize, z as usize);
+            if x >= sx || y >= sy || z >= sz {
+                return Err(invalid_data("voxel outside the model"));
+            }
+            let (i, j, k) = (x, z, sy - 1 - y);
+            cells[i + dims[0] * (j + dims[1] * k)] = index;
+        }
+
+        let materials = (1..=255)
+            .map(|index| palette_material(&model, index))
+            .collect();
+        Ok(VoxelGrid::new(dims, cells, materials, corner, voxel_size))
+    }
+}
+
+fn palette_material(model: &VoxModel, index: u8) -> Rc<dyn Material> {
+    let [r, g, b, _] = model.palette[index as usize];
+    let color = Color::new(r as f64 / 255., g as f64 / 255., b as f64 / 255.);
+
+    let settings = model.materials.get(&index);
+    let setting = |key: &str| -> Option<f64> { settings?.get(key)?.parse().ok() };
+    match settings.and_then(|s| s.get("_type")).map(String::as_str) {
+        Some("_metal") => {
+            let fuzz = setting("_rough").unwrap_or(0.).clamp(0., 1.);
+            Rc::new(Metal::new(Rc::new(SolidColor { color }), fuzz))
+        }
+        Some("_emit") => {
+            let strength =
+                setting("_emit").unwrap_or(1.) * f64::powf(2., setting("_flux").unwrap_or(0.));
+            Rc::new(DiffuseLight::new(strength * color))
+        }
+        _ => Rc::new(Lambertian::from_color(color)),
+    }
+}
+
+fn parse(bytes: &[u8]) -> io::Result<VoxModel> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(4)? != b"VOX " {
+        return Err(invalid_data("not a .vox file"));
+    }
+    let _version = reader.u32()?;
+
+    let (id, main) = reader.chunk()?;
+    if id != b"MAIN" {
+        return Err(invalid_data("no MAIN chunk"));
+    }
+
+    let mut model = VoxModel {
+        size: [0; 3],
+        voxels: Vec::new(),
+        palette: default_palette(),
+        materials: HashMap::new(),
+    };
+    let (mut have_size, mut have_voxels) = (false, false);
+    let mut children = Reader {
+        bytes: main,
+        pos: 0,
+    };
+    while children.pos < children.bytes.len() {
+        let (id, contents) = children.chunk()?;
+        let mut contents = Reader {
+            bytes: contents,
+            pos: 0,
+        };
+        match id {
+            b"SIZE" if !have_size => {
+                for size in model.size.iter_mut() {
+                    *size = contents.u32()? as usize;
+                    if !(1..=MAX_SIZE).contains(size) {
+                        return Err(invalid_data("model size out of range"));
+                    }
+                }
+                have_size = true;
+            }
+            b"XYZI" if !have_voxels => {
+                let count = contents.u32()? as usize;
+                for _ in 0..count {
+                    let voxel = contents.take(4)?;
+                    model.voxels.push([voxel[0], voxel[1], voxel[2], voxel[3]]);
+                }
+                have_voxels = true;
+            }
+            b"RGBA" => {
+                for index in 1..256 {
+                    let color = contents.take(4)?;
+                    model.palette[index] = [color[0], color[1], color[2], color[3]];
+                }
+            }
+            b"MATL" => {
+                let id = contents.u32()?;
+                let settings = contents.dict()?;
+                if (1..=255).contains(&id) {
+                    model.materials.insert(id as u8, settings);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !have_size || !have_voxels {
+        return Err(invalid_data("no model"));
+    }
+    Ok(model)
+}
+
+/// MagicaVoxel's palette for models that don't have their own: a 6 by 6 by 6 cube of colours with
+/// the components stepping down from 255 by 51, blue fastest and leaving out black, then ramps
+/// of red, green, blue and grey.
+fn default_palette() -> Vec<[u8; 4]> {
+    let mut palette = vec![[0, 0, 0, 0]];
+    for r in (0..6).rev() {
+        for g in (0..6).rev() {
+            for b in (0..6).rev() {
+                if r + g + b > 0 {
+                    palette.push([51 * r, 51 * g, 51 * b, 255]);
+                }
+            }
+        }
+    }
+    let ramp = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    for channel in 0..4 {
+        for &value in ramp.iter() {
+            let mut color = [0, 0, 0, 255];
+            for (i, component) in color.iter_mut().take(3).enumerate() {
+                if i == channel || channel == 3 {
+                    *component = value;
+                }
+            }
+            palette.push(color);
+        }
+    }
+    palette
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.pos + n > self.bytes.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected end of .vox file",
+            ));
+        }
+        let bytes = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A chunk's id and, for `MAIN`, its children, or for anything else, its contents.
+    fn chunk(&mut self) -> io::Result<(&'a [u8], &'a [u8])> {
+        let id = self.take(4)?;
+        let contents_size = self.u32()? as usize;
+        let children_size = self.u32()? as usize;
+        let contents = self.take(contents_size)?;
+        let children = self.take(children_size)?;
+        Ok((id, if id == b"MAIN" { children } else { contents }))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn dict(&mut self) -> io::Result<HashMap<String, String>> {
+        let count = self.u32()?;
+        let mut dict = HashMap::new();
+        for _ in 0..count {
+            let key = self.string()?;
+            dict.insert(key, self.string()?);
+        }
+        Ok(dict)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Ray, Vec3};
+    use crate::hittable::Hittable;
+
+    fn chunk(id: &[u8], contents: &[u8], children: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(children.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(contents);
+        bytes.extend_from_slice(children);
+        bytes
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut bytes = (s.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(s.as_bytes());
+        bytes
+    }
+
+    // a 2 by 3 by 4 model (4 tall) with a red voxel at the origin, and a glowing one at the top
+    // using colour index 2, which is bright in a material
+    fn get_vox(with_palette: bool) -> Vec<u8> {
+        get_vox_sized([2, 3, 4], with_palette)
+    }
+
+    fn get_vox_sized(dims: [u32; 3], with_palette: bool) -> Vec<u8> {
+        let mut size = Vec::new();
+        for &n in dims.iter() {
+            size.extend_from_slice(&n.to_le_bytes());
+        }
+        let mut xyzi = 2u32.to_le_bytes().to_vec();
+        xyzi.extend_from_slice(&[0, 0, 0, 1, 1, 2, 3, 2]);
+        let mut rgba = vec![255, 0, 0, 255, 0, 0, 255, 255];
+        rgba.resize(1024, 0);
+        let mut matl = 2u32.to_le_bytes().to_vec();
+        matl.extend_from_slice(&2u32.to_le_bytes());
+        for s in ["_type", "_emit", "_emit", "0.5"].iter() {
+            matl.extend_from_slice(&string(s));
+        }
+
+        let mut children = chunk(b"SIZE", &size, &[]);
+        children.extend(chunk(b"XYZI", &xyzi, &[]));
+        children.extend(chunk(b"nTRN", &[1, 2, 3], &[]));
+        if with_palette {
+            children.extend(chunk(b"RGBA", &rgba, &[]));
+        }
+        children.extend(chunk(b"MATL", &matl, &[]));
+
+        let mut bytes = b"VOX ".to_vec();
+        bytes.extend_from_slice(&150u32.to_le_bytes());
+        bytes.extend(chunk(b"MAIN", &[], &children));
+        bytes
+    }
+
+    #[test]
+    fn test_vox_import() {
+        let grid = VoxelGrid::from_vox_bytes(&get_vox(true), Point3::new(0., 0., 0.), 0.5).unwrap();
+        // z up in the file is y up here
+        assert_eq!(
+            grid.bounding_box().unwrap().maximum,
+            Point3::new(1., 2., 1.5)
+        );
+
+        // the red voxel, at the back since y in the file runs towards -z
+        let ray = Ray::new(Point3::new(0.25, 0.25, 5.), Vec3::new(0., 0., -1.), 0.);
+        let rec = grid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.p.z - 1.5) < Vec3::TOL);
+        let scatter = rec.material_rc.scatter(&ray, &rec).unwrap();
+        assert_eq!(
+            scatter.attenuation.value(rec.u, rec.v, &rec.p),
+            Color::new(1., 0., 0.)
+        );
+
+        // the glowing voxel, at the top and the front
+        let ray = Ray::new(Point3::new(0.75, 5., 0.25), Vec3::new(0., -1., 0.), 0.);
+        let rec = grid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.p.y - 2.) < Vec3::TOL);
+        assert_eq!(
+            rec.material_rc.emit(rec.u, rec.v, &rec.p),
+            Color::new(0., 0., 0.5)
+        );
+    }
+
+    #[test]
+    fn test_vox_default_palette() {
+        let palette = default_palette();
+        assert_eq!(palette.len(), 256);
+        assert_eq!(palette[1], [255, 255, 255, 255]);
+        assert_eq!(palette[2], [255, 255, 204, 255]);
+        assert_eq!(palette[215], [0, 0, 51, 255]);
+        assert_eq!(palette[216], [0xee, 0, 0, 255]);
+        assert_eq!(palette[255], [0x11, 0x11, 0x11, 255]);
+
+        // colour index 1 from the default palette is white
+        let grid = VoxelGrid::from_vox_bytes(&get_vox(false), Point3::new(0., 0., 0.), 1.).unwrap();
+        let ray = Ray::new(Point3::new(0.5, 0.5, 5.), Vec3::new(0., 0., -1.), 0.);
+        let rec = grid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let scatter = rec.material_rc.scatter(&ray, &rec).unwrap();
+        assert_eq!(
+            scatter.attenuation.value(rec.u, rec.v, &rec.p),
+            Color::new(1., 1., 1.)
+        );
+    }
+
+    #[test]
+    fn test_vox_errors() {
+        let vox = get_vox(true);
+        let error = VoxelGrid::from_vox_bytes(b"RIFF", Point3::new(0., 0., 0.), 1.)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let truncated = &vox[..vox.len() - 1];
+        let error = VoxelGrid::from_vox_bytes(truncated, Point3::new(0., 0., 0.), 1.)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // sizes that are empty, bigger than MagicaVoxel allows, or would overflow the cell count
+        for &dims in [[0, 3, 4], [2, 257, 4], [u32::MAX, u32::MAX, u32::MAX]].iter() {
+            let error =
+                VoxelGrid::from_vox_bytes(&get_vox_sized(dims, true), Point3::new(0., 0., 0.), 1.)
+                    .err()
+                    .unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
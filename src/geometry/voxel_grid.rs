@@ -0,0 +1,220 @@
+use std::rc::Rc;
+
+use super::heightfield::GridWalk;
+use super::{Point3, Ray, Vec3};
+use crate::hittable::{HitRecord, Hittable, AABB};
+use crate::material::Material;
+
+/// A block of cube voxels, each either empty or filled with one of a palette of materials, from
+/// `corner` along the positive axes.
+///
+/// Rays walk the grid voxel by voxel with a 3D DDA and stop at the first filled one, so the cost
+/// depends on how many voxels a ray passes rather than how many there are. A ray starting inside
+/// the grid only hits voxels it enters, not the one it starts in.
+pub struct VoxelGrid {
+    dims: [usize; 3],
+    // 0 for empty, otherwise one more than the index into materials; x varying fastest, then y
+    cells: Vec<u8>,
+    materials: Vec<Rc<dyn Material>>,
+    corner: Point3,
+    voxel_size: f64,
+    bbox: AABB,
+}
+
+impl VoxelGrid {
+    /// `cells` holds `dims[0]` by `dims[1]` by `dims[2]` voxels, x varying fastest, then y, and
+    /// each is 0 for an empty voxel or `n` for one made of `materials[n - 1]`.
+    pub fn new(
+        dims: [usize; 3],
+        cells: Vec<u8>,
+        materials: Vec<Rc<dyn Material>>,
+        corner: Point3,
+        voxel_size: f64,
+    ) -> VoxelGrid {
+        assert_eq!(cells.len(), dims[0] * dims[1] * dims[2]);
+        assert!(cells.iter().all(|&c| (c as usize) <= materials.len()));
+        let extent = voxel_size * Vec3::new(dims[0] as f64, dims[1] as f64, dims[2] as f64);
+        let bbox = AABB {
+            minimum: corner.clone(),
+            maximum: &corner + extent,
+        };
+        VoxelGrid {
+            dims,
+            cells,
+            materials,
+            corner,
+            voxel_size,
+            bbox,
+        }
+    }
+
+    fn cell(&self, i: usize, j: usize, k: usize) -> u8 {
+        self.cells[i + self.dims[0] * (j + self.dims[1] * k)]
+    }
+
+    /// Where the ray crosses into the slab between the grid's faces along `axis`.
+    fn face_t(&self, ray: &Ray, axis: usize) -> f64 {
+        if ray.direction[axis] > 0. {
+            (self.bbox.minimum[axis] - ray.origin[axis]) / ray.direction[axis]
+        } else if ray.direction[axis] < 0. {
+            (self.bbox.maximum[axis] - ray.origin[axis]) / ray.direction[axis]
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+
+    /// The hit on the face of a voxel made of `material` that's entered across `axis` at `t`.
+    fn hit_voxel(&self, ray: &Ray, t: f64, axis: usize, material: u8) -> HitRecord {
+        let p = ray.at(t);
+        let mut outward_normal = Vec3::new(0., 0., 0.);
+        outward_normal[axis] = -f64::signum(ray.direction[axis]);
+
+        // the position across the face, as a fraction of the voxel
+        let fraction = |axis: usize| {
+            let x = (p[axis] - self.corner[axis]) / self.voxel_size;
+            x - f64::floor(x)
+        };
+        let (u, v) = (fraction((axis + 1) % 3), fraction((axis + 2) % 3));
+
+        HitRecord::new(
+            ray,
+            t,
+            p,
+            outward_normal,
+            self.materials[material as usize - 1].clone(),
+            u,
+            v,
+        )
+    }
+}
+
+impl Hittable for VoxelGrid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_start, t_end) = self.bbox.intersect(ray, t_min, t_max)?;
+
+        // walk the voxels in grid coordinates, relative to the corner
+        let start = ray.at(t_start);
+        let mut walks = Vec::with_capacity(3);
+        for axis in 0..3 {
+            walks.push(GridWalk::new(
+                ray.origin[axis] - self.corner[axis],
+                ray.direction[axis],
+                start[axis] - self.corner[axis],
+                self.voxel_size,
+                self.dims[axis],
+            ));
+        }
+
+        // If the ray comes in from outside, the first voxel counts, entered across whichever face
+        // of the grid the ray crossed last.
+        let entered_from_outside = t_start > t_min;
+        let mut axis = (0..3)
+            .max_by(|&a, &b| {
+                let t_a = self.face_t(ray, a);
+                t_a.partial_cmp(&self.face_t(ray, b)).unwrap()
+            })
+            .unwrap();
+        let mut t = t_start;
+        let mut first = true;
+        loop {
+            let material = self.cell(
+                walks[0].cell as usize,
+                walks[1].cell as usize,
+                walks[2].cell as usize,
+            );
+            if material != 0 && (!first || entered_from_outside) {
+                return Some(self.hit_voxel(ray, t, axis, material));
+            }
+            first = false;
+
+            axis = (0..3)
+                .min_by(|&a, &b| walks[a].t_next.partial_cmp(&walks[b].t_next).unwrap())
+                .unwrap();
+            t = walks[axis].t_next;
+            if t > t_end {
+                return None;
+            }
+            walks[axis].advance();
+            if walks[axis].cell < 0 || walks[axis].cell >= self.dims[axis] as isize {
+                return None;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::color::Color;
+    use crate::hittable::test_util::grey;
+    use crate::material::types::DiffuseLight;
+
+    // a 3 by 3 by 3 grid of unit voxels from the origin, with a lambertian voxel in the middle
+    // and a light in the corner at the origin
+    fn get_grid() -> VoxelGrid {
+        let mut cells = vec![0; 27];
+        cells[13] = 1;
+        cells[0] = 2;
+        let materials: Vec<Rc<dyn Material>> =
+            vec![grey(), Rc::new(DiffuseLight::new(Color::new(1., 1., 1.)))];
+        VoxelGrid::new([3, 3, 3], cells, materials, Point3::new(0., 0., 0.), 1.)
+    }
+
+    #[test]
+    fn test_voxel_grid_hit() {
+        let grid = get_grid();
+        assert_eq!(
+            grid.bounding_box().unwrap().maximum,
+            Point3::new(3., 3., 3.)
+        );
+
+        // straight into the middle voxel from above
+        let ray = Ray::new(Point3::new(1.5, 5., 1.25), Vec3::new(0., -1., 0.), 0.);
+        let rec = grid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 3.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+        assert!(rec.front_face);
+        assert!(f64::abs(rec.u - 0.25) < Vec3::TOL);
+        assert!(f64::abs(rec.v - 0.5) < Vec3::TOL);
+
+        // along the bottom row, into the light at the origin
+        let ray = Ray::new(Point3::new(-1., 0.5, 0.25), Vec3::new(1., 0., 0.), 0.);
+        let rec = grid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(-1., 0., 0.));
+        assert_eq!(
+            rec.material_rc.emit(rec.u, rec.v, &rec.p),
+            Color::new(1., 1., 1.)
+        );
+
+        // along a row of empty voxels
+        let ray = Ray::new(Point3::new(-1., 2.5, 2.5), Vec3::new(1., 0., 0.), 0.);
+        assert!(grid.hit(&ray, 0.001, f64::INFINITY).is_none());
+
+        // stopping short of the middle voxel
+        let ray = Ray::new(Point3::new(1.5, 5., 1.5), Vec3::new(0., -1., 0.), 0.);
+        assert!(grid.hit(&ray, 0.001, 2.5).is_none());
+    }
+
+    #[test]
+    fn test_voxel_grid_hit_from_inside() {
+        let grid = get_grid();
+
+        // from an empty voxel into the middle one, crossing into the voxels beside it on the way
+        let ray = Ray::new(Point3::new(2.5, 2.5, 2.5), Vec3::new(-1., -0.9, -1.1), 0.);
+        let rec = grid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(1. <= rec.p.x && rec.p.x <= 2.);
+        assert!(1. <= rec.p.z && rec.p.z <= 2.);
+        assert!(f64::abs(rec.p.y - 2.) < Vec3::TOL);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+
+        // leaving the middle voxel from the inside, where there's nothing else to hit
+        let ray = Ray::new(Point3::new(1.5, 1.5, 1.5), Vec3::new(0., 1., 0.), 0.);
+        assert!(grid.hit(&ray, 0.001, f64::INFINITY).is_none());
+    }
+}
@@ -34,6 +34,12 @@ impl AABB {
     //
     // optimized (?)
     pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.intersect(r, t_min, t_max).is_some()
+    }
+
+    /// The range of `t` within `[t_min, t_max]` where the ray is inside the box, if any.
+    pub fn intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (mut t_min, mut t_max) = (t_min, t_max);
         for i in 0..3 {
             let inv_d = 1. / r.direction[i];
             let mut t0 = (self.minimum[i] - r.origin[i]) * inv_d;
@@ -41,14 +47,14 @@ impl AABB {
             if inv_d < 0. {
                 std::mem::swap(&mut t0, &mut t1);
             }
-            let t_min = f64::max(t0, t_min);
-            let t_max = f64::min(t1, t_max);
+            t_min = f64::max(t0, t_min);
+            t_max = f64::min(t1, t_max);
 
             if t_max <= t_min {
-                return false;
+                return None;
             }
         }
-        true
+        Some((t_min, t_max))
     }
 
     pub fn surrounding_box(box0: &Self, box1: &Self) -> Self {
@@ -65,4 +71,70 @@ impl AABB {
 
         Self { minimum, maximum }
     }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = &self.maximum - &self.minimum;
+        2. * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (&self.minimum + &self.maximum)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Vec3;
+
+    #[test]
+    fn test_aabb_intersect() {
+        let bbox = AABB {
+            minimum: Point3::new(-1., -1., -1.),
+            maximum: Point3::new(1., 1., 1.),
+        };
+
+        let r = Ray::new(Point3::new(-5., 0.5, 0.), Vec3::new(2., 0., 0.), 0.);
+        assert_eq!(bbox.intersect(&r, 0., f64::INFINITY), Some((2., 3.)));
+        assert_eq!(bbox.intersect(&r, 2.5, 10.), Some((2.5, 3.)));
+        assert_eq!(bbox.intersect(&r, 0., 1.), None);
+
+        let r = Ray::new(Point3::new(-5., 2., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert_eq!(bbox.intersect(&r, 0., f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_aabb_hit() {
+        let bbox = AABB {
+            minimum: Point3::new(-1., -1., -1.),
+            maximum: Point3::new(1., 1., 1.),
+        };
+        let r = Ray::new(Point3::new(-5., 0., 0.), Vec3::new(1., 0.1, 0.), 0.);
+        assert!(bbox.hit(&r, 0., f64::INFINITY));
+    }
+
+    #[test]
+    fn test_aabb_hit_across_slabs() {
+        // rays that pass through each slab, but never through all of them at once, so each
+        // slab's range has to narrow the next's
+        let bbox = AABB {
+            minimum: Point3::new(-1., -1., -1.),
+            maximum: Point3::new(1., 1., 1.),
+        };
+
+        // passing the corner, through the x slab (t from 4 to 6) before the y slab (8 to 12)
+        let r = Ray::new(Point3::new(-5., -5., 0.), Vec3::new(1., 0.5, 0.), 0.);
+        assert!(!bbox.hit(&r, 0., f64::INFINITY));
+        assert_eq!(bbox.intersect(&r, 0., f64::INFINITY), None);
+
+        // the same going the other way, through the z slab after leaving the x slab
+        let r = Ray::new(Point3::new(5., 0., 5.), Vec3::new(-1., 0., -0.5), 0.);
+        assert!(!bbox.hit(&r, 0., f64::INFINITY));
+
+        // and one that clips the corner, inside all three slabs from t = 5 to 6
+        let r = Ray::new(Point3::new(-5., -3.5, 0.), Vec3::new(1., 0.5, 0.), 0.);
+        assert!(bbox.hit(&r, 0., f64::INFINITY));
+        assert_eq!(bbox.intersect(&r, 0., f64::INFINITY), Some((5., 6.)));
+    }
 }
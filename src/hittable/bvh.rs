@@ -1,5 +1,3 @@
-// TODO: unit tests
-
 #![allow(dead_code)]
 
 use std::cmp::Ordering;
@@ -7,75 +5,311 @@ use std::rc::Rc;
 
 use rand::Rng;
 
+use super::stats::{self, BVHStats};
 use super::Ray;
+use crate::geometry::Point3;
 use crate::hittable::{HitRecord, Hittable, AABB};
 
-pub struct BVHNode {
-    left: Rc<dyn Hittable>,
-    right: Rc<dyn Hittable>,
-    bbox: AABB,
+/// How a `BVHNode` divides its objects between its two children.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitStrategy {
+    /// Sort along a random axis and split at the median. Quick to build, but the children's boxes
+    /// can overlap a lot when the objects are unevenly spread.
+    Median,
+    /// The binned Surface Area Heuristic: try splitting between buckets of objects along each
+    /// axis, and keep the split that makes a ray least likely to have to test many objects, or
+    /// make a leaf if that's cheaper than any split.
+    Sah,
 }
 
-impl BVHNode {
-    pub fn new(src_objects: &Vec<Rc<dyn Hittable>>, start: usize, end: usize) -> BVHNode {
-        let left;
-        let right;
-        let mut objects = src_objects.clone();
-
-        let comparator = match rand::thread_rng().gen_range(0..3) {
-            0 => |a: &_, b: &_| BVHNode::box_compare(a, b, 0),
-            1 => |a: &_, b: &_| BVHNode::box_compare(a, b, 1),
-            _ => |a: &_, b: &_| BVHNode::box_compare(a, b, 2),
+#[derive(Clone, Debug)]
+pub struct BVHOptions {
+    pub strategy: SplitStrategy,
+    /// The most objects a leaf holds, unless `max_depth` stops the splitting first.
+    pub max_leaf_size: usize,
+    /// How deep the tree can get; whatever's left at this depth goes in one leaf.
+    pub max_depth: usize,
+    /// How many times its cost when built a `LinearBVH`'s SAH cost can grow to, as its objects
+    /// move and it's refitted around them, before it's rebuilt instead.
+    pub rebuild_ratio: f64,
+}
+
+impl Default for BVHOptions {
+    fn default() -> Self {
+        BVHOptions {
+            strategy: SplitStrategy::Sah,
+            max_leaf_size: 4,
+            max_depth: 64,
+            rebuild_ratio: 1.5,
+        }
+    }
+}
+
+/// The cost of testing a ray against a node's box, relative to testing it against an object.
+const TRAVERSAL_COST: f64 = 0.125;
+
+/// How many buckets the SAH builder sorts objects into along each axis.
+const BINS: usize = 12;
+
+/// The SAH cost of a branch with the box `bbox`, from its children's boxes and costs.
+pub(super) fn branch_cost(bbox: &AABB, children: &[(&AABB, f64)]) -> f64 {
+    let area = bbox.surface_area();
+    let child_cost = |&(child_bbox, cost): &(&AABB, f64)| {
+        if area > 0. {
+            child_bbox.surface_area() / area * cost
+        } else {
+            cost / children.len() as f64
+        }
+    };
+    TRAVERSAL_COST + children.iter().map(child_cost).sum::<f64>()
+}
+
+/// An object's box, and where the object is in the list the tree is being built over, so that
+/// building only shuffles these around rather than the objects.
+pub(super) struct BuildItem {
+    pub index: usize,
+    pub bbox: AABB,
+    pub centroid: Point3,
+}
+
+impl BuildItem {
+    /// One item for each of `objects`, which must all have bounding boxes.
+    pub fn from_objects(objects: &[Rc<dyn Hittable>]) -> Vec<BuildItem> {
+        objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bbox = object
+                    .bounding_box()
+                    .expect("No bounding box in BVHNode constructor")
+                    .clone();
+                let centroid = bbox.centroid();
+                BuildItem {
+                    index,
+                    bbox,
+                    centroid,
+                }
+            })
+            .collect()
+    }
+
+    /// The box around all of `items`, of which there must be at least one.
+    pub fn bbox(items: &[BuildItem]) -> AABB {
+        items
+            .iter()
+            .skip(1)
+            .fold(items[0].bbox.clone(), |bbox, item| {
+                AABB::surrounding_box(&bbox, &item.bbox)
+            })
+    }
+}
+
+/// Reorders `items`, which are `depth` levels down and fit in `bbox`, so that the first `n` go in
+/// one child and the rest in the other, and returns `n` and the axis they were split along, or
+/// `None` if they should all go in a leaf.
+pub(super) fn split(
+    items: &mut [BuildItem],
+    bbox: &AABB,
+    options: &BVHOptions,
+    depth: usize,
+) -> Option<(usize, usize)> {
+    if items.len() == 1 || depth >= options.max_depth {
+        return None;
+    }
+    match options.strategy {
+        SplitStrategy::Median if items.len() > options.max_leaf_size => Some(split_median(items)),
+        SplitStrategy::Median => None,
+        SplitStrategy::Sah => split_sah(items, bbox, options),
+    }
+}
+
+/// Splits at the median of the boxes' minimums along a random axis.
+fn split_median(items: &mut [BuildItem]) -> (usize, usize) {
+    let axis = rand::thread_rng().gen_range(0..3);
+    let mid = items.len() / 2;
+    items.select_nth_unstable_by(mid, |a, b| {
+        a.bbox.minimum[axis]
+            .partial_cmp(&b.bbox.minimum[axis])
+            .unwrap_or(Ordering::Equal)
+    });
+    (mid, axis)
+}
+
+fn split_sah(items: &mut [BuildItem], bbox: &AABB, options: &BVHOptions) -> Option<(usize, usize)> {
+    let mut lo = items[0].centroid.clone();
+    let mut hi = items[0].centroid.clone();
+    for item in items.iter() {
+        for axis in 0..3 {
+            lo[axis] = f64::min(lo[axis], item.centroid[axis]);
+            hi[axis] = f64::max(hi[axis], item.centroid[axis]);
+        }
+    }
+    let bin_of = |centroid: &Point3, axis: usize| {
+        let fraction = (centroid[axis] - lo[axis]) / (hi[axis] - lo[axis]);
+        usize::min((fraction * BINS as f64) as usize, BINS - 1)
+    };
+
+    // the cheapest split, as the axis and the first bin on the far side
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        if hi[axis] <= lo[axis] {
+            continue;
+        }
+        let mut counts = [0; BINS];
+        let mut boxes: Vec<Option<AABB>> = vec![None; BINS];
+        for item in items.iter() {
+            let bin = bin_of(&item.centroid, axis);
+            counts[bin] += 1;
+            boxes[bin] = Some(match &boxes[bin] {
+                Some(bbox) => AABB::surrounding_box(bbox, &item.bbox),
+                None => item.bbox.clone(),
+            });
+        }
+
+        // the area times the number of objects on each side of each split
+        let sweep = |bins: &mut dyn Iterator<Item = usize>| {
+            let mut costs = [0.; BINS];
+            let mut count = 0;
+            let mut bbox: Option<AABB> = None;
+            for bin in bins {
+                count += counts[bin];
+                if let Some(bin_box) = &boxes[bin] {
+                    bbox = Some(match &bbox {
+                        Some(bbox) => AABB::surrounding_box(bbox, bin_box),
+                        None => bin_box.clone(),
+                    });
+                }
+                costs[bin] = bbox.as_ref().map_or(0., |b| b.surface_area()) * count as f64;
+            }
+            costs
         };
+        let below = sweep(&mut (0..BINS));
+        let above = sweep(&mut (0..BINS).rev());
 
-        let object_span = end - start;
-        match object_span {
-            1 => {
-                left = objects[start].clone();
-                right = objects[start].clone();
+        for split in 1..BINS {
+            let cost = below[split - 1] + above[split];
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, split));
             }
-            2 => {
-                if comparator(&objects[start], &objects[start + 1]) == Ordering::Less {
-                    left = objects[start].clone();
-                    right = objects[start + 1].clone();
-                } else {
-                    left = objects[start + 1].clone();
-                    right = objects[start].clone();
-                }
+        }
+    }
+
+    let leaf_cost = items.len() as f64;
+    let may_be_leaf = items.len() <= options.max_leaf_size;
+    match best {
+        Some((cost, axis, split)) => {
+            let area = bbox.surface_area();
+            let cost = if area > 0. {
+                TRAVERSAL_COST + cost / area
+            } else {
+                TRAVERSAL_COST + leaf_cost / 2.
+            };
+            if may_be_leaf && leaf_cost <= cost {
+                return None;
             }
-            _ => {
-                let mid = start + object_span / 2;
 
-                objects.sort_by(comparator);
-                left = Rc::new(BVHNode::new(&objects, start, mid));
-                right = Rc::new(BVHNode::new(&objects, mid, end));
+            // move the items below the split to the front
+            let mut n = 0;
+            for i in 0..items.len() {
+                if bin_of(&items[i].centroid, axis) < split {
+                    items.swap(i, n);
+                    n += 1;
+                }
             }
+            Some((n, axis))
         }
+        // all the centroids are in the same place, so there's no telling them apart
+        None if may_be_leaf => None,
+        None => Some((items.len() / 2, 0)),
+    }
+}
+
+enum Contents {
+    Branch(Box<BVHNode>, Box<BVHNode>),
+    Leaf(Vec<Rc<dyn Hittable>>),
+}
+
+/// A bounding volume hierarchy of boxed nodes with two children each.
+///
+/// Unlike a `LinearBVH`, it can't be refitted when its objects move, so an animated `World` with
+/// one builds it again for every frame.
+pub struct BVHNode {
+    contents: Contents,
+    bbox: AABB,
+}
+
+impl BVHNode {
+    /// A tree over `src_objects[start..end]` with the default options.
+    ///
+    /// Every object must have a bounding box; a `World` keeps the ones that don't to one side.
+    pub fn new(src_objects: &Vec<Rc<dyn Hittable>>, start: usize, end: usize) -> BVHNode {
+        BVHNode::with_options(&src_objects[start..end], &BVHOptions::default())
+    }
 
-        let lbbox = left
-            .bounding_box()
-            .expect("No bounding box in BVHNode constructor");
-        let rbbox = right
-            .bounding_box()
-            .expect("No bounding box in BVHNode constructor");
-        return BVHNode {
-            left: left.clone(),
-            right: right.clone(),
-            bbox: AABB::surrounding_box(lbbox, rbbox),
+    pub fn with_options(objects: &[Rc<dyn Hittable>], options: &BVHOptions) -> BVHNode {
+        assert!(!objects.is_empty(), "BVHNode needs at least one object");
+        let mut items = BuildItem::from_objects(objects);
+        BVHNode::build(&mut items, objects, options, 0)
+    }
+
+    fn build(
+        items: &mut [BuildItem],
+        objects: &[Rc<dyn Hittable>],
+        options: &BVHOptions,
+        depth: usize,
+    ) -> BVHNode {
+        let bbox = BuildItem::bbox(items);
+        let contents = match split(items, &bbox, options, depth) {
+            Some((n, _)) => {
+                let (left, right) = items.split_at_mut(n);
+                Contents::Branch(
+                    Box::new(BVHNode::build(left, objects, options, depth + 1)),
+                    Box::new(BVHNode::build(right, objects, options, depth + 1)),
+                )
+            }
+            None => Contents::Leaf(
+                items
+                    .iter()
+                    .map(|item| objects[item.index].clone())
+                    .collect(),
+            ),
         };
+        BVHNode { contents, bbox }
     }
 
-    fn box_compare(a: &Rc<dyn Hittable>, b: &Rc<dyn Hittable>, axis: usize) -> Ordering {
-        let box_a = a
-            .bounding_box()
-            .expect("No bounding box in BVHNode constructor");
-        let box_b = b
-            .bounding_box()
-            .expect("No bounding box in BVHNode constructor");
+    /// The expected cost of a ray hitting the tree under the Surface Area Heuristic, counting one
+    /// for each object tested, which is lower for a better tree.
+    pub fn sah_cost(&self) -> f64 {
+        match &self.contents {
+            Contents::Leaf(objects) => objects.len() as f64,
+            Contents::Branch(left, right) => branch_cost(
+                &self.bbox,
+                &[
+                    (&left.bbox, left.sah_cost()),
+                    (&right.bbox, right.sah_cost()),
+                ],
+            ),
+        }
+    }
+
+    pub fn stats(&self) -> BVHStats {
+        let mut stats = BVHStats {
+            sah_cost: self.sah_cost(),
+            ..BVHStats::default()
+        };
+        self.add_stats(&mut stats, 0);
+        stats
+    }
 
-        box_a.minimum[axis]
-            .partial_cmp(&box_b.minimum[axis])
-            .unwrap()
+    fn add_stats(&self, stats: &mut BVHStats, depth: usize) {
+        match &self.contents {
+            Contents::Leaf(objects) => stats.add_leaf(depth, objects.len()),
+            Contents::Branch(left, right) => {
+                stats.add_branch();
+                left.add_stats(stats, depth + 1);
+                right.add_stats(stats, depth + 1);
+            }
+        }
     }
 }
 
@@ -85,16 +319,183 @@ impl Hittable for BVHNode {
     }
 
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        stats::count_box_test();
         if !self.bbox.hit(ray, t_min, t_max) {
             return None;
         }
 
-        match self.left.hit(ray, t_min, t_max) {
-            Some(lrec) => match self.right.hit(ray, t_min, lrec.t) {
-                Some(rrec) => return Some(rrec),
-                None => return Some(lrec),
+        match &self.contents {
+            Contents::Branch(left, right) => match left.hit(ray, t_min, t_max) {
+                Some(lrec) => match right.hit(ray, t_min, lrec.t) {
+                    Some(rrec) => Some(rrec),
+                    None => Some(lrec),
+                },
+                None => right.hit(ray, t_min, t_max),
             },
-            None => return self.right.hit(ray, t_min, t_max),
+            Contents::Leaf(objects) => {
+                stats::count_primitive_tests(objects.len());
+                let mut closest_so_far = t_max;
+                let mut rec = None;
+                for object in objects {
+                    if let Some(temp_rec) = object.hit(ray, t_min, closest_so_far) {
+                        closest_so_far = temp_rec.t;
+                        rec = Some(temp_rec);
+                    }
+                }
+                rec
+            }
+        }
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        stats::count_box_test();
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return false;
+        }
+
+        match &self.contents {
+            Contents::Branch(left, right) => {
+                left.occluded(ray, t_min, t_max) || right.occluded(ray, t_min, t_max)
+            }
+            Contents::Leaf(objects) => objects.iter().any(|object| {
+                stats::count_primitive_tests(1);
+                object.occluded(ray, t_min, t_max)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Sphere, Vec3};
+    use crate::hittable::test_util::{grey, random_point, sphere_row, RcHittable};
+    use crate::hittable::HittableList;
+
+    // small spheres in a few tight clumps, spread out along x
+    fn get_spheres() -> Vec<Rc<dyn Hittable>> {
+        let material = grey();
+        let mut spheres: Vec<Rc<dyn Hittable>> = Vec::new();
+        for clump in 0..5 {
+            let center = Point3::new(20. * clump as f64, 0., 0.);
+            for _ in 0..40 {
+                spheres.push(Rc::new(Sphere::new(
+                    &center + random_point(1.),
+                    0.1,
+                    material.clone(),
+                )));
+            }
+        }
+        spheres
+    }
+
+    fn leaf_sizes(node: &BVHNode, depth: usize, sizes: &mut Vec<(usize, usize)>) {
+        match &node.contents {
+            Contents::Leaf(objects) => sizes.push((objects.len(), depth)),
+            Contents::Branch(left, right) => {
+                leaf_sizes(left, depth + 1, sizes);
+                leaf_sizes(right, depth + 1, sizes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bvh_sub_range() {
+        // a row of spheres, listed back to front so that building the tree reorders them
+        let mut spheres = sphere_row(20);
+        spheres.reverse();
+        let median = BVHOptions {
+            strategy: SplitStrategy::Median,
+            ..BVHOptions::default()
+        };
+        let trees = [
+            BVHNode::new(&spheres, 5, 15),
+            BVHNode::with_options(&spheres[5..15], &median),
+        ];
+        for tree in trees.iter() {
+            let mut sizes = Vec::new();
+            leaf_sizes(tree, 0, &mut sizes);
+            assert_eq!(sizes.iter().map(|(n, _)| n).sum::<usize>(), 10);
+
+            // spheres 14 down to 5 are in the tree, and only them
+            for i in 0..20 {
+                let ray = Ray::new(
+                    Point3::new(3. * i as f64, 5., 0.),
+                    Vec3::new(0., -1., 0.),
+                    0.,
+                );
+                assert_eq!(
+                    tree.hit(&ray, 0., f64::INFINITY).is_some(),
+                    (5..15).contains(&i)
+                );
+            }
         }
     }
+
+    #[test]
+    fn test_bvh_hits_match_list() {
+        let spheres = get_spheres();
+        let mut list = HittableList::new();
+        for sphere in spheres.iter() {
+            let sphere = sphere.clone();
+            list.add(Box::new(RcHittable(sphere)));
+        }
+
+        let mut rng = rand::thread_rng();
+        for strategy in [SplitStrategy::Median, SplitStrategy::Sah].iter() {
+            let options = BVHOptions {
+                strategy: *strategy,
+                max_leaf_size: 3,
+                ..BVHOptions::default()
+            };
+            let bvh = BVHNode::with_options(&spheres, &options);
+
+            let mut sizes = Vec::new();
+            leaf_sizes(&bvh, 0, &mut sizes);
+            assert_eq!(sizes.iter().map(|(n, _)| n).sum::<usize>(), spheres.len());
+            assert!(sizes.iter().all(|&(n, _)| (1..=3).contains(&n)));
+
+            for _ in 0..500 {
+                let origin = Point3::new(rng.gen_range(-5. ..85.), 5., rng.gen_range(-5. ..5.));
+                let target = Point3::new(rng.gen_range(-5. ..85.), 0., 0.);
+                let ray = Ray::new(origin.clone(), target - origin, 0.);
+                let expected = list.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                let actual = bvh.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                assert_eq!(expected, actual);
+                assert_eq!(
+                    list.occluded(&ray, 0.001, 3.),
+                    bvh.occluded(&ray, 0.001, 3.)
+                );
+                assert_eq!(bvh.occluded(&ray, 0.001, f64::INFINITY), expected.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_bvh_sah_is_cheaper() {
+        let spheres = get_spheres();
+        let median = BVHNode::with_options(
+            &spheres,
+            &BVHOptions {
+                strategy: SplitStrategy::Median,
+                ..BVHOptions::default()
+            },
+        );
+        let sah = BVHNode::with_options(&spheres, &BVHOptions::default());
+        assert!(sah.sah_cost() < median.sah_cost());
+
+        // max_depth stops the splitting, leaving everything at that depth in one leaf
+        let shallow = BVHNode::with_options(
+            &spheres,
+            &BVHOptions {
+                max_depth: 2,
+                ..BVHOptions::default()
+            },
+        );
+        let mut sizes = Vec::new();
+        leaf_sizes(&shallow, 0, &mut sizes);
+        assert!(sizes.iter().all(|&(_, depth)| depth <= 2));
+        assert_eq!(sizes.iter().map(|(n, _)| n).sum::<usize>(), spheres.len());
+    }
 }
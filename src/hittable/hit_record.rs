@@ -12,6 +12,8 @@ pub struct HitRecord {
     pub material_rc: Rc<dyn Material>,
     pub u: f64,
     pub v: f64,
+    /// The direction along the surface at the hit, for surfaces like `Curve`s that have one.
+    pub tangent: Option<Vec3>,
 }
 
 impl HitRecord {
@@ -34,6 +36,7 @@ impl HitRecord {
             material_rc,
             u,
             v,
+            tangent: None,
         }
     }
 
@@ -57,7 +60,7 @@ mod test {
         let mut rec: HitRecord;
 
         let ray_origin = Vec3::new(0., 0., 0.);
-        let ray_opposite_direction = Ray::new(ray_origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let ray_opposite_direction = Ray::new(ray_origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
 
         let t = 0.5;
         let p = Vec3::new(0.0, 0.0, -0.5);
@@ -80,7 +83,7 @@ mod test {
         assert_eq!(&rec.p, &p);
         assert_eq!(rec.normal, normal);
 
-        let ray_same_direction = Ray::new(ray_origin.clone(), Vec3::new(0.0, 0.0, 1.0));
+        let ray_same_direction = Ray::new(ray_origin.clone(), Vec3::new(0.0, 0.0, 1.0), 0.);
 
         rec = HitRecord::new(
             &ray_same_direction,
@@ -99,7 +102,7 @@ mod test {
     #[test]
     fn test_hitrecord_get_front_face() {
         let origin = Point3::new(0., 0., 0.);
-        let ray = Ray::new(origin, Vec3::new(0., 0., 0.5));
+        let ray = Ray::new(origin, Vec3::new(0., 0., 0.5), 0.);
 
         let outward_normal_same = Vec3::new(0., 0., -0.5);
         let same_direction = HitRecord::get_front_face(&ray, &outward_normal_same);
@@ -1,33 +1,35 @@
 use super::{HitRecord, Hittable, AABB};
-use crate::geometry::{Point3, Ray};
+use crate::geometry::Ray;
 
 pub struct HittableList {
-    bbox: AABB,
+    // the box around the objects that have one
+    bbox: Option<AABB>,
+    // whether any of the objects has no bounding box
+    unbounded: bool,
     objects: Vec<Box<dyn Hittable>>,
 }
 
 impl HittableList {
     pub fn new() -> HittableList {
         HittableList {
-            bbox: AABB {
-                minimum: Point3::new(0., 0., 0.),
-                maximum: Point3::new(0., 0., 0.),
-            },
+            bbox: None,
+            unbounded: false,
             objects: Vec::new(),
         }
     }
 
     pub fn add(&mut self, object: Box<dyn Hittable>) {
-        if object.bounding_box().is_some() {
-            let obj_bbox = object.bounding_box().unwrap();
-            if self.objects.len() == 0 {
-                self.bbox = obj_bbox.clone();
-            } else {
-                self.bbox = AABB::surrounding_box(&self.bbox, obj_bbox)
-            }
+        match (object.bounding_box(), &self.bbox) {
+            (None, _) => self.unbounded = true,
+            (Some(obj_bbox), None) => self.bbox = Some(obj_bbox.clone()),
+            (Some(obj_bbox), Some(bbox)) => self.bbox = Some(AABB::surrounding_box(bbox, obj_bbox)),
         }
         self.objects.push(object);
     }
+
+    pub fn into_objects(self) -> Vec<Box<dyn Hittable>> {
+        self.objects
+    }
 }
 
 impl Hittable for HittableList {
@@ -48,12 +50,19 @@ impl Hittable for HittableList {
         rec
     }
 
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.objects
+            .iter()
+            .any(|object| object.occluded(ray, t_min, t_max))
+    }
+
     fn bounding_box(&self) -> Option<&AABB> {
-        if self.objects.len() == 0 {
+        // an unbounded object makes the whole list unbounded
+        if self.unbounded {
             return None;
         }
 
-        Some(&self.bbox)
+        self.bbox.as_ref()
     }
 }
 
@@ -64,7 +73,8 @@ mod test {
     use std::rc::Rc;
 
     use crate::color::Color;
-    use crate::geometry::{Sphere, Vec3};
+    use crate::geometry::{Point3, Sphere, Vec3};
+    use crate::hittable::test_util::grey;
     use crate::hittable::HitRecord;
     use crate::material::types::Lambertian;
     use crate::texture::SolidColor;
@@ -106,7 +116,7 @@ mod test {
         let t_min = 0.;
         let t_max = std::f64::INFINITY;
 
-        let r_hit = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r_hit = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         rec = world.hit(&r_hit, t_min, t_max);
         let miss = rec.is_none();
         assert!(miss);
@@ -122,7 +132,7 @@ mod test {
         let hit = rec.is_some();
         assert!(hit);
 
-        let r_miss = Ray::new(origin.clone(), Vec3::new(1.0, 1.0, 0.0));
+        let r_miss = Ray::new(origin.clone(), Vec3::new(1.0, 1.0, 0.0), 0.);
         rec = world.hit(&r_miss, t_min, t_max);
         let miss = rec.is_none();
         assert!(miss);
@@ -187,4 +197,16 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_hittablelist_unbounded_bounding_box() {
+        let mut world = HittableList::new();
+        world.add(Box::new(GenericHittable {}));
+        assert!(world.bounding_box().is_none());
+
+        let material_rc = grey();
+        let sphere = Sphere::new(Point3::new(0., 0., -1.), 0.5, material_rc);
+        world.add(Box::new(sphere));
+        assert!(world.bounding_box().is_none());
+    }
 }
@@ -3,3 +3,9 @@ pub use translate::Translate;
 
 mod rotate;
 pub use rotate::Rotate;
+
+mod transform;
+pub use transform::Transform;
+
+mod scale;
+pub use scale::Scale;
@@ -7,36 +7,124 @@ use crate::geometry::{Point3, Ray, Vec3};
 
 pub struct Rotate {
     ptr: Box<dyn Hittable>,
+    axis: Vec3,
+    angle0: f64,
+    angle1: f64,
+    time0: f64,
+    time1: f64,
     sin_theta: f64,
     cos_theta: f64,
-    bbox: AABB,
+    bbox: Option<AABB>,
 }
 
 impl Rotate {
+    /// Rotate `ptr` about the y-axis by `angle` degrees.
     pub fn new(ptr: Box<dyn Hittable>, angle: f64) -> Rotate {
-        let angle = angle * PI / 180.;
-        let sin_theta = f64::sin(angle);
-        let cos_theta = f64::cos(angle);
-        let ptr_bbox = ptr.bounding_box().unwrap();
+        Rotate::about_axis(ptr, Vec3::new(0., 1., 0.), angle)
+    }
+
+    /// Rotate `ptr` about `axis` (through the origin) by `angle` degrees, counter-clockwise when
+    /// looking down the axis towards the origin.
+    pub fn about_axis(ptr: Box<dyn Hittable>, axis: Vec3, angle: f64) -> Rotate {
+        Rotate::moving(ptr, axis, angle, angle, 0., 0.)
+    }
 
-        let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
-        let mut max = Point3::new(NEG_INFINITY, NEG_INFINITY, NEG_INFINITY);
+    /// Rotate `ptr` about `axis` by an angle (in degrees) that changes linearly from `angle0` at
+    /// `time0` to `angle1` at `time1`.
+    pub fn moving(
+        ptr: Box<dyn Hittable>,
+        axis: Vec3,
+        angle0: f64,
+        angle1: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Rotate {
+        let axis = axis.unit_vector();
+        let angle0 = angle0 * PI / 180.;
+        let angle1 = angle1 * PI / 180.;
+        let sin_theta = f64::sin(angle0);
+        let cos_theta = f64::cos(angle0);
+        let (lo, hi) = (f64::min(angle0, angle1), f64::max(angle0, angle1));
+        let bbox = ptr
+            .bounding_box()
+            .map(|ptr_bbox| swept_bounding_box(ptr_bbox, &axis, lo, hi));
+        Rotate {
+            ptr,
+            axis,
+            angle0,
+            angle1,
+            time0,
+            time1,
+            sin_theta,
+            cos_theta,
+            bbox,
+        }
+    }
 
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    let i = i as f64;
-                    let j = j as f64;
-                    let k = k as f64;
+    fn sin_cos(&self, time: f64) -> (f64, f64) {
+        if self.angle0 == self.angle1 || self.time0 == self.time1 {
+            return (self.sin_theta, self.cos_theta);
+        }
+        let fraction = (time - self.time0) / (self.time1 - self.time0);
+        let angle = self.angle0 + fraction * (self.angle1 - self.angle0);
+        (f64::sin(angle), f64::cos(angle))
+    }
 
-                    let x = i * ptr_bbox.maximum.x + (1. - i) * ptr_bbox.minimum.x;
-                    let y = j * ptr_bbox.maximum.y + (1. - j) * ptr_bbox.minimum.y;
-                    let z = k * ptr_bbox.maximum.z + (1. - k) * ptr_bbox.minimum.z;
+    /// The ray in object space, which rotating by -theta takes it to.
+    fn local_ray(&self, ray: &Ray) -> Ray {
+        let (sin_theta, cos_theta) = self.sin_cos(ray.time);
+        Ray::new(
+            rotate(&self.axis, -sin_theta, cos_theta, &ray.origin),
+            rotate(&self.axis, -sin_theta, cos_theta, &ray.direction),
+            ray.time,
+        )
+    }
+}
 
-                    let x_rot = cos_theta * x + sin_theta * z;
-                    let z_rot = -sin_theta * x + cos_theta * z;
+/// Rotate `v` about the unit vector `axis` by the angle with the given sine and cosine, using
+/// Rodrigues' rotation formula.
+fn rotate(axis: &Vec3, sin_theta: f64, cos_theta: f64, v: &Vec3) -> Vec3 {
+    cos_theta * v + sin_theta * Vec3::cross(axis, v) + (1. - cos_theta) * Vec3::dot(axis, v) * axis
+}
 
-                    let tester = Vec3::new(x_rot, y, z_rot);
+/// Angles in `[lo, hi]` at which `b * cos(theta) + c * sin(theta)` reaches a minimum or maximum.
+fn sinusoid_extrema(b: f64, c: f64, lo: f64, hi: f64) -> Vec<f64> {
+    let phase = f64::atan2(c, b);
+    let first = f64::ceil((lo - phase) / PI) as i64;
+    let last = f64::floor((hi - phase) / PI) as i64;
+    (first..=last).map(|n| phase + (n as f64) * PI).collect()
+}
+
+/// The box bounding `ptr_bbox` as it's rotated about `axis` through every angle in `[lo, hi]`.
+fn swept_bounding_box(ptr_bbox: &AABB, axis: &Vec3, lo: f64, hi: f64) -> AABB {
+    let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
+    let mut max = Point3::new(NEG_INFINITY, NEG_INFINITY, NEG_INFINITY);
+
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let i = i as f64;
+                let j = j as f64;
+                let k = k as f64;
+
+                let x = i * ptr_bbox.maximum.x + (1. - i) * ptr_bbox.minimum.x;
+                let y = j * ptr_bbox.maximum.y + (1. - j) * ptr_bbox.minimum.y;
+                let z = k * ptr_bbox.maximum.z + (1. - k) * ptr_bbox.minimum.z;
+                let corner = Vec3::new(x, y, z);
+
+                // Each rotated coordinate is a sinusoid in the angle, a + b cos + c sin, so
+                // besides the end points of the sweep, the corner can only reach an extreme
+                // where one of its coordinates is stationary.
+                let a = Vec3::dot(axis, &corner) * axis;
+                let b = &corner - &a;
+                let c = Vec3::cross(axis, &corner);
+                let mut angles = vec![lo, hi];
+                for d in 0..3 {
+                    angles.extend(sinusoid_extrema(b[d], c[d], lo, hi));
+                }
+
+                for theta in angles {
+                    let tester = rotate(axis, f64::sin(theta), f64::cos(theta), &corner);
                     for c in 0..3 {
                         min[c] = f64::min(min[c], tester[c]);
                         max[c] = f64::max(max[c], tester[c]);
@@ -44,60 +132,162 @@ impl Rotate {
                 }
             }
         }
-        let bbox = AABB {
-            minimum: min,
-            maximum: max,
-        };
-        Rotate {
-            ptr,
-            sin_theta,
-            cos_theta,
-            bbox,
-        }
+    }
+    AABB {
+        minimum: min,
+        maximum: max,
     }
 }
 
 impl Hittable for Rotate {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let rot_origin = Point3 {
-            x: &self.cos_theta * ray.origin.x - &self.sin_theta * ray.origin.z,
-            y: ray.origin.y,
-            z: &self.sin_theta * ray.origin.x + &self.cos_theta * ray.origin.z,
-        };
-        let rot_direction = Vec3 {
-            x: &self.cos_theta * ray.direction.x - &self.sin_theta * ray.direction.z,
-            y: ray.direction.y,
-            z: &self.sin_theta * ray.direction.x + &self.cos_theta * ray.direction.z,
-        };
-        let rot_ray = Ray::new(rot_origin, rot_direction);
-
-        return match self.ptr.hit(&rot_ray, t_min, t_max) {
-            Some(rec) => {
-                let p = Point3 {
-                    x: &self.cos_theta * rec.p.x + &self.sin_theta * rec.p.z,
-                    y: rec.p.y,
-                    z: -&self.sin_theta * rec.p.x + &self.cos_theta * rec.p.z,
-                };
-                let normal = Vec3 {
-                    x: &self.cos_theta * rec.normal.x + &self.sin_theta * rec.normal.z,
-                    y: rec.normal.y,
-                    z: -&self.sin_theta * rec.normal.x + &self.cos_theta * rec.normal.z,
-                };
-                Some(HitRecord::new(
-                    &rot_ray,
-                    rec.t,
-                    p,
-                    normal,
-                    rec.material_rc.clone(),
-                    rec.u,
-                    rec.v,
-                ))
-            }
-            None => None,
+        let (sin_theta, cos_theta) = self.sin_cos(ray.time);
+        let rec = self.ptr.hit(&self.local_ray(ray), t_min, t_max)?;
+        let outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
         };
+        let mut rotated = HitRecord::new(
+            ray,
+            rec.t,
+            rotate(&self.axis, sin_theta, cos_theta, &rec.p),
+            rotate(&self.axis, sin_theta, cos_theta, &outward_normal),
+            rec.material_rc,
+            rec.u,
+            rec.v,
+        );
+        rotated.tangent = rec
+            .tangent
+            .map(|tangent| rotate(&self.axis, sin_theta, cos_theta, &tangent));
+        Some(rotated)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.ptr.occluded(&self.local_ray(ray), t_min, t_max)
     }
 
     fn bounding_box(&self) -> Option<&AABB> {
-        Some(&self.bbox)
+        self.bbox.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Block, Sphere};
+    use crate::hittable::test_util::grey;
+
+    fn get_block() -> Box<dyn Hittable> {
+        let material = grey();
+        Box::new(Block::new(
+            Point3::new(1., 0., -1.),
+            Point3::new(3., 1., 1.),
+            material,
+        ))
+    }
+
+    #[test]
+    fn test_sinusoid_extrema() {
+        // cos(theta) peaks at 0 and bottoms out at pi
+        let extrema = sinusoid_extrema(1., 0., -0.5, 4.);
+        assert_eq!(extrema.len(), 2);
+        assert!(f64::abs(extrema[0]) < Vec3::TOL);
+        assert!(f64::abs(extrema[1] - PI) < Vec3::TOL);
+
+        assert!(sinusoid_extrema(1., 0., 0.5, 1.).is_empty());
+    }
+
+    #[test]
+    fn test_rotate_bounding_box() {
+        let rotated = Rotate::new(get_block(), 90.);
+        assert_eq!(
+            *rotated.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-1., 0., -3.),
+                maximum: Vec3::new(1., 1., -1.),
+            }
+        );
+    }
+
+    #[test]
+    fn test_rotate_moving_bounding_box() {
+        // Sweeping through 90 degrees, the corner (3, _, 1) passes its furthest point along x in
+        // between the two end points, so the box must extend past both end-point boxes.
+        let rotated = Rotate::moving(get_block(), Vec3::new(0., 1., 0.), 0., 90., 0., 1.);
+        let bbox = rotated.bounding_box().unwrap();
+        assert!(f64::abs(bbox.maximum.x - f64::sqrt(10.)) < Vec3::TOL);
+        assert!(f64::abs(bbox.minimum.z + f64::sqrt(10.)) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_rotate_about_axis() {
+        // a quarter turn about the x-axis lays the block down along the y-axis
+        let rotated = Rotate::about_axis(get_block(), Vec3::new(2., 0., 0.), 90.);
+        assert_eq!(
+            *rotated.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(1., -1., 0.),
+                maximum: Vec3::new(3., 1., 1.),
+            }
+        );
+
+        let r = Ray::new(Point3::new(2., -5., 0.5), Vec3::new(0., 1., 0.), 0.);
+        let rec = rotated.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        assert_eq!(rec.p, Point3::new(2., -1., 0.5));
+        assert_eq!(rec.normal, Vec3::new(0., -1., 0.));
+        assert!(rec.front_face);
+
+        // rotating about an arbitrary axis and back again is the identity
+        let axis = Vec3::new(1., -2., 0.5).unit_vector();
+        let v = Vec3::new(0.3, 4., -1.);
+        let theta = 1.234;
+        let there = rotate(&axis, f64::sin(theta), f64::cos(theta), &v);
+        assert_eq!(rotate(&axis, -f64::sin(theta), f64::cos(theta), &there), v);
+        assert!(f64::abs(there.length() - v.length()) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_rotate_front_face() {
+        let material = grey();
+        let sphere = Box::new(Sphere::new(Point3::new(2., 0.5, 0.), 0.5, material));
+        let axis = Vec3::new(1., 1., 0.);
+        let rotated = Rotate::about_axis(sphere, axis.clone(), 30.);
+        let center = rotate(
+            &axis.unit_vector(),
+            f64::sin(PI / 6.),
+            f64::cos(PI / 6.),
+            &Point3::new(2., 0.5, 0.),
+        );
+
+        let r = Ray::new(&center - Vec3::new(0., 0., 5.), Vec3::new(0., 0., 1.), 0.);
+        let rec = rotated.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(rec.front_face);
+        assert_eq!(rec.p, &center - Vec3::new(0., 0., 0.5));
+        assert_eq!(rec.normal, Vec3::new(0., 0., -1.));
+
+        // leaving the sphere from its center, the hit is on the inside
+        let r = Ray::new(center.clone(), Vec3::new(0., 0., 1.), 0.);
+        let rec = rotated.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(!rec.front_face);
+        assert_eq!(rec.p, &center + Vec3::new(0., 0., 0.5));
+        assert_eq!(rec.normal, Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_rotate_moving_hit() {
+        let rotated = Rotate::moving(get_block(), Vec3::new(0., 1., 0.), 0., 90., 0., 1.);
+
+        // at time 0 the block is unrotated and straddles the x-axis in front of the ray...
+        let r = Ray::new(Point3::new(-5., 0.5, 0.), Vec3::new(1., 0., 0.), 0.);
+        let rec = rotated.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 6.) < Vec3::TOL);
+
+        // ...and at time 1 it has swung round to straddle the negative z-axis
+        let r = Ray::new(Point3::new(0., 0.5, 5.), Vec3::new(0., 0., -1.), 1.);
+        let rec = rotated.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 6.) < Vec3::TOL);
     }
 }
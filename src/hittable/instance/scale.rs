@@ -0,0 +1,167 @@
+use super::super::*;
+
+use crate::geometry::{Point3, Ray, Vec3};
+
+/// Scale `ptr` about the origin by a (possibly different) factor along each axis.
+pub struct Scale {
+    ptr: Box<dyn Hittable>,
+    factors: Vec3,
+    bbox: Option<AABB>,
+}
+
+impl Scale {
+    pub fn new(ptr: Box<dyn Hittable>, factors: Vec3) -> Scale {
+        assert!(
+            factors.x != 0. && factors.y != 0. && factors.z != 0.,
+            "Scale factors must be non-zero"
+        );
+        let bbox = ptr.bounding_box().map(|ptr_bbox| {
+            let corner0 = scale(&ptr_bbox.minimum, &factors);
+            let corner1 = scale(&ptr_bbox.maximum, &factors);
+
+            // negative factors mirror the object, swapping which corner is the minimum
+            AABB {
+                minimum: Point3::new(
+                    f64::min(corner0.x, corner1.x),
+                    f64::min(corner0.y, corner1.y),
+                    f64::min(corner0.z, corner1.z),
+                ),
+                maximum: Point3::new(
+                    f64::max(corner0.x, corner1.x),
+                    f64::max(corner0.y, corner1.y),
+                    f64::max(corner0.z, corner1.z),
+                ),
+            }
+        });
+        Scale { ptr, factors, bbox }
+    }
+
+    pub fn uniform(ptr: Box<dyn Hittable>, factor: f64) -> Scale {
+        Scale::new(ptr, Vec3::new(factor, factor, factor))
+    }
+
+    /// The ray in object space, with its direction unscaled along with its origin so that t is
+    /// the same in both spaces.
+    fn local_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            unscale(&ray.origin, &self.factors),
+            unscale(&ray.direction, &self.factors),
+            ray.time,
+        )
+    }
+}
+
+fn scale(v: &Vec3, factors: &Vec3) -> Vec3 {
+    Vec3::new(v.x * factors.x, v.y * factors.y, v.z * factors.z)
+}
+
+fn unscale(v: &Vec3, factors: &Vec3) -> Vec3 {
+    Vec3::new(v.x / factors.x, v.y / factors.y, v.z / factors.z)
+}
+
+impl Hittable for Scale {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rec = self.ptr.hit(&self.local_ray(ray), t_min, t_max)?;
+        let outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        // normals scale by the inverse factors to stay perpendicular to the surface, while
+        // tangents scale like any other direction along it
+        let mut scaled = HitRecord::new(
+            ray,
+            rec.t,
+            scale(&rec.p, &self.factors),
+            unscale(&outward_normal, &self.factors).unit_vector(),
+            rec.material_rc,
+            rec.u,
+            rec.v,
+        );
+        scaled.tangent = rec
+            .tangent
+            .map(|tangent| scale(&tangent, &self.factors).unit_vector());
+        Some(scaled)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.ptr.occluded(&self.local_ray(ray), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.bbox.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Sphere;
+    use crate::hittable::test_util::grey;
+
+    fn get_sphere() -> Box<dyn Hittable> {
+        let material = grey();
+        Box::new(Sphere::new(Point3::new(1., 0., 0.), 1., material))
+    }
+
+    #[test]
+    fn test_scale_hit() {
+        let scaled = Scale::new(get_sphere(), Vec3::new(2., 0.5, 1.));
+
+        let r = Ray::new(Point3::new(10., 0., 0.), Vec3::new(-1., 0., 0.), 0.);
+        let rec = scaled.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 6.) < Vec3::TOL);
+        assert_eq!(rec.p, Point3::new(4., 0., 0.));
+        assert_eq!(rec.normal, Vec3::new(1., 0., 0.));
+        assert!(scaled.occluded(&r, 0., 6.5));
+        assert!(!scaled.occluded(&r, 0., 5.5));
+
+        let r = Ray::new(Point3::new(2., 10., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = scaled.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 9.5) < Vec3::TOL);
+
+        // off the axes, the normal of the ellipse (x - 2)^2 / 4 + 4 y^2 = 1 is parallel to
+        // ((x - 2) / 4, 4 y)
+        let y = 0.25;
+        let x = 2. + f64::sqrt(3.);
+        let r = Ray::new(Point3::new(10., y, 0.), Vec3::new(-1., 0., 0.), 0.);
+        let rec = scaled.hit(&r, 0., f64::INFINITY).unwrap();
+        assert_eq!(rec.p, Point3::new(x, y, 0.));
+        assert_eq!(
+            rec.normal,
+            Vec3::new((x - 2.) / 4., 4. * y, 0.).unit_vector()
+        );
+    }
+
+    #[test]
+    fn test_scale_bounding_box() {
+        let scaled = Scale::new(get_sphere(), Vec3::new(-2., 0.5, 3.));
+        assert_eq!(
+            *scaled.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-4., -0.5, -3.),
+                maximum: Vec3::new(0., 0.5, 3.),
+            }
+        );
+
+        let uniform = Scale::uniform(get_sphere(), 2.);
+        assert_eq!(
+            *uniform.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(0., -2., -2.),
+                maximum: Vec3::new(4., 2., 2.),
+            }
+        );
+    }
+
+    #[test]
+    fn test_scale_mirrored_front_face() {
+        // mirroring flips the handedness, but the outside must stay the outside
+        let scaled = Scale::new(get_sphere(), Vec3::new(-1., 1., 1.));
+        let r = Ray::new(Point3::new(-10., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        let rec = scaled.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(-1., 0., 0.));
+    }
+}
@@ -0,0 +1,202 @@
+use std::rc::Rc;
+
+use super::super::*;
+
+use crate::geometry::{Mat4, Point3, Ray};
+
+/// An instance of `ptr` placed in the world by an arbitrary affine transform.
+pub struct Transform {
+    ptr: Rc<dyn Hittable>,
+    matrix: Mat4,
+    inverse: Mat4,
+    // inverse-transpose of the matrix, for carrying normals into world space
+    normal_matrix: Mat4,
+    bbox: Option<AABB>,
+}
+
+impl Transform {
+    pub fn new(ptr: Box<dyn Hittable>, matrix: Mat4) -> Transform {
+        Transform::shared(Rc::from(ptr), matrix)
+    }
+
+    /// An instance of an object that other instances can share, like the bottom level of a
+    /// `Tlas`.
+    pub fn shared(ptr: Rc<dyn Hittable>, matrix: Mat4) -> Transform {
+        let inverse = matrix
+            .inverse()
+            .expect("Transform matrix must be invertible");
+        let normal_matrix = inverse.transpose();
+        let bbox = ptr
+            .bounding_box()
+            .map(|ptr_bbox| transform_bounding_box(&matrix, ptr_bbox));
+        Transform {
+            ptr,
+            matrix,
+            inverse,
+            normal_matrix,
+            bbox,
+        }
+    }
+
+    /// The ray in object space. The direction is left unnormalized, so that t is the same in
+    /// both spaces.
+    fn local_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.inverse.transform_point(&ray.origin),
+            self.inverse.transform_vector(&ray.direction),
+            ray.time,
+        )
+    }
+}
+
+/// The box bounding all eight corners of `bbox` after they've been transformed by `matrix`.
+pub(super) fn transform_bounding_box(matrix: &Mat4, bbox: &AABB) -> AABB {
+    let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+    let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let corner = Point3::new(
+                    if i == 1 {
+                        bbox.maximum.x
+                    } else {
+                        bbox.minimum.x
+                    },
+                    if j == 1 {
+                        bbox.maximum.y
+                    } else {
+                        bbox.minimum.y
+                    },
+                    if k == 1 {
+                        bbox.maximum.z
+                    } else {
+                        bbox.minimum.z
+                    },
+                );
+                let tester = matrix.transform_point(&corner);
+                for c in 0..3 {
+                    min[c] = f64::min(min[c], tester[c]);
+                    max[c] = f64::max(max[c], tester[c]);
+                }
+            }
+        }
+    }
+    AABB {
+        minimum: min,
+        maximum: max,
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let rec = self.ptr.hit(&self.local_ray(ray), t_min, t_max)?;
+        let outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let mut transformed = HitRecord::new(
+            ray,
+            rec.t,
+            self.matrix.transform_point(&rec.p),
+            self.normal_matrix
+                .transform_vector(&outward_normal)
+                .unit_vector(),
+            rec.material_rc,
+            rec.u,
+            rec.v,
+        );
+        transformed.tangent = rec
+            .tangent
+            .map(|tangent| self.matrix.transform_vector(&tangent).unit_vector());
+        Some(transformed)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.ptr.occluded(&self.local_ray(ray), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.bbox.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Sphere, Vec3};
+    use crate::hittable::instance::Translate;
+    use crate::hittable::test_util::grey;
+
+    fn get_sphere() -> Box<dyn Hittable> {
+        let material = grey();
+        Box::new(Sphere::new(Point3::new(0., 0., 0.), 1., material))
+    }
+
+    #[test]
+    fn test_transform_hit() {
+        // squash the unit sphere into an ellipsoid and stand it up on the y-axis
+        let matrix = Mat4::identity()
+            .scale(&Vec3::new(1., 3., 1.))
+            .translate(&Vec3::new(0., 3., 0.));
+        let ellipsoid = Transform::new(get_sphere(), matrix);
+
+        let r = Ray::new(Point3::new(0., 10., 0.), Vec3::new(0., -2., 0.), 0.);
+        let rec = ellipsoid.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.) < Vec3::TOL);
+        assert_eq!(rec.p, Point3::new(0., 6., 0.));
+        assert!(ellipsoid.occluded(&r, 0., 2.5));
+        assert!(!ellipsoid.occluded(&r, 0., 1.5));
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+        assert!(rec.front_face);
+
+        // normals must be carried over by the inverse-transpose to stay perpendicular to the
+        // surface: on the ellipse x^2 + y^2 / 9 = 1 the normal is parallel to (x, y / 9)
+        let r = Ray::new(
+            Point3::new(10., 3. + 1.5 * f64::sqrt(3.), 0.),
+            Vec3::new(-1., 0., 0.),
+            0.,
+        );
+        let rec = ellipsoid.hit(&r, 0., f64::INFINITY).unwrap();
+        assert_eq!(rec.p, Point3::new(0.5, 3. + 1.5 * f64::sqrt(3.), 0.));
+        assert_eq!(
+            rec.normal,
+            Vec3::new(0.5, 1.5 * f64::sqrt(3.) / 9., 0.).unit_vector()
+        );
+
+        let r = Ray::new(Point3::new(2., 10., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(ellipsoid.hit(&r, 0., f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_transform_hit_from_inside() {
+        let matrix = Mat4::rotation(&Vec3::new(1., 0., 0.), 45.).scale(&Vec3::new(2., 2., 2.));
+        let sphere = Transform::new(get_sphere(), matrix);
+
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 1.), 0.);
+        let rec = sphere.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 2.) < Vec3::TOL);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn test_transform_bounding_box() {
+        let matrix = Mat4::rotation(&Vec3::new(0., 0., 1.), 45.).scale(&Vec3::new(1., 1., 2.));
+        let rotated = Transform::new(
+            Box::new(Translate::new(get_sphere(), Vec3::new(1., 0., 0.))),
+            matrix,
+        );
+        let bbox = rotated.bounding_box().unwrap();
+        let half_diag = f64::sqrt(2.) / 2.;
+        assert_eq!(
+            *bbox,
+            AABB {
+                minimum: Vec3::new(-half_diag, -half_diag, -2.),
+                maximum: Vec3::new(3. * half_diag, 3. * half_diag, 2.),
+            }
+        );
+    }
+}
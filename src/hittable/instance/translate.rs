@@ -5,42 +5,91 @@ use crate::hittable::{Hittable, AABB};
 
 pub struct Translate {
     ptr: Box<dyn Hittable>,
-    offset: Vec3,
-    bbox: AABB,
+    offset0: Vec3,
+    offset1: Vec3,
+    time0: f64,
+    time1: f64,
+    bbox: Option<AABB>,
 }
 
 impl Translate {
     pub fn new(ptr: Box<dyn Hittable>, offset: Vec3) -> Translate {
-        let ptr_bbox = ptr.bounding_box().unwrap();
-        let bbox = AABB {
-            minimum: &ptr_bbox.minimum + &offset,
-            maximum: &ptr_bbox.maximum + &offset,
-        };
-        Translate { ptr, offset, bbox }
+        Translate::moving(ptr, offset.clone(), offset, 0., 0.)
+    }
+
+    /// Translate `ptr` by an offset that moves linearly from `offset0` at `time0` to `offset1` at
+    /// `time1`.
+    pub fn moving(
+        ptr: Box<dyn Hittable>,
+        offset0: Vec3,
+        offset1: Vec3,
+        time0: f64,
+        time1: f64,
+    ) -> Translate {
+        let bbox = ptr.bounding_box().map(|ptr_bbox| {
+            let box0 = AABB {
+                minimum: &ptr_bbox.minimum + &offset0,
+                maximum: &ptr_bbox.maximum + &offset0,
+            };
+            let box1 = AABB {
+                minimum: &ptr_bbox.minimum + &offset1,
+                maximum: &ptr_bbox.maximum + &offset1,
+            };
+            AABB::surrounding_box(&box0, &box1)
+        });
+        Translate {
+            ptr,
+            offset0,
+            offset1,
+            time0,
+            time1,
+            bbox,
+        }
+    }
+
+    fn offset(&self, time: f64) -> Vec3 {
+        if self.time1 == self.time0 {
+            return self.offset0.clone();
+        }
+        let fraction = (time - self.time0) / (self.time1 - self.time0);
+        &self.offset0 + fraction * (&self.offset1 - &self.offset0)
     }
 }
 
 impl Hittable for Translate {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let ray_moved = Ray::new(&ray.origin - &self.offset, ray.direction.clone());
+        let offset = self.offset(ray.time);
+        let ray_moved = Ray::new(&ray.origin - &offset, ray.direction.clone(), ray.time);
         return match self.ptr.hit(&ray_moved, t_min, t_max) {
-            Some(rec) => Some(HitRecord::new(
-                &ray_moved,
-                rec.t,
-                rec.p + &self.offset,
-                rec.normal,
-                rec.material_rc,
-                rec.u,
-                rec.v,
-            )),
+            Some(rec) => {
+                let mut moved = HitRecord::new(
+                    &ray_moved,
+                    rec.t,
+                    rec.p + &offset,
+                    // undo the flip towards the ray, so the front face is recomputed correctly
+                    if rec.front_face {
+                        rec.normal
+                    } else {
+                        -rec.normal
+                    },
+                    rec.material_rc,
+                    rec.u,
+                    rec.v,
+                );
+                moved.tangent = rec.tangent;
+                Some(moved)
+            }
             None => None,
         };
     }
 
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let offset = self.offset(ray.time);
+        let ray_moved = Ray::new(&ray.origin - &offset, ray.direction.clone(), ray.time);
+        self.ptr.occluded(&ray_moved, t_min, t_max)
+    }
+
     fn bounding_box(&self) -> Option<&AABB> {
-        return match self.ptr.bounding_box() {
-            Some(_) => Some(&self.bbox),
-            None => None,
-        };
+        self.bbox.as_ref()
     }
 }
@@ -0,0 +1,432 @@
+use std::rc::Rc;
+
+use super::mailbox::Mailbox;
+use super::stats::{self, BVHStats};
+use super::{HitRecord, Hittable, AABB};
+use crate::geometry::Ray;
+
+#[derive(Clone, Debug)]
+pub struct KdTreeOptions {
+    /// The most objects a leaf holds, unless splitting it further wouldn't be worth it.
+    pub max_leaf_size: usize,
+    /// How deep the tree can get, or `None` for 8 + 1.3 log₂ n, as PBRT does.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for KdTreeOptions {
+    fn default() -> Self {
+        KdTreeOptions {
+            max_leaf_size: 1,
+            max_depth: None,
+        }
+    }
+}
+
+/// The cost of stepping down a node, relative to `INTERSECT_COST`.
+const TRAVERSAL_COST: f64 = 1.;
+/// The cost of testing a ray against an object.
+const INTERSECT_COST: f64 = 80.;
+/// How much cheaper a split is made to look for each side it leaves empty, as empty space is
+/// quick to skip.
+const EMPTY_BONUS: f64 = 0.5;
+
+/// A node of a `KdTree`, found by index rather than pointer.
+struct KdNode {
+    // where a branch is split
+    split: f64,
+    // for a leaf, the index of its first object in indices; for a branch, the index of the child
+    // above the split, the one below coming straight after the branch
+    offset: u32,
+    // how many objects a leaf has
+    count: u32,
+    // the axis a branch is split along, or 3 for a leaf
+    axis: u8,
+}
+
+/// One side of an object's box along an axis, as a place to split.
+struct Edge {
+    t: f64,
+    index: u32,
+    start: bool,
+}
+
+/// A kd-tree, which cuts space in two with an axis-aligned plane at each node and puts each
+/// object on whichever sides of it its box reaches, following Wald and Havran's SAH build as
+/// PBRT has it.
+///
+/// Unlike a BVH, the children never overlap, so a ray can visit the leaves strictly in order and
+/// stop at the first with a hit inside it. The price is that an object can be in more than one
+/// leaf, so the tree can take more memory and a ray has to keep track of which objects it's already
+/// been tested against.
+pub struct KdTree {
+    bbox: AABB,
+    nodes: Vec<KdNode>,
+    // the objects in each leaf, one run after another
+    indices: Vec<u32>,
+    objects: Vec<Rc<dyn Hittable>>,
+    mailbox: Mailbox,
+}
+
+impl KdTree {
+    /// How many nodes a ray can have left to visit, which limits how deep the tree can be.
+    const STACK_SIZE: usize = 64;
+
+    /// Every object must have a bounding box; a `World` keeps the ones that don't to one side.
+    pub fn new(objects: &[Rc<dyn Hittable>], options: &KdTreeOptions) -> KdTree {
+        assert!(!objects.is_empty(), "KdTree needs at least one object");
+        let boxes: Vec<AABB> = objects
+            .iter()
+            .map(|object| {
+                object
+                    .bounding_box()
+                    .expect("No bounding box in KdTree constructor")
+                    .clone()
+            })
+            .collect();
+        let bbox = boxes
+            .iter()
+            .skip(1)
+            .fold(boxes[0].clone(), |bbox, b| AABB::surrounding_box(&bbox, b));
+        let max_depth = options
+            .max_depth
+            .unwrap_or_else(|| (8. + 1.3 * f64::log2(objects.len() as f64)).round() as usize)
+            .min(KdTree::STACK_SIZE);
+
+        let mut tree = KdTree {
+            bbox: bbox.clone(),
+            nodes: Vec::new(),
+            indices: Vec::new(),
+            objects: objects.to_vec(),
+            mailbox: Mailbox::new(objects.len()),
+        };
+        let all = (0..objects.len() as u32).collect();
+        tree.build(&bbox, &boxes, all, max_depth, 0, options);
+        tree
+    }
+
+    /// Adds the nodes of the tree over the objects `indices`, which fit in `bounds`, starting with
+    /// its root. `depth` is how many more levels it can have, and `bad_refines` how many splits on
+    /// the way down made things worse.
+    fn build(
+        &mut self,
+        bounds: &AABB,
+        boxes: &[AABB],
+        indices: Vec<u32>,
+        depth: usize,
+        bad_refines: usize,
+        options: &KdTreeOptions,
+    ) {
+        let n = indices.len();
+        // with no area, there's no telling which side of a split a ray is more likely to go
+        if n <= options.max_leaf_size || depth == 0 || bounds.surface_area() <= 0. {
+            self.add_leaf(&indices);
+            return;
+        }
+
+        // try the longest axis first, and the others only if there's nowhere to split along it
+        let extent = &bounds.maximum - &bounds.minimum;
+        let mut axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+        let inv_area = 1. / bounds.surface_area();
+        let old_cost = INTERSECT_COST * n as f64;
+        let mut best: Option<(usize, f64)> = None;
+        let mut edges = Vec::with_capacity(2 * n);
+        for _ in 0..3 {
+            edges.clear();
+            for &index in indices.iter() {
+                let b = &boxes[index as usize];
+                edges.push(Edge {
+                    t: b.minimum[axis],
+                    index,
+                    start: true,
+                });
+                edges.push(Edge {
+                    t: b.maximum[axis],
+                    index,
+                    start: false,
+                });
+            }
+            // where a box starts and another ends at the same place, the start comes first
+            edges.sort_by(|a, b| a.t.total_cmp(&b.t).then(b.start.cmp(&a.start)));
+
+            let (other0, other1) = ((axis + 1) % 3, (axis + 2) % 3);
+            let (below_min, above_max) = (bounds.minimum[axis], bounds.maximum[axis]);
+            let (mut below, mut above) = (0, n);
+            for (i, edge) in edges.iter().enumerate() {
+                if !edge.start {
+                    above -= 1;
+                }
+                if below_min < edge.t && edge.t < above_max {
+                    let side = |length: f64| {
+                        2. * (extent[other0] * extent[other1]
+                            + length * (extent[other0] + extent[other1]))
+                    };
+                    let p_below = side(edge.t - below_min) * inv_area;
+                    let p_above = side(above_max - edge.t) * inv_area;
+                    let bonus = if below == 0 || above == 0 {
+                        EMPTY_BONUS
+                    } else {
+                        0.
+                    };
+                    let cost = TRAVERSAL_COST
+                        + INTERSECT_COST
+                            * (1. - bonus)
+                            * (p_below * below as f64 + p_above * above as f64);
+                    if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+                        best = Some((i, cost));
+                    }
+                }
+                if edge.start {
+                    below += 1;
+                }
+            }
+            if best.is_some() {
+                break;
+            }
+            axis = (axis + 1) % 3;
+        }
+
+        let (split_at, cost) = match best {
+            Some(best) => best,
+            None => {
+                self.add_leaf(&indices);
+                return;
+            }
+        };
+        let bad_refines = bad_refines + (cost > old_cost) as usize;
+        if (cost > 4. * old_cost && n < 16) || bad_refines == 3 {
+            self.add_leaf(&indices);
+            return;
+        }
+
+        // objects that start before the split go below it, and ones that end after it above
+        let below: Vec<u32> = edges[..split_at]
+            .iter()
+            .filter(|edge| edge.start)
+            .map(|edge| edge.index)
+            .collect();
+        let above: Vec<u32> = edges[split_at + 1..]
+            .iter()
+            .filter(|edge| !edge.start)
+            .map(|edge| edge.index)
+            .collect();
+        let split = edges[split_at].t;
+        let mut below_bounds = bounds.clone();
+        below_bounds.maximum[axis] = split;
+        let mut above_bounds = bounds.clone();
+        above_bounds.minimum[axis] = split;
+
+        let index = self.nodes.len();
+        self.nodes.push(KdNode {
+            split,
+            offset: 0,
+            count: 0,
+            axis: axis as u8,
+        });
+        self.build(&below_bounds, boxes, below, depth - 1, bad_refines, options);
+        self.nodes[index].offset = self.nodes.len() as u32;
+        self.build(&above_bounds, boxes, above, depth - 1, bad_refines, options);
+    }
+
+    fn add_leaf(&mut self, indices: &[u32]) {
+        self.nodes.push(KdNode {
+            split: 0.,
+            offset: self.indices.len() as u32,
+            count: indices.len() as u32,
+            axis: 3,
+        });
+        self.indices.extend_from_slice(indices);
+    }
+
+    pub fn stats(&self) -> BVHStats {
+        let mut stats = BVHStats::default();
+        stats.sah_cost = self.add_stats(0, &self.bbox, 0, &mut stats);
+        stats
+    }
+
+    /// Adds the node at `index`, which fills `bounds`, and the ones under it, to `stats`, and
+    /// returns their SAH cost, counted the same way as a BVH's.
+    fn add_stats(&self, index: usize, bounds: &AABB, depth: usize, stats: &mut BVHStats) -> f64 {
+        let node = &self.nodes[index];
+        if node.axis == 3 {
+            stats.add_leaf(depth, node.count as usize);
+            return node.count as f64;
+        }
+        stats.add_branch();
+        let axis = node.axis as usize;
+        let mut below = bounds.clone();
+        below.maximum[axis] = node.split;
+        let mut above = bounds.clone();
+        above.minimum[axis] = node.split;
+        let below_cost = self.add_stats(index + 1, &below, depth + 1, stats);
+        let above_cost = self.add_stats(node.offset as usize, &above, depth + 1, stats);
+        super::bvh::branch_cost(bounds, &[(&below, below_cost), (&above, above_cost)])
+    }
+
+    /// Walks the ray through the leaves between `t_min` and `t_max`, nearest first, calling
+    /// `visit` with each one's objects and where the ray leaves it, until `visit` returns true.
+    fn traverse(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut visit: impl FnMut(&[u32], f64) -> bool,
+    ) {
+        let (mut t0, mut t1) = match self.bbox.intersect(ray, t_min, t_max) {
+            Some(t) => t,
+            None => return,
+        };
+
+        let mut stack = [(0u32, 0., 0.); KdTree::STACK_SIZE];
+        let mut stack_len = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            stats::count_box_test();
+            if node.axis < 3 {
+                let axis = node.axis as usize;
+                let origin = ray.origin[axis];
+                let t_plane = (node.split - origin) / ray.direction[axis];
+
+                // the child on the side the ray starts from comes first
+                let below_first =
+                    origin < node.split || (origin == node.split && ray.direction[axis] <= 0.);
+                let (first, second) = if below_first {
+                    (index + 1, node.offset as usize)
+                } else {
+                    (node.offset as usize, index + 1)
+                };
+
+                if t_plane > t1 || t_plane <= 0. {
+                    // it doesn't get to the plane before leaving the node
+                    index = first;
+                } else if t_plane < t0 {
+                    // it's already crossed the plane
+                    index = second;
+                } else {
+                    stack[stack_len] = (second as u32, t_plane, t1);
+                    stack_len += 1;
+                    index = first;
+                    t1 = t_plane;
+                }
+                continue;
+            }
+
+            let first = node.offset as usize;
+            if visit(&self.indices[first..first + node.count as usize], t1) || stack_len == 0 {
+                return;
+            }
+            stack_len -= 1;
+            let (next, next_t0, next_t1) = stack[stack_len];
+            index = next as usize;
+            t0 = next_t0;
+            t1 = next_t1;
+        }
+    }
+}
+
+impl Hittable for KdTree {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest_so_far = t_max;
+        let mut rec = None;
+        let ray_id = self.mailbox.next_ray();
+        self.traverse(ray, t_min, t_max, |objects, t_leave| {
+            // an object tested in an earlier leaf would miss now, or hit where it did then
+            for &index in objects {
+                if !self.mailbox.first_test(index, ray_id) {
+                    continue;
+                }
+                stats::count_primitive_tests(1);
+                if let Some(temp_rec) = self.objects[index as usize].hit(ray, t_min, closest_so_far)
+                {
+                    closest_so_far = temp_rec.t;
+                    rec = Some(temp_rec);
+                }
+            }
+            // a hit beyond this leaf might still be beaten by something in the next one
+            closest_so_far <= t_leave
+        });
+        rec
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut occluded = false;
+        let ray_id = self.mailbox.next_ray();
+        self.traverse(ray, t_min, t_max, |objects, _| {
+            occluded = objects.iter().any(|&index| {
+                if !self.mailbox.first_test(index, ray_id) {
+                    return false;
+                }
+                stats::count_primitive_tests(1);
+                self.objects[index as usize].occluded(ray, t_min, t_max)
+            });
+            occluded
+        });
+        occluded
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Point3, Sphere, Vec3};
+    use crate::hittable::test_util::{grey, sphere_row};
+
+    #[test]
+    fn test_kd_tree() {
+        let material = grey();
+        // a row of spheres, and a pile of them all in the same place, which can't be split
+        let mut spheres = sphere_row(20);
+        spheres.extend((0..10).map(|_| {
+            Rc::new(Sphere::new(Point3::new(100., 0., 0.), 1., material.clone()))
+                as Rc<dyn Hittable>
+        }));
+        let tree = KdTree::new(&spheres, &KdTreeOptions::default());
+
+        let stats = tree.stats();
+        assert_eq!(stats.node_count, tree.nodes.len());
+        assert_eq!(stats.node_count, 2 * stats.leaf_count - 1);
+        assert!(stats.object_count >= spheres.len());
+        assert!(stats.max_depth() <= KdTree::STACK_SIZE);
+
+        for i in 0..20 {
+            let ray = Ray::new(
+                Point3::new(i as f64 * 3., 5., 0.),
+                Vec3::new(0., -1., 0.),
+                0.,
+            );
+            let rec = tree.hit(&ray, 0., f64::INFINITY).unwrap();
+            assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        }
+        let ray = Ray::new(Point3::new(100., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(f64::abs(tree.hit(&ray, 0., f64::INFINITY).unwrap().t - 4.) < Vec3::TOL);
+        let ray = Ray::new(Point3::new(31.5, 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(f64::abs(tree.hit(&ray, 0., f64::INFINITY).unwrap().t - 0.5) < Vec3::TOL);
+        let ray = Ray::new(Point3::new(200., 0., 0.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(f64::abs(tree.hit(&ray, 0., f64::INFINITY).unwrap().t - 99.) < Vec3::TOL);
+        assert!(tree.occluded(&ray, 0., 100.));
+        assert!(!tree.occluded(&ray, 0., 98.));
+
+        let ray = Ray::new(Point3::new(-10., 2., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(tree.hit(&ray, 0., f64::INFINITY).is_none());
+
+        let shallow = KdTree::new(
+            &spheres,
+            &KdTreeOptions {
+                max_depth: Some(2),
+                ..KdTreeOptions::default()
+            },
+        );
+        assert!(shallow.stats().max_depth() <= 2);
+    }
+}
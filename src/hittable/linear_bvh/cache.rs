@@ -0,0 +1,454 @@
+//! A cache file for a built `LinearBVH`, so that a big scene's tree can be loaded on later renders
+//! instead of being built again.
+//!
+//! The objects themselves aren't stored, only the tree's nodes and the order the objects go in,
+//! so loading needs the same objects the tree was built over. The file carries a hash of their
+//! bounding boxes and the options the tree was built with, and is only used if it matches.
+//!
+//! Everything is little-endian:
+//!
+//! | offset | size | contents                                                               |
+//! |--------|------|------------------------------------------------------------------------|
+//! | 0      | 4    | the magic bytes `RTBV`                                                 |
+//! | 4      | 4    | the version, a `u32`, which is 2                                       |
+//! | 8      | 8    | the hash of the objects and options, a `u64`, from `scene_hash`        |
+//! | 16     | 4    | the number of objects n, a `u32`                                       |
+//! | 20     | 4    | the number of nodes m, a `u32`                                         |
+//! | 24     | 4·n  | for each place in the tree's leaves, the index of the object there     |
+//! | 24+4n  | 60·m | the nodes, each a box as six `f64`s, minimum x, y and z then maximum,  |
+//! |        |      | its offset and count, `u32`s, axis, a `u8`, and three padding bytes    |
+//!
+//! The nodes are in the order they're kept in memory, depth first, with the same meanings.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{LinearBVH, LinearNode};
+use crate::geometry::Point3;
+use crate::hittable::{BVHOptions, Hittable, SplitStrategy, AABB};
+
+const MAGIC: &[u8; 4] = b"RTBV";
+const VERSION: u32 = 2;
+
+/// A hash of everything a tree's shape depends on: its objects' boxes, in order, and the options
+/// it's built with. It's FNV-1a, which is quick and plenty to tell one scene from another, though
+/// not to stand up to anyone trying to make two scenes collide.
+pub fn scene_hash(objects: &[Rc<dyn Hittable>], options: &BVHOptions) -> u64 {
+    let mut hash = Fnv1a::new();
+    hash.write(&(objects.len() as u64).to_le_bytes());
+    for object in objects {
+        match object.bounding_box() {
+            Some(bbox) => {
+                for corner in [&bbox.minimum, &bbox.maximum].iter() {
+                    for axis in 0..3 {
+                        hash.write(&corner[axis].to_le_bytes());
+                    }
+                }
+            }
+            None => hash.write(&[0]),
+        }
+    }
+    let strategy: u8 = match options.strategy {
+        SplitStrategy::Median => 0,
+        SplitStrategy::Sah => 1,
+    };
+    hash.write(&[strategy]);
+    hash.write(&(options.max_leaf_size as u64).to_le_bytes());
+    hash.write(&(options.max_depth as u64).to_le_bytes());
+    hash.finish()
+}
+
+/// The 64-bit Fowler–Noll–Vo hash, FNV-1a.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Fnv1a {
+        Fnv1a(Fnv1a::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(Fnv1a::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// How `LinearBVH::load_or_build` came by its tree.
+#[derive(Debug)]
+pub enum CacheStatus {
+    /// It was loaded from the cache.
+    Loaded,
+    /// The cache couldn't be used, for the reason given, so the tree was built, and then saved
+    /// to the cache unless that failed too.
+    Built {
+        reason: io::Error,
+        saved: io::Result<()>,
+    },
+}
+
+impl LinearBVH {
+    /// Saves the tree to `path`, with the hash of the objects and options it was built with.
+    pub fn save<P: AsRef<Path>>(&self, path: P, hash: u64) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, hash)?;
+        writer.flush()
+    }
+
+    /// Loads the tree over `objects` from the cache at `path` if it was made for them and
+    /// `options`, and otherwise builds it and saves it there for next time.
+    pub fn load_or_build<P: AsRef<Path>>(
+        path: P,
+        objects: &[Rc<dyn Hittable>],
+        options: &BVHOptions,
+    ) -> (LinearBVH, CacheStatus) {
+        let hash = scene_hash(objects, options);
+        let loaded = File::open(&path)
+            .and_then(|file| LinearBVH::read(&mut BufReader::new(file), objects, options, hash));
+        match loaded {
+            Ok(bvh) => (bvh, CacheStatus::Loaded),
+            Err(reason) => {
+                let bvh = LinearBVH::new(objects, options);
+                let saved = bvh.save(&path, hash);
+                (bvh, CacheStatus::Built { reason, saved })
+            }
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, hash: u64) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&hash.to_le_bytes())?;
+        writer.write_all(&(self.objects.len() as u32).to_le_bytes())?;
+        writer.write_all(&(self.nodes.len() as u32).to_le_bytes())?;
+
+        // the slots the other way round: which object is in each place
+        let mut order = vec![0u32; self.slots.len()];
+        for (index, &slot) in self.slots.iter().enumerate() {
+            order[slot as usize] = index as u32;
+        }
+        for index in order {
+            writer.write_all(&index.to_le_bytes())?;
+        }
+
+        for node in self.nodes.iter() {
+            for corner in [&node.bbox.minimum, &node.bbox.maximum].iter() {
+                for axis in 0..3 {
+                    writer.write_all(&corner[axis].to_le_bytes())?;
+                }
+            }
+            writer.write_all(&node.offset.to_le_bytes())?;
+            writer.write_all(&node.count.to_le_bytes())?;
+            writer.write_all(&[node.axis, 0, 0, 0])?;
+        }
+        Ok(())
+    }
+
+    /// Reads a tree over `objects` with `options`, checking it was saved with `hash` and that its
+    /// nodes and object order hang together, so that a damaged file can't make a broken tree.
+    fn read<R: Read>(
+        reader: &mut R,
+        objects: &[Rc<dyn Hittable>],
+        options: &BVHOptions,
+        hash: u64,
+    ) -> io::Result<LinearBVH> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a BVH cache"));
+        }
+        let version = read_u32(reader)?;
+        if version != VERSION {
+            return Err(invalid_data(&format!("unsupported version {}", version)));
+        }
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        if u64::from_le_bytes(bytes) != hash {
+            return Err(invalid_data(
+                "the cache was made for different objects or options",
+            ));
+        }
+
+        let object_count = read_u32(reader)? as usize;
+        let node_count = read_u32(reader)? as usize;
+        if object_count != objects.len() || node_count == 0 || node_count >= 2 * object_count {
+            return Err(invalid_data("wrong number of objects or nodes"));
+        }
+
+        let mut slots = vec![u32::MAX; object_count];
+        let mut ordered = Vec::with_capacity(object_count);
+        for slot in 0..object_count {
+            let index = read_u32(reader)? as usize;
+            if index >= object_count || slots[index] != u32::MAX {
+                return Err(invalid_data("object order is not a permutation"));
+            }
+            slots[index] = slot as u32;
+            ordered.push(objects[index].clone());
+        }
+
+        let mut nodes = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let mut corners = [0.; 6];
+            for corner in corners.iter_mut() {
+                let mut bytes = [0; 8];
+                reader.read_exact(&mut bytes)?;
+                *corner = f64::from_le_bytes(bytes);
+            }
+            let offset = read_u32(reader)?;
+            let count = read_u32(reader)?;
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            nodes.push(LinearNode {
+                bbox: AABB {
+                    minimum: Point3::new(corners[0], corners[1], corners[2]),
+                    maximum: Point3::new(corners[3], corners[4], corners[5]),
+                },
+                offset,
+                count,
+                axis: bytes[0],
+            });
+        }
+        check_nodes(&nodes, object_count)?;
+
+        let mut bvh = LinearBVH {
+            nodes,
+            objects: ordered,
+            slots,
+            options: options.clone(),
+            built_cost: 0.,
+        };
+        bvh.built_cost = bvh.sah_cost();
+        Ok(bvh)
+    }
+}
+
+/// Checks that `nodes` make a tree, laid out as `LinearBVH` does, whose leaves hold each of
+/// `object_count` objects once, and that it's no deeper than a ray's stack allows.
+fn check_nodes(nodes: &[LinearNode], object_count: usize) -> io::Result<()> {
+    // walking the tree from the root in depth-first order should visit every node in turn, and
+    // every object in turn
+    let mut stack = vec![(0, 0)];
+    let (mut next_node, mut next_object) = (0, 0);
+    while let Some((index, depth)) = stack.pop() {
+        if index != next_node || index >= nodes.len() {
+            return Err(invalid_data("nodes are out of order"));
+        }
+        next_node += 1;
+        let node = &nodes[index];
+        if node.count > 0 {
+            if node.offset as usize != next_object {
+                return Err(invalid_data("leaves are out of order"));
+            }
+            next_object += node.count as usize;
+        } else {
+            let second = node.offset as usize;
+            if second <= index + 1 || second >= nodes.len() || node.axis > 2 {
+                return Err(invalid_data("a branch's children are out of place"));
+            }
+            // each branch a ray goes down can leave one child on its stack
+            if depth >= LinearBVH::STACK_SIZE {
+                return Err(invalid_data("the tree is too deep"));
+            }
+            stack.push((second, depth + 1));
+            stack.push((index + 1, depth + 1));
+        }
+    }
+    if next_node != nodes.len() || next_object != object_count {
+        return Err(invalid_data("nodes or objects are left over"));
+    }
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::Rng;
+
+    use crate::geometry::{Ray, Sphere};
+    use crate::hittable::test_util::{grey, random_point};
+
+    /// Loads the tree that was built over `objects` with `options` from `path`.
+    fn load<P: AsRef<Path>>(
+        path: P,
+        objects: &[Rc<dyn Hittable>],
+        options: &BVHOptions,
+    ) -> io::Result<LinearBVH> {
+        let hash = scene_hash(objects, options);
+        LinearBVH::read(
+            &mut BufReader::new(File::open(path)?),
+            objects,
+            options,
+            hash,
+        )
+    }
+
+    fn get_spheres(centers: &[Point3]) -> Vec<Rc<dyn Hittable>> {
+        let material = grey();
+        centers
+            .iter()
+            .map(|center| {
+                Rc::new(Sphere::new(center.clone(), 0.5, material.clone())) as Rc<dyn Hittable>
+            })
+            .collect()
+    }
+
+    fn random_centers(n: usize) -> Vec<Point3> {
+        (0..n).map(|_| random_point(20.)).collect()
+    }
+
+    #[test]
+    fn test_bvh_cache_round_trip() {
+        let mut rng = rand::thread_rng();
+        let objects = get_spheres(&random_centers(300));
+        let options = BVHOptions::default();
+        let hash = scene_hash(&objects, &options);
+        let bvh = LinearBVH::new(&objects, &options);
+
+        let mut bytes = Vec::new();
+        bvh.write(&mut bytes, hash).unwrap();
+        assert_eq!(bytes.len(), 24 + 4 * 300 + 60 * bvh.node_count());
+        let loaded = LinearBVH::read(&mut &bytes[..], &objects, &options, hash).unwrap();
+
+        assert_eq!(loaded.stats(), bvh.stats());
+        assert_eq!(loaded.slots, bvh.slots);
+        for _ in 0..1000 {
+            let origin = Point3::new(rng.gen_range(-30. ..30.), 30., rng.gen_range(-30. ..30.));
+            let target = Point3::new(rng.gen_range(-20. ..20.), 0., rng.gen_range(-20. ..20.));
+            let ray = Ray::new(origin.clone(), target - origin, 0.);
+            assert_eq!(
+                loaded.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t),
+                bvh.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bvh_cache_errors() {
+        let mut centers = random_centers(100);
+        let objects = get_spheres(&centers);
+        let options = BVHOptions::default();
+        let hash = scene_hash(&objects, &options);
+        let mut bytes = Vec::new();
+        LinearBVH::new(&objects, &options)
+            .write(&mut bytes, hash)
+            .unwrap();
+        let read = |bytes: &[u8], objects: &[Rc<dyn Hittable>], options: &BVHOptions| {
+            let hash = scene_hash(objects, options);
+            LinearBVH::read(&mut &bytes[..], objects, options, hash).err()
+        };
+        assert!(read(&bytes, &objects, &options).is_none());
+
+        // moving one object, or building with different options, changes the hash
+        centers[42].y += 0.001;
+        let moved = get_spheres(&centers);
+        assert_ne!(scene_hash(&moved, &options), hash);
+        let error = read(&bytes, &moved, &options).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let median = BVHOptions {
+            strategy: SplitStrategy::Median,
+            ..BVHOptions::default()
+        };
+        let error = read(&bytes, &objects, &median).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let error = read(&bad_magic, &objects, &options).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let truncated = &bytes[..bytes.len() - 1];
+        let error = read(truncated, &objects, &options).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // the same object in two places
+        let mut repeated = bytes.clone();
+        repeated.copy_within(24..28, 28);
+        let error = read(&repeated, &objects, &options).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // the root's offset pointing somewhere else, as a branch or as a leaf
+        let root = 24 + 4 * 100;
+        for &(offset, count) in [(1u32, 0u32), (5000, 0), (3, 1)].iter() {
+            let mut broken = bytes.clone();
+            broken[root + 48..root + 52].copy_from_slice(&offset.to_le_bytes());
+            broken[root + 52..root + 56].copy_from_slice(&count.to_le_bytes());
+            let error = read(&broken, &objects, &options).unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_bvh_cache_load_or_build() {
+        let path = std::env::temp_dir().join("raytracer_test_bvh_cache.rtbv");
+        let _ = std::fs::remove_file(&path);
+        let mut centers = random_centers(100);
+        let objects = get_spheres(&centers);
+        let options = BVHOptions::default();
+
+        let (built, status) = LinearBVH::load_or_build(&path, &objects, &options);
+        match status {
+            CacheStatus::Built { reason, saved } => {
+                assert_eq!(reason.kind(), io::ErrorKind::NotFound);
+                assert!(saved.is_ok());
+            }
+            CacheStatus::Loaded => panic!("loaded a cache that wasn't there"),
+        }
+        let (loaded, status) = LinearBVH::load_or_build(&path, &objects, &options);
+        assert!(matches!(status, CacheStatus::Loaded));
+        assert_eq!(loaded.stats(), built.stats());
+        assert!(load(&path, &objects, &options).is_ok());
+
+        // a changed scene is built again, and the cache made over for it
+        centers[0].x += 1.;
+        let objects = get_spheres(&centers);
+        let (_, status) = LinearBVH::load_or_build(&path, &objects, &options);
+        match status {
+            CacheStatus::Built { reason, saved } => {
+                assert_eq!(reason.kind(), io::ErrorKind::InvalidData);
+                assert!(saved.is_ok());
+            }
+            CacheStatus::Loaded => panic!("loaded a cache for a different scene"),
+        }
+        assert!(load(&path, &objects, &options).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_bvh_cache() {
+        // cargo test --release bench_bvh_cache -- --ignored --nocapture
+        use std::time::Instant;
+
+        let path = std::env::temp_dir().join("raytracer_bench_bvh_cache.rtbv");
+        let objects = get_spheres(&random_centers(500_000));
+        let options = BVHOptions::default();
+
+        let start = Instant::now();
+        let bvh = LinearBVH::new(&objects, &options);
+        println!("built in {:?}", start.elapsed());
+        bvh.save(&path, scene_hash(&objects, &options)).unwrap();
+        let start = Instant::now();
+        load(&path, &objects, &options).unwrap();
+        println!("loaded in {:?}", start.elapsed());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,471 @@
+use std::rc::Rc;
+
+use crate::geometry::{Point3, Ray, Vec3};
+use crate::hittable::bvh::{self, BuildItem};
+use crate::hittable::stats::{self, BVHStats};
+use crate::hittable::{BVHOptions, HitRecord, Hittable, AABB};
+
+mod cache;
+pub use cache::CacheStatus;
+
+/// A node of a `LinearBVH`, with its children or objects found by index rather than pointer.
+struct LinearNode {
+    bbox: AABB,
+    // for a leaf, the index of its first object; for a branch, the index of its second child, the
+    // first coming straight after the branch
+    offset: u32,
+    // how many objects a leaf has, or 0 for a branch
+    count: u32,
+    // the axis a branch's children were split along
+    axis: u8,
+}
+
+/// A bounding volume hierarchy laid out flat in one array, in depth-first order.
+///
+/// It's built the same way as a `BVHNode`, but the nodes sit next to each other in memory with
+/// no pointers between them, and each leaf's objects are a run of one array. A ray walks it with
+/// a small stack instead of recursive calls, and visits the child on the side it's coming from
+/// first, so it can often skip the other child once it's found something closer.
+///
+/// For animation, objects can be swapped for moved versions of themselves and the boxes refitted
+/// around them, which is much quicker than building a new tree but makes it worse the further
+/// things move from where it was built; `update` rebuilds it once it's got too bad.
+pub struct LinearBVH {
+    nodes: Vec<LinearNode>,
+    objects: Vec<Rc<dyn Hittable>>,
+    // where each object given to new is in objects
+    slots: Vec<u32>,
+    options: BVHOptions,
+    // the SAH cost when the tree was built
+    built_cost: f64,
+}
+
+impl LinearBVH {
+    /// How many nodes a ray can have left to visit, which limits how deep the tree can be.
+    const STACK_SIZE: usize = 64;
+
+    /// Every object must have a bounding box; a `World` keeps the ones that don't to one side.
+    pub fn new(objects: &[Rc<dyn Hittable>], options: &BVHOptions) -> LinearBVH {
+        assert!(!objects.is_empty(), "LinearBVH needs at least one object");
+        assert!(options.max_depth <= LinearBVH::STACK_SIZE);
+        assert!(objects.len() <= u32::MAX as usize);
+
+        let mut items = BuildItem::from_objects(objects);
+        let mut bvh = LinearBVH {
+            nodes: Vec::with_capacity(2 * objects.len()),
+            objects: Vec::with_capacity(objects.len()),
+            slots: vec![0; objects.len()],
+            options: options.clone(),
+            built_cost: 0.,
+        };
+        bvh.build(&mut items, objects, options, 0);
+        bvh.built_cost = bvh.sah_cost();
+        bvh
+    }
+
+    /// Adds the nodes of the tree over `items`, starting with its root.
+    fn build(
+        &mut self,
+        items: &mut [BuildItem],
+        objects: &[Rc<dyn Hittable>],
+        options: &BVHOptions,
+        depth: usize,
+    ) {
+        let bbox = BuildItem::bbox(items);
+        let index = self.nodes.len();
+        match bvh::split(items, &bbox, options, depth) {
+            Some((n, axis)) => {
+                self.nodes.push(LinearNode {
+                    bbox,
+                    offset: 0,
+                    count: 0,
+                    axis: axis as u8,
+                });
+                let (left, right) = items.split_at_mut(n);
+                self.build(left, objects, options, depth + 1);
+                self.nodes[index].offset = self.nodes.len() as u32;
+                self.build(right, objects, options, depth + 1);
+            }
+            None => {
+                self.nodes.push(LinearNode {
+                    bbox,
+                    offset: self.objects.len() as u32,
+                    count: items.len() as u32,
+                    axis: 0,
+                });
+                for item in items.iter() {
+                    self.slots[item.index] = self.objects.len() as u32;
+                    self.objects.push(objects[item.index].clone());
+                }
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Puts `object` in place of the one that was `objects[index]` when the tree was built. The
+    /// boxes aren't fitted to it until `refit` or `update`.
+    pub fn replace(&mut self, index: usize, object: Rc<dyn Hittable>) {
+        assert!(
+            object.bounding_box().is_some(),
+            "No bounding box in LinearBVH::replace"
+        );
+        self.objects[self.slots[index] as usize] = object;
+    }
+
+    /// Fits every node's box to its objects where they are now, keeping the shape of the tree.
+    pub fn refit(&mut self) {
+        // children always come after their parents
+        for index in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[index];
+            let bbox = if node.count > 0 {
+                let first = node.offset as usize;
+                let objects = &self.objects[first..first + node.count as usize];
+                objects.iter().skip(1).fold(
+                    objects[0].bounding_box().unwrap().clone(),
+                    |bbox, object| AABB::surrounding_box(&bbox, object.bounding_box().unwrap()),
+                )
+            } else {
+                AABB::surrounding_box(
+                    &self.nodes[index + 1].bbox,
+                    &self.nodes[node.offset as usize].bbox,
+                )
+            };
+            self.nodes[index].bbox = bbox;
+        }
+    }
+
+    /// Refits the tree, or rebuilds it if refitting leaves its SAH cost more than `rebuild_ratio`
+    /// times what it was when it was built. Returns whether it was rebuilt.
+    pub fn update(&mut self) -> bool {
+        self.refit();
+        if self.sah_cost() <= self.options.rebuild_ratio * self.built_cost {
+            return false;
+        }
+        let objects: Vec<_> = self
+            .slots
+            .iter()
+            .map(|&slot| self.objects[slot as usize].clone())
+            .collect();
+        *self = LinearBVH::new(&objects, &self.options);
+        true
+    }
+
+    /// The expected cost of a ray hitting the tree under the Surface Area Heuristic, counting one
+    /// for each object tested.
+    pub fn sah_cost(&self) -> f64 {
+        let mut costs = vec![0.; self.nodes.len()];
+        for index in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[index];
+            costs[index] = if node.count > 0 {
+                node.count as f64
+            } else {
+                let second = node.offset as usize;
+                bvh::branch_cost(
+                    &node.bbox,
+                    &[
+                        (&self.nodes[index + 1].bbox, costs[index + 1]),
+                        (&self.nodes[second].bbox, costs[second]),
+                    ],
+                )
+            };
+        }
+        costs[0]
+    }
+
+    pub fn stats(&self) -> BVHStats {
+        let mut stats = BVHStats {
+            sah_cost: self.sah_cost(),
+            ..BVHStats::default()
+        };
+        // each node's depth, filled in from its parent's as the array is walked in order
+        let mut depths = vec![0; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.count > 0 {
+                stats.add_leaf(depths[index], node.count as usize);
+            } else {
+                stats.add_branch();
+                depths[index + 1] = depths[index] + 1;
+                depths[node.offset as usize] = depths[index] + 1;
+            }
+        }
+        stats
+    }
+}
+
+/// The ray's direction with each component inverted, for `hit_box`.
+fn inverse_direction(ray: &Ray) -> Vec3 {
+    Vec3::new(
+        1. / ray.direction.x,
+        1. / ray.direction.y,
+        1. / ray.direction.z,
+    )
+}
+
+/// Whether a ray from `origin`, with each component of its direction inverted, goes through
+/// `bbox` between `t_min` and `t_max`.
+fn hit_box(bbox: &AABB, origin: &Point3, inv_direction: &Vec3, t_min: f64, t_max: f64) -> bool {
+    let (mut t_min, mut t_max) = (t_min, t_max);
+    for axis in 0..3 {
+        let mut t0 = (bbox.minimum[axis] - origin[axis]) * inv_direction[axis];
+        let mut t1 = (bbox.maximum[axis] - origin[axis]) * inv_direction[axis];
+        if inv_direction[axis] < 0. {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        t_min = f64::max(t0, t_min);
+        t_max = f64::min(t1, t_max);
+        if t_max <= t_min {
+            return false;
+        }
+    }
+    true
+}
+
+impl Hittable for LinearBVH {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let inv_direction = inverse_direction(ray);
+        let negative = [
+            inv_direction.x < 0.,
+            inv_direction.y < 0.,
+            inv_direction.z < 0.,
+        ];
+
+        let mut closest_so_far = t_max;
+        let mut rec = None;
+        let mut stack = [0u32; LinearBVH::STACK_SIZE];
+        let mut stack_len = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            stats::count_box_test();
+            if hit_box(
+                &node.bbox,
+                &ray.origin,
+                &inv_direction,
+                t_min,
+                closest_so_far,
+            ) {
+                if node.count > 0 {
+                    let first = node.offset as usize;
+                    stats::count_primitive_tests(node.count as usize);
+                    for object in &self.objects[first..first + node.count as usize] {
+                        if let Some(temp_rec) = object.hit(ray, t_min, closest_so_far) {
+                            closest_so_far = temp_rec.t;
+                            rec = Some(temp_rec);
+                        }
+                    }
+                } else if negative[node.axis as usize] {
+                    // the second child is nearer, so come back to the first one
+                    stack[stack_len] = index as u32 + 1;
+                    stack_len += 1;
+                    index = node.offset as usize;
+                    continue;
+                } else {
+                    stack[stack_len] = node.offset;
+                    stack_len += 1;
+                    index += 1;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                return rec;
+            }
+            stack_len -= 1;
+            index = stack[stack_len] as usize;
+        }
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let inv_direction = inverse_direction(ray);
+
+        // with any hit good enough, the order the children are visited in doesn't matter
+        let mut stack = [0u32; LinearBVH::STACK_SIZE];
+        let mut stack_len = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            stats::count_box_test();
+            if hit_box(&node.bbox, &ray.origin, &inv_direction, t_min, t_max) {
+                if node.count == 0 {
+                    stack[stack_len] = node.offset;
+                    stack_len += 1;
+                    index += 1;
+                    continue;
+                }
+                let first = node.offset as usize;
+                if self.objects[first..first + node.count as usize]
+                    .iter()
+                    .any(|object| {
+                        stats::count_primitive_tests(1);
+                        object.occluded(ray, t_min, t_max)
+                    })
+                {
+                    return true;
+                }
+            }
+
+            if stack_len == 0 {
+                return false;
+            }
+            stack_len -= 1;
+            index = stack[stack_len] as usize;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.nodes[0].bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::Rng;
+
+    use crate::geometry::Sphere;
+    use crate::hittable::test_util::{grey, random_point, random_ray, random_spheres, sphere_row};
+    use crate::hittable::{BVHNode, HittableList, SplitStrategy};
+
+    #[test]
+    fn test_linear_bvh_hits_match_tree() {
+        let spheres = random_spheres(500);
+        for strategy in [SplitStrategy::Median, SplitStrategy::Sah].iter() {
+            let options = BVHOptions {
+                strategy: *strategy,
+                ..BVHOptions::default()
+            };
+            let tree = BVHNode::with_options(&spheres, &options);
+            let linear = LinearBVH::new(&spheres, &options);
+            assert_eq!(linear.objects.len(), spheres.len());
+            assert_eq!(linear.bounding_box(), tree.bounding_box());
+
+            for _ in 0..2000 {
+                let ray = random_ray();
+                let expected = tree.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                let actual = linear.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                assert_eq!(expected, actual);
+                assert_eq!(
+                    tree.occluded(&ray, 0.001, 60.),
+                    linear.occluded(&ray, 0.001, 60.)
+                );
+                assert_eq!(
+                    linear.occluded(&ray, 0.001, f64::INFINITY),
+                    expected.is_some()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_linear_bvh_refit() {
+        let mut rng = rand::thread_rng();
+        let material = grey();
+        let sphere = |center: Point3| {
+            Rc::new(Sphere::new(center, 0.5, material.clone())) as Rc<dyn Hittable>
+        };
+        let mut centers: Vec<Point3> = (0..500).map(|_| random_point(50.)).collect();
+        let spheres: Vec<_> = centers.iter().cloned().map(sphere).collect();
+        let mut bvh = LinearBVH::new(&spheres, &BVHOptions::default());
+
+        let check_hits = |bvh: &LinearBVH, centers: &[Point3]| {
+            let mut list = HittableList::new();
+            for center in centers.iter() {
+                list.add(Box::new(Sphere::new(center.clone(), 0.5, material.clone())));
+            }
+            for _ in 0..1000 {
+                let ray = random_ray();
+                let expected = list.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                let actual = bvh.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                assert_eq!(expected, actual);
+            }
+        };
+
+        // nudging every sphere a little only needs the boxes refitting
+        for (index, center) in centers.iter_mut().enumerate() {
+            *center = &*center + Vec3::new(rng.gen_range(-1. ..1.), 0., rng.gen_range(-1. ..1.));
+            bvh.replace(index, sphere(center.clone()));
+        }
+        assert!(!bvh.update());
+        check_hits(&bvh, &centers);
+
+        // but scattering them all somewhere else leaves the tree's boxes spread right across the
+        // scene, so it's rebuilt
+        for (index, center) in centers.iter_mut().enumerate() {
+            *center = random_point(50.);
+            bvh.replace(index, sphere(center.clone()));
+        }
+        bvh.refit();
+        let refitted_cost = bvh.sah_cost();
+        assert!(bvh.update());
+        assert!(bvh.sah_cost() < refitted_cost);
+        check_hits(&bvh, &centers);
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_linear_bvh() {
+        // cargo test --release bench_linear_bvh -- --ignored --nocapture
+        use std::time::Instant;
+
+        let spheres = random_spheres(200_000);
+        let rays: Vec<Ray> = (0..1_000_000).map(|_| random_ray()).collect();
+        let options = BVHOptions::default();
+
+        let start = Instant::now();
+        let tree = BVHNode::with_options(&spheres, &options);
+        println!("BVHNode built in {:?}", start.elapsed());
+        let start = Instant::now();
+        let linear = LinearBVH::new(&spheres, &options);
+        println!("LinearBVH built in {:?}", start.elapsed());
+
+        let hittables: [(&str, &dyn Hittable); 2] = [("BVHNode", &tree), ("LinearBVH", &linear)];
+        for (name, hittable) in hittables.iter() {
+            let start = Instant::now();
+            let hits = rays
+                .iter()
+                .filter(|ray| hittable.hit(ray, 0.001, f64::INFINITY).is_some())
+                .count();
+            println!(
+                "{}: {} rays, {} hits, in {:?}",
+                name,
+                rays.len(),
+                hits,
+                start.elapsed()
+            );
+        }
+    }
+
+    #[test]
+    fn test_linear_bvh_big_leaf() {
+        // max_depth stopping the splitting two levels down, leaving leaves far bigger than
+        // max_leaf_size
+        let spheres = sphere_row(300);
+        let options = BVHOptions {
+            max_depth: 2,
+            ..BVHOptions::default()
+        };
+        let bvh = LinearBVH::new(&spheres, &options);
+        let stats = bvh.stats();
+        assert_eq!(stats.leaf_count, 4);
+        assert_eq!(stats.object_count, 300);
+        assert!(stats
+            .leaves_by_size
+            .keys()
+            .all(|&size| size > options.max_leaf_size));
+
+        for i in 0..300 {
+            let ray = Ray::new(
+                Point3::new(3. * i as f64, 5., 0.),
+                Vec3::new(0., -1., 0.),
+                0.,
+            );
+            let rec = bvh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        }
+    }
+}
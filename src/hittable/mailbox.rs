@@ -0,0 +1,66 @@
+use std::cell::Cell;
+
+/// Remembers which objects have been tested against the ray being traced, for the structures
+/// that can hold an object in more than one place, like a `UniformGrid` or a `KdTree`.
+///
+/// Testing an object again would be wasted work at best. For one whose hits are random, like a
+/// `ConstantMedium`, it would give the ray another chance to scatter, and so make it look denser.
+pub(super) struct Mailbox {
+    // the ray each object was last tested against
+    last_ray: Vec<Cell<u32>>,
+    ray: Cell<u32>,
+}
+
+impl Mailbox {
+    pub(super) fn new(object_count: usize) -> Mailbox {
+        Mailbox {
+            last_ray: vec![Cell::new(0); object_count],
+            ray: Cell::new(0),
+        }
+    }
+
+    /// A number for the next ray, different to the ones any object was last tested against.
+    pub(super) fn next_ray(&self) -> u32 {
+        let ray = self.ray.get().wrapping_add(1);
+        if ray == 0 {
+            // start again, forgetting the rays from before
+            for last_ray in self.last_ray.iter() {
+                last_ray.set(0);
+            }
+            self.ray.set(1);
+            return 1;
+        }
+        self.ray.set(ray);
+        ray
+    }
+
+    /// Whether the object `index` is yet to be tested against `ray`, marking it as tested.
+    pub(super) fn first_test(&self, index: u32, ray: u32) -> bool {
+        self.last_ray[index as usize].replace(ray) != ray
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mailbox() {
+        let mailbox = Mailbox::new(3);
+        let ray = mailbox.next_ray();
+        assert!(mailbox.first_test(1, ray));
+        assert!(!mailbox.first_test(1, ray));
+        assert!(mailbox.first_test(2, ray));
+
+        let ray = mailbox.next_ray();
+        assert!(mailbox.first_test(1, ray));
+
+        // the numbers wrapping around
+        mailbox.ray.set(u32::MAX);
+        mailbox.last_ray[0].set(1);
+        let ray = mailbox.next_ray();
+        assert_eq!(ray, 1);
+        assert!(mailbox.first_test(0, ray));
+        assert!(!mailbox.first_test(0, ray));
+    }
+}
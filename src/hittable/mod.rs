@@ -4,7 +4,27 @@ mod aabb;
 pub use aabb::AABB;
 
 mod bvh;
-pub use bvh::BVHNode;
+pub use bvh::{BVHNode, BVHOptions, SplitStrategy};
+
+mod linear_bvh;
+pub use linear_bvh::{CacheStatus, LinearBVH};
+
+mod uniform_grid;
+pub use uniform_grid::{GridOptions, UniformGrid};
+
+mod kd_tree;
+pub use kd_tree::{KdTree, KdTreeOptions};
+
+mod mailbox;
+
+mod wide_bvh;
+pub use wide_bvh::WideBVH;
+
+mod stats;
+pub use stats::{BVHStats, TraversalCounts};
+
+mod tlas;
+pub use tlas::Tlas;
 
 pub mod instance;
 
@@ -14,7 +34,20 @@ pub use hittable_list::HittableList;
 mod hit_record;
 pub use hit_record::HitRecord;
 
+mod world;
+pub use world::{Accelerator, World};
+
+#[cfg(test)]
+pub mod test_util;
+
 pub trait Hittable {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
     fn bounding_box(&self) -> Option<&AABB>;
+
+    /// Whether the ray hits anything between `t_min` and `t_max`, for shadow and occlusion rays
+    /// that don't need to know what or where. Collections can stop at the first hit they find
+    /// rather than looking for the closest.
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.hit(ray, t_min, t_max).is_some()
+    }
 }
@@ -0,0 +1,226 @@
+#[cfg(feature = "heatmap")]
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How a bounding volume hierarchy turned out: how big and deep it is, how its objects are
+/// spread over its leaves, and its SAH cost, for telling a well built tree from a badly built one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BVHStats {
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub object_count: usize,
+    /// How many leaves there are at each depth, the root being at depth 0.
+    pub leaves_by_depth: BTreeMap<usize, usize>,
+    /// How many leaves there are with each number of objects. Only the sizes there are leaves of
+    /// are kept, so one huge leaf doesn't cost a bucket for every size below it.
+    pub leaves_by_size: BTreeMap<usize, usize>,
+    pub sah_cost: f64,
+}
+
+impl BVHStats {
+    pub(super) fn add_branch(&mut self) {
+        self.node_count += 1;
+    }
+
+    pub(super) fn add_leaf(&mut self, depth: usize, size: usize) {
+        self.node_count += 1;
+        self.leaf_count += 1;
+        self.object_count += size;
+        *self.leaves_by_depth.entry(depth).or_insert(0) += 1;
+        *self.leaves_by_size.entry(size).or_insert(0) += 1;
+    }
+
+    /// The depth of the deepest leaf.
+    pub fn max_depth(&self) -> usize {
+        self.leaves_by_depth
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn mean_leaf_size(&self) -> f64 {
+        self.object_count as f64 / self.leaf_count.max(1) as f64
+    }
+}
+
+impl fmt::Display for BVHStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} nodes, {} leaves holding {} objects ({:.2} per leaf)",
+            self.node_count,
+            self.leaf_count,
+            self.object_count,
+            self.mean_leaf_size()
+        )?;
+        writeln!(
+            f,
+            "max depth {}, SAH cost {:.2}",
+            self.max_depth(),
+            self.sah_cost
+        )?;
+        writeln!(f, "leaves by depth:")?;
+        write_histogram(f, &self.leaves_by_depth)?;
+        writeln!(f, "leaves by size:")?;
+        write_histogram(f, &self.leaves_by_size)
+    }
+}
+
+/// Writes each bucket of `histogram` as a line with a bar scaled to the biggest one.
+fn write_histogram(f: &mut fmt::Formatter, histogram: &BTreeMap<usize, usize>) -> fmt::Result {
+    let most = histogram.values().copied().max().unwrap_or(0).max(1);
+    for (value, &count) in histogram.iter() {
+        let bar = "#".repeat((40 * count).div_ceil(most));
+        writeln!(f, "  {:>4} {:>9} {}", value, count, bar)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "heatmap")]
+thread_local! {
+    static BOX_TESTS: Cell<u64> = const { Cell::new(0) };
+    static PRIMITIVE_TESTS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Without the heatmap feature, these count nothing, so renders don't pay for counting.
+
+#[cfg(feature = "heatmap")]
+pub(super) fn count_box_test() {
+    BOX_TESTS.with(|count| count.set(count.get() + 1));
+}
+
+#[cfg(not(feature = "heatmap"))]
+#[inline(always)]
+pub(super) fn count_box_test() {}
+
+#[cfg(feature = "heatmap")]
+pub(super) fn count_primitive_tests(n: usize) {
+    PRIMITIVE_TESTS.with(|count| count.set(count.get() + n as u64));
+}
+
+#[cfg(not(feature = "heatmap"))]
+#[inline(always)]
+pub(super) fn count_primitive_tests(_n: usize) {}
+
+/// How many bounding boxes and objects rays have been tested against on this thread, counted by
+/// the acceleration structures as they're traversed. A `WideBVH` node's four boxes count as one
+/// test, as do a step into a `UniformGrid` cell or a `KdTree` node. Objects inside instances count
+/// too, as do the boxes of their own BVHs.
+///
+/// They're only counted when built with the `heatmap` feature, and are always zero otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraversalCounts {
+    pub box_tests: u64,
+    pub primitive_tests: u64,
+}
+
+impl TraversalCounts {
+    /// The counts so far, setting them back to zero.
+    #[cfg(feature = "heatmap")]
+    pub fn take() -> TraversalCounts {
+        TraversalCounts {
+            box_tests: BOX_TESTS.with(|count| count.replace(0)),
+            primitive_tests: PRIMITIVE_TESTS.with(|count| count.replace(0)),
+        }
+    }
+
+    #[cfg(not(feature = "heatmap"))]
+    pub fn take() -> TraversalCounts {
+        TraversalCounts::default()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.box_tests + self.primitive_tests
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Vec3;
+    use crate::hittable::test_util::random_spheres;
+    use crate::hittable::{BVHNode, BVHOptions, LinearBVH};
+
+    #[test]
+    fn test_bvh_stats() {
+        let objects = random_spheres(1000);
+        let options = BVHOptions::default();
+        let tree: BVHStats = BVHNode::with_options(&objects, &options).stats();
+        let linear = LinearBVH::new(&objects, &options);
+
+        // built the same way, the two layouts make the same tree
+        assert_eq!(tree, linear.stats());
+        assert_eq!(tree.node_count, linear.node_count());
+        assert_eq!(tree.object_count, 1000);
+        assert_eq!(tree.node_count, 2 * tree.leaf_count - 1);
+        assert_eq!(
+            tree.leaves_by_depth.values().sum::<usize>(),
+            tree.leaf_count
+        );
+        assert_eq!(
+            tree.leaves_by_size
+                .iter()
+                .map(|(size, count)| size * count)
+                .sum::<usize>(),
+            1000
+        );
+        assert!(tree
+            .leaves_by_size
+            .keys()
+            .all(|&size| size <= options.max_leaf_size));
+        assert!(tree.max_depth() <= options.max_depth);
+        assert!(f64::abs(tree.sah_cost - linear.sah_cost()) < Vec3::TOL);
+        assert!(tree.to_string().contains("1000 objects"));
+    }
+
+    #[test]
+    fn test_bvh_stats_one_big_leaf() {
+        // everything in the root, which only takes one bucket of the size histogram
+        let objects = random_spheres(300);
+        let options = BVHOptions {
+            max_depth: 0,
+            ..BVHOptions::default()
+        };
+        let stats = LinearBVH::new(&objects, &options).stats();
+        assert_eq!(stats.leaf_count, 1);
+        assert_eq!(stats.max_depth(), 0);
+        assert_eq!(
+            stats.leaves_by_size.iter().collect::<Vec<_>>(),
+            [(&300, &1)]
+        );
+        assert!(stats.to_string().contains(" 300 "));
+    }
+
+    #[test]
+    #[cfg(feature = "heatmap")]
+    fn test_traversal_counts() {
+        use crate::geometry::{Point3, Ray};
+        use crate::hittable::Hittable;
+
+        let objects = random_spheres(1000);
+        let bvh = LinearBVH::new(&objects, &BVHOptions::default());
+        TraversalCounts::take();
+
+        // a ray that misses the whole tree only tests the root's box
+        let away = Ray::new(Point3::new(0., 100., 0.), Vec3::new(0., 1., 0.), 0.);
+        assert!(bvh.hit(&away, 0.001, f64::INFINITY).is_none());
+        assert_eq!(
+            TraversalCounts::take(),
+            TraversalCounts {
+                box_tests: 1,
+                primitive_tests: 0,
+            }
+        );
+
+        // one that goes through it tests far fewer objects than there are
+        let through = Ray::new(Point3::new(0., 0., -100.), Vec3::new(0., 0., 1.), 0.);
+        bvh.hit(&through, 0.001, f64::INFINITY);
+        let counts = TraversalCounts::take();
+        assert!(counts.box_tests > 1);
+        assert!(counts.primitive_tests < 100);
+        assert_eq!(TraversalCounts::take().total(), 0);
+    }
+}
@@ -0,0 +1,79 @@
+//! Materials, objects and rays shared by the tests.
+
+use std::rc::Rc;
+
+use rand::Rng;
+
+use super::{HitRecord, Hittable, AABB};
+use crate::color::Color;
+use crate::geometry::{Point3, Ray, Sphere};
+use crate::material::types::Lambertian;
+use crate::material::Material;
+
+/// A plain grey diffuse material, for tests that don't care what things are made of.
+pub fn grey() -> Rc<dyn Material> {
+    Rc::new(Lambertian::from_color(Color::new(0.5, 0.5, 0.5)))
+}
+
+/// A point anywhere in the cube from `-range` to `range` along each axis.
+pub fn random_point(range: f64) -> Point3 {
+    let mut rng = rand::thread_rng();
+    Point3::new(
+        rng.gen_range(-range..range),
+        rng.gen_range(-range..range),
+        rng.gen_range(-range..range),
+    )
+}
+
+/// `n` spheres of radius 0.1 to 1 scattered through the cube from -50 to 50.
+pub fn random_spheres(n: usize) -> Vec<Rc<dyn Hittable>> {
+    let mut rng = rand::thread_rng();
+    let material = grey();
+    (0..n)
+        .map(|_| {
+            Rc::new(Sphere::new(
+                random_point(50.),
+                rng.gen_range(0.1..1.),
+                material.clone(),
+            )) as Rc<dyn Hittable>
+        })
+        .collect()
+}
+
+/// A ray from somewhere around the spheres of `random_spheres` towards somewhere among them.
+pub fn random_ray() -> Ray {
+    let origin = random_point(60.);
+    let target = random_point(50.);
+    Ray::new(origin.clone(), target - origin, 0.)
+}
+
+/// `n` spheres of radius 1 in a row along x, 3 apart, so sphere `i` is centred on `(3i, 0, 0)`.
+pub fn sphere_row(n: usize) -> Vec<Rc<dyn Hittable>> {
+    let material = grey();
+    (0..n)
+        .map(|i| {
+            Rc::new(Sphere::new(
+                Point3::new(3. * i as f64, 0., 0.),
+                1.,
+                material.clone(),
+            )) as Rc<dyn Hittable>
+        })
+        .collect()
+}
+
+/// Lets objects shared with an acceleration structure go in a `HittableList` too.
+pub struct RcHittable(pub Rc<dyn Hittable>);
+
+impl Hittable for RcHittable {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.0.hit(ray, t_min, t_max)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.0.occluded(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.0.bounding_box()
+    }
+}
@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use super::instance::Transform;
+use super::{BVHOptions, HitRecord, Hittable, LinearBVH, AABB};
+use crate::geometry::Ray;
+
+/// A two-level acceleration structure: a top-level `LinearBVH` over instances, each a
+/// `Transform` of a shared bottom-level structure, usually a `LinearBVH` over a mesh.
+///
+/// The bottom level is stored once however many times it's placed, so each instance only costs
+/// its matrices and box. A ray is tested against the instances' boxes in world space, then
+/// carried into the space of each one it might hit and tested against the shared structure there.
+pub struct Tlas {
+    bvh: LinearBVH,
+}
+
+impl Tlas {
+    /// Every instance must have a bounding box.
+    pub fn new(instances: Vec<Transform>, options: &BVHOptions) -> Tlas {
+        let instances: Vec<Rc<dyn Hittable>> = instances
+            .into_iter()
+            .map(|instance| Rc::new(instance) as Rc<dyn Hittable>)
+            .collect();
+        Tlas {
+            bvh: LinearBVH::new(&instances, options),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn instance_count(&self) -> usize {
+        self.bvh.stats().object_count
+    }
+}
+
+impl Hittable for Tlas {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.hit(ray, t_min, t_max)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.bvh.occluded(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        self.bvh.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::Rng;
+
+    use crate::geometry::{Mat4, Point3, Sphere, Vec3};
+    use crate::hittable::test_util::grey;
+    use crate::hittable::HittableList;
+
+    // a little cluster of spheres, as the shared bottom level
+    fn get_blas() -> Rc<dyn Hittable> {
+        let material = grey();
+        let spheres: Vec<Rc<dyn Hittable>> = [(0., 0., 0.), (1., 0., 0.), (0., 1.5, 0.)]
+            .iter()
+            .map(|&(x, y, z)| {
+                Rc::new(Sphere::new(Point3::new(x, y, z), 0.5, material.clone()))
+                    as Rc<dyn Hittable>
+            })
+            .collect();
+        Rc::new(LinearBVH::new(&spheres, &BVHOptions::default()))
+    }
+
+    #[test]
+    fn test_tlas_hits_match_instances() {
+        let mut rng = rand::thread_rng();
+        let blas = get_blas();
+        let matrices: Vec<Mat4> = (0..300)
+            .map(|_| {
+                Mat4::identity()
+                    .scale(&Vec3::new(1., rng.gen_range(0.5..2.), 1.))
+                    .rotate(&Vec3::new(0., 1., 0.), rng.gen_range(0. ..360.))
+                    .translate(&Vec3::new(
+                        rng.gen_range(-40. ..40.),
+                        0.,
+                        rng.gen_range(-40. ..40.),
+                    ))
+            })
+            .collect();
+
+        let tlas = Tlas::new(
+            matrices
+                .iter()
+                .map(|matrix| Transform::shared(blas.clone(), matrix.clone()))
+                .collect(),
+            &BVHOptions::default(),
+        );
+        assert_eq!(tlas.instance_count(), 300);
+        // the instances all share the one bottom level
+        assert_eq!(Rc::strong_count(&blas), 301);
+
+        let mut list = HittableList::new();
+        for matrix in matrices.iter() {
+            list.add(Box::new(Transform::shared(blas.clone(), matrix.clone())));
+        }
+
+        for _ in 0..2000 {
+            let origin = Point3::new(rng.gen_range(-50. ..50.), 5., rng.gen_range(-50. ..50.));
+            let target = Point3::new(rng.gen_range(-40. ..40.), 0., rng.gen_range(-40. ..40.));
+            let ray = Ray::new(origin.clone(), target - origin, 0.);
+            let expected = list.hit(&ray, 0.001, f64::INFINITY);
+            let actual = tlas.hit(&ray, 0.001, f64::INFINITY);
+            assert_eq!(expected.is_some(), actual.is_some());
+            assert_eq!(
+                tlas.occluded(&ray, 0.001, 4.),
+                list.occluded(&ray, 0.001, 4.)
+            );
+            if let (Some(expected), Some(actual)) = (expected, actual) {
+                assert!(f64::abs(expected.t - actual.t) < Vec3::TOL);
+                assert_eq!(expected.normal, actual.normal);
+            }
+        }
+    }
+}
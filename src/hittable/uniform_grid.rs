@@ -0,0 +1,309 @@
+use std::rc::Rc;
+
+use super::mailbox::Mailbox;
+use super::stats;
+use super::{HitRecord, Hittable, AABB};
+use crate::geometry::{Ray, Vec3};
+
+#[derive(Clone, Debug)]
+pub struct GridOptions {
+    /// How many cells to make for each object, spread over the grid's box so the cells are as near
+    /// cubes as they can be.
+    pub density: f64,
+    /// The most cells along any axis.
+    pub max_resolution: usize,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        GridOptions {
+            density: 4.,
+            max_resolution: 128,
+        }
+    }
+}
+
+/// The box around a scene cut into equal cells, each listing the objects whose boxes overlap it.
+///
+/// A ray steps from cell to cell in the order it goes through them, by the 3D-DDA of Amanatides
+/// and Woo, and stops at the first cell that ends beyond the closest hit it's found so far. It's
+/// quick to build and to walk through, but does badly when the objects are unevenly spread, as a
+/// few cells end up with most of them and most cells with none. An object in many cells is still
+/// only tested once for each ray.
+pub struct UniformGrid {
+    bbox: AABB,
+    // the box the cells fill, a little bigger than bbox
+    bounds: AABB,
+    resolution: [usize; 3],
+    cell_size: Vec3,
+    // the objects in cell i, numbered with x changing fastest, are the ones indexed by
+    // cell_objects[cell_starts[i]..cell_starts[i + 1]]
+    cell_starts: Vec<u32>,
+    cell_objects: Vec<u32>,
+    objects: Vec<Rc<dyn Hittable>>,
+    mailbox: Mailbox,
+}
+
+impl UniformGrid {
+    /// Every object must have a bounding box; a `World` keeps the ones that don't to one side.
+    pub fn new(objects: &[Rc<dyn Hittable>], options: &GridOptions) -> UniformGrid {
+        assert!(!objects.is_empty(), "UniformGrid needs at least one object");
+        let boxes: Vec<&AABB> = objects
+            .iter()
+            .map(|object| {
+                object
+                    .bounding_box()
+                    .expect("No bounding box in UniformGrid constructor")
+            })
+            .collect();
+        let bbox = boxes
+            .iter()
+            .skip(1)
+            .fold(boxes[0].clone(), |bbox, b| AABB::surrounding_box(&bbox, b));
+
+        // padded a little, so that a flat scene still has some room inside its grid
+        let pad = 1e-6 * f64::max((&bbox.maximum - &bbox.minimum).length(), 1.);
+        let pad = Vec3::new(pad, pad, pad);
+        let bounds = AABB {
+            minimum: &bbox.minimum - &pad,
+            maximum: &bbox.maximum + &pad,
+        };
+
+        let extent = &bounds.maximum - &bounds.minimum;
+        let volume = extent.x * extent.y * extent.z;
+        let cells_per_length = f64::cbrt(options.density * objects.len() as f64 / volume);
+        let mut resolution = [1; 3];
+        for (axis, n) in resolution.iter_mut().enumerate() {
+            *n = ((extent[axis] * cells_per_length).round() as usize)
+                .clamp(1, options.max_resolution);
+        }
+        let cell_size = Vec3::new(
+            extent.x / resolution[0] as f64,
+            extent.y / resolution[1] as f64,
+            extent.z / resolution[2] as f64,
+        );
+
+        let mut grid = UniformGrid {
+            bbox,
+            bounds,
+            resolution,
+            cell_size,
+            cell_starts: Vec::new(),
+            cell_objects: Vec::new(),
+            objects: objects.to_vec(),
+            mailbox: Mailbox::new(objects.len()),
+        };
+
+        // count each cell's objects, then fill them in
+        let cell_count = resolution.iter().product::<usize>();
+        let mut counts = vec![0u32; cell_count + 1];
+        for b in boxes.iter() {
+            grid.for_each_cell(b, |cell| counts[cell] += 1);
+        }
+        let mut start = 0;
+        for count in counts.iter_mut() {
+            let n = *count;
+            *count = start;
+            start += n;
+        }
+        let mut next = counts.clone();
+        let mut cell_objects = vec![0; start as usize];
+        for (index, b) in boxes.iter().enumerate() {
+            grid.for_each_cell(b, |cell| {
+                cell_objects[next[cell] as usize] = index as u32;
+                next[cell] += 1;
+            });
+        }
+        grid.cell_starts = counts;
+        grid.cell_objects = cell_objects;
+        grid
+    }
+
+    /// How many cells there are along each axis.
+    #[cfg(test)]
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    /// The cell `p` is in along `axis`, or the nearest one if it's outside the grid.
+    fn cell_along(&self, p: f64, axis: usize) -> usize {
+        let cell = ((p - self.bounds.minimum[axis]) / self.cell_size[axis]).floor();
+        (cell.max(0.) as usize).min(self.resolution[axis] - 1)
+    }
+
+    fn cell_index(&self, cell: [usize; 3]) -> usize {
+        cell[0] + self.resolution[0] * (cell[1] + self.resolution[1] * cell[2])
+    }
+
+    /// Calls `f` with the index of each cell `bbox` overlaps.
+    fn for_each_cell(&self, bbox: &AABB, mut f: impl FnMut(usize)) {
+        let lo: Vec<usize> = (0..3)
+            .map(|axis| self.cell_along(bbox.minimum[axis], axis))
+            .collect();
+        let hi: Vec<usize> = (0..3)
+            .map(|axis| self.cell_along(bbox.maximum[axis], axis))
+            .collect();
+        for z in lo[2]..=hi[2] {
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    f(self.cell_index([x, y, z]));
+                }
+            }
+        }
+    }
+
+    /// Walks the ray through the cells between `t_min` and `t_max`, nearest first, calling `visit`
+    /// with each one's objects and where the ray leaves it, until `visit` returns true.
+    fn traverse(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut visit: impl FnMut(&[u32], f64) -> bool,
+    ) {
+        let (t_enter, t_exit) = match self.bounds.intersect(ray, t_min, t_max) {
+            Some(t) => t,
+            None => return,
+        };
+
+        // the cell the ray comes in at, and for each axis, which way it steps, where it next
+        // crosses into another cell, and how far it goes between crossings
+        let entry = ray.at(t_enter);
+        let mut cell = [0isize; 3];
+        let mut step = [0isize; 3];
+        let mut t_next = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            let c = self.cell_along(entry[axis], axis);
+            cell[axis] = c as isize;
+            let direction = ray.direction[axis];
+            let minimum = self.bounds.minimum[axis];
+            let size = self.cell_size[axis];
+            if direction > 0. {
+                step[axis] = 1;
+                t_next[axis] = (minimum + (c + 1) as f64 * size - ray.origin[axis]) / direction;
+                t_delta[axis] = size / direction;
+            } else if direction < 0. {
+                step[axis] = -1;
+                t_next[axis] = (minimum + c as f64 * size - ray.origin[axis]) / direction;
+                t_delta[axis] = -size / direction;
+            }
+        }
+
+        loop {
+            let axis = if t_next[0] < t_next[1] {
+                if t_next[0] < t_next[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_next[1] < t_next[2] {
+                1
+            } else {
+                2
+            };
+
+            let index = self.cell_index([cell[0] as usize, cell[1] as usize, cell[2] as usize]);
+            let objects = &self.cell_objects
+                [self.cell_starts[index] as usize..self.cell_starts[index + 1] as usize];
+            stats::count_box_test();
+            if visit(objects, f64::min(t_next[axis], t_exit)) || t_next[axis] >= t_exit {
+                return;
+            }
+
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= self.resolution[axis] as isize {
+                return;
+            }
+            t_next[axis] += t_delta[axis];
+        }
+    }
+}
+
+impl Hittable for UniformGrid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest_so_far = t_max;
+        let mut rec = None;
+        let ray_id = self.mailbox.next_ray();
+        self.traverse(ray, t_min, t_max, |objects, t_leave| {
+            // an object tested in an earlier cell would miss now, or hit where it did then
+            for &index in objects {
+                if !self.mailbox.first_test(index, ray_id) {
+                    continue;
+                }
+                stats::count_primitive_tests(1);
+                if let Some(temp_rec) = self.objects[index as usize].hit(ray, t_min, closest_so_far)
+                {
+                    closest_so_far = temp_rec.t;
+                    rec = Some(temp_rec);
+                }
+            }
+            // a hit beyond this cell might still be beaten by something in the next one
+            closest_so_far <= t_leave
+        });
+        rec
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut occluded = false;
+        let ray_id = self.mailbox.next_ray();
+        self.traverse(ray, t_min, t_max, |objects, _| {
+            occluded = objects.iter().any(|&index| {
+                if !self.mailbox.first_test(index, ray_id) {
+                    return false;
+                }
+                stats::count_primitive_tests(1);
+                self.objects[index as usize].occluded(ray, t_min, t_max)
+            });
+            occluded
+        });
+        occluded
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Point3;
+    use crate::hittable::test_util::sphere_row;
+
+    #[test]
+    fn test_uniform_grid() {
+        // a row of spheres along x, so the grid is long and thin
+        let spheres = sphere_row(20);
+        let grid = UniformGrid::new(&spheres, &GridOptions::default());
+        let resolution = grid.resolution();
+        assert!(resolution[0] > 4 * resolution[1]);
+        assert_eq!(resolution[1], resolution[2]);
+
+        // straight down onto each sphere, then along the row from either end and from inside it
+        for i in 0..20 {
+            let ray = Ray::new(
+                Point3::new(i as f64 * 3., 5., 0.),
+                Vec3::new(0., -1., 0.),
+                0.,
+            );
+            let rec = grid.hit(&ray, 0., f64::INFINITY).unwrap();
+            assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        }
+        let ray = Ray::new(Point3::new(-10., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(f64::abs(grid.hit(&ray, 0., f64::INFINITY).unwrap().t - 9.) < Vec3::TOL);
+        let ray = Ray::new(Point3::new(100., 0., 0.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(f64::abs(grid.hit(&ray, 0., f64::INFINITY).unwrap().t - 42.) < Vec3::TOL);
+        let ray = Ray::new(Point3::new(31.5, 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(f64::abs(grid.hit(&ray, 0., f64::INFINITY).unwrap().t - 0.5) < Vec3::TOL);
+        assert!(grid.occluded(&ray, 0., 1.));
+        assert!(!grid.occluded(&ray, 0., 0.4));
+
+        // past the end, and beside the row
+        let ray = Ray::new(Point3::new(60., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(grid.hit(&ray, 0., f64::INFINITY).is_none());
+        let ray = Ray::new(Point3::new(-10., 2., 0.), Vec3::new(1., 0., 0.), 0.);
+        assert!(grid.hit(&ray, 0., f64::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,656 @@
+use std::rc::Rc;
+
+use super::bvh::{self, BuildItem};
+use super::stats::{self, BVHStats};
+use super::{BVHOptions, HitRecord, Hittable, AABB};
+use crate::geometry::{Point3, Ray};
+
+/// How many children a node of a `WideBVH` has.
+const WIDTH: usize = 4;
+
+/// A node of a `WideBVH`, with its children's boxes laid out side by side, one lane each, so that
+/// a ray can be tested against all four at once.
+struct WideNode {
+    // the children's boxes, by axis and then by lane
+    minimum: [[f64; WIDTH]; 3],
+    maximum: [[f64; WIDTH]; 3],
+    // for a leaf, the index of its first object; for a node, its index
+    offsets: [u32; WIDTH],
+    // how many objects a leaf has, or 0 for a node
+    counts: [u32; WIDTH],
+}
+
+impl WideNode {
+    /// A node with no children. Its lanes have boxes inside out, which no ray can go through.
+    fn empty() -> WideNode {
+        WideNode {
+            minimum: [[f64::INFINITY; WIDTH]; 3],
+            maximum: [[f64::NEG_INFINITY; WIDTH]; 3],
+            offsets: [0; WIDTH],
+            counts: [0; WIDTH],
+        }
+    }
+
+    fn set_bbox(&mut self, lane: usize, bbox: &AABB) {
+        for axis in 0..3 {
+            self.minimum[axis][lane] = bbox.minimum[axis];
+            self.maximum[axis][lane] = bbox.maximum[axis];
+        }
+    }
+
+    fn bbox(&self, lane: usize) -> AABB {
+        AABB {
+            minimum: Point3::new(
+                self.minimum[0][lane],
+                self.minimum[1][lane],
+                self.minimum[2][lane],
+            ),
+            maximum: Point3::new(
+                self.maximum[0][lane],
+                self.maximum[1][lane],
+                self.maximum[2][lane],
+            ),
+        }
+    }
+
+    fn is_used(&self, lane: usize) -> bool {
+        self.minimum[0][lane] <= self.maximum[0][lane]
+    }
+
+    /// The sides of the boxes a ray comes into and goes out of along `axis`, which are the
+    /// maximums and minimums the other way round if it's going backwards along it.
+    fn slabs(&self, axis: usize, negative: bool) -> (&[f64; WIDTH], &[f64; WIDTH]) {
+        if negative {
+            (&self.maximum[axis], &self.minimum[axis])
+        } else {
+            (&self.minimum[axis], &self.maximum[axis])
+        }
+    }
+}
+
+/// A run of the items being built over that will be one child of a node, and how it would be
+/// split in two if it's not to be a leaf.
+struct Group {
+    start: usize,
+    end: usize,
+    bbox: AABB,
+    depth: usize,
+    split: Option<usize>,
+}
+
+impl Group {
+    fn new(
+        items: &mut [BuildItem],
+        start: usize,
+        end: usize,
+        options: &BVHOptions,
+        depth: usize,
+    ) -> Group {
+        let bbox = BuildItem::bbox(&items[start..end]);
+        let split = bvh::split(&mut items[start..end], &bbox, options, depth).map(|(n, _)| n);
+        Group {
+            start,
+            end,
+            bbox,
+            depth,
+            split,
+        }
+    }
+
+    /// The two groups this one splits into.
+    fn children(&self, items: &mut [BuildItem], options: &BVHOptions) -> [Group; 2] {
+        let mid = self.start + self.split.unwrap();
+        [
+            Group::new(items, self.start, mid, options, self.depth + 1),
+            Group::new(items, mid, self.end, options, self.depth + 1),
+        ]
+    }
+}
+
+/// A bounding volume hierarchy with four children to a node, laid out flat like a `LinearBVH`.
+///
+/// It's the same tree with every other level taken out, each node adopting its grandchildren,
+/// which halves how many nodes a ray has to go through. A node's four boxes are stored a
+/// coordinate at a time, so the slab test runs on all of them together: with SSE2, two lanes to a
+/// register, and otherwise as plain loops the compiler can vectorize.
+pub struct WideBVH {
+    nodes: Vec<WideNode>,
+    objects: Vec<Rc<dyn Hittable>>,
+    bbox: AABB,
+}
+
+impl WideBVH {
+    /// How many nodes and leaves a ray can have left to visit: each level can leave three behind.
+    const STACK_SIZE: usize = (WIDTH - 1) * 64 + 1;
+
+    /// Every object must have a bounding box; a `World` keeps the ones that don't to one side.
+    pub fn new(objects: &[Rc<dyn Hittable>], options: &BVHOptions) -> WideBVH {
+        assert!(!objects.is_empty(), "WideBVH needs at least one object");
+        assert!(options.max_depth <= 64);
+        assert!(objects.len() <= u32::MAX as usize);
+
+        let mut items = BuildItem::from_objects(objects);
+        let root = Group::new(&mut items, 0, objects.len(), options, 0);
+        let mut bvh = WideBVH {
+            nodes: Vec::with_capacity(objects.len() / 2 + 1),
+            objects: Vec::with_capacity(objects.len()),
+            bbox: root.bbox.clone(),
+        };
+        // the root is a node even when everything fits in one leaf
+        let groups = if root.split.is_some() {
+            WideBVH::open(&mut items, root, options)
+        } else {
+            vec![root]
+        };
+        bvh.build(&mut items, objects, options, groups);
+        bvh
+    }
+
+    /// Splits `group` into as many as four groups, opening up the biggest of them that can be
+    /// split each time.
+    fn open(items: &mut [BuildItem], group: Group, options: &BVHOptions) -> Vec<Group> {
+        let mut groups = Vec::from(group.children(items, options));
+        while groups.len() < WIDTH {
+            let biggest = groups
+                .iter()
+                .enumerate()
+                .filter(|(_, group)| group.split.is_some())
+                .max_by(|(_, a), (_, b)| a.bbox.surface_area().total_cmp(&b.bbox.surface_area()))
+                .map(|(index, _)| index);
+            match biggest {
+                Some(index) => {
+                    let group = groups.swap_remove(index);
+                    groups.extend(group.children(items, options));
+                }
+                None => break,
+            }
+        }
+        groups
+    }
+
+    /// Adds a node with a child for each of `groups`, then the nodes under them.
+    fn build(
+        &mut self,
+        items: &mut [BuildItem],
+        objects: &[Rc<dyn Hittable>],
+        options: &BVHOptions,
+        groups: Vec<Group>,
+    ) {
+        let index = self.nodes.len();
+        self.nodes.push(WideNode::empty());
+        for (lane, group) in groups.into_iter().enumerate() {
+            self.nodes[index].set_bbox(lane, &group.bbox);
+            if group.split.is_some() {
+                self.nodes[index].offsets[lane] = self.nodes.len() as u32;
+                let groups = WideBVH::open(items, group, options);
+                self.build(items, objects, options, groups);
+            } else {
+                self.nodes[index].offsets[lane] = self.objects.len() as u32;
+                self.nodes[index].counts[lane] = (group.end - group.start) as u32;
+                for item in items[group.start..group.end].iter() {
+                    self.objects.push(objects[item.index].clone());
+                }
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The expected cost of a ray hitting the tree under the Surface Area Heuristic, counting one
+    /// for each object tested and a node's four boxes as one test.
+    pub fn sah_cost(&self) -> f64 {
+        let mut costs = vec![0.; self.nodes.len()];
+        // children always come after their parents
+        for index in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[index];
+            let children: Vec<(AABB, f64)> = (0..WIDTH)
+                .filter(|&lane| node.is_used(lane))
+                .map(|lane| {
+                    let cost = match node.counts[lane] {
+                        0 => costs[node.offsets[lane] as usize],
+                        count => count as f64,
+                    };
+                    (node.bbox(lane), cost)
+                })
+                .collect();
+            let bbox = children
+                .iter()
+                .skip(1)
+                .fold(children[0].0.clone(), |bbox, child| {
+                    AABB::surrounding_box(&bbox, &child.0)
+                });
+            let children: Vec<(&AABB, f64)> = children.iter().map(|(b, c)| (b, *c)).collect();
+            costs[index] = bvh::branch_cost(&bbox, &children);
+        }
+        costs[0]
+    }
+
+    pub fn stats(&self) -> BVHStats {
+        let mut stats = BVHStats {
+            sah_cost: self.sah_cost(),
+            ..BVHStats::default()
+        };
+        let mut depths = vec![0; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            stats.add_branch();
+            for lane in (0..WIDTH).filter(|&lane| node.is_used(lane)) {
+                match node.counts[lane] {
+                    0 => depths[node.offsets[lane] as usize] = depths[index] + 1,
+                    count => stats.add_leaf(depths[index] + 1, count as usize),
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// A ray by axis, with its direction inverted for the slab test.
+struct RayLanes {
+    origin: [f64; 3],
+    inv_direction: [f64; 3],
+    negative: [bool; 3],
+}
+
+impl RayLanes {
+    fn new(ray: &Ray) -> RayLanes {
+        let inv_direction = [
+            1. / ray.direction.x,
+            1. / ray.direction.y,
+            1. / ray.direction.z,
+        ];
+        RayLanes {
+            origin: [ray.origin.x, ray.origin.y, ray.origin.z],
+            inv_direction,
+            negative: [
+                inv_direction[0] < 0.,
+                inv_direction[1] < 0.,
+                inv_direction[2] < 0.,
+            ],
+        }
+    }
+}
+
+/// Which of `node`'s boxes the ray goes through between `t_min` and `t_max`, as a bit for each
+/// lane, and how far along the ray it goes into each of them.
+#[cfg(target_arch = "x86_64")]
+fn intersect(node: &WideNode, ray: &RayLanes, t_min: f64, t_max: f64) -> (u32, [f64; WIDTH]) {
+    intersect_sse2(node, ray, t_min, t_max)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn intersect(node: &WideNode, ray: &RayLanes, t_min: f64, t_max: f64) -> (u32, [f64; WIDTH]) {
+    intersect_scalar(node, ray, t_min, t_max)
+}
+
+/// `intersect` with SSE2, two lanes to a register. Every x86-64 processor has SSE2, so there's
+/// no need to check for it.
+#[cfg(target_arch = "x86_64")]
+fn intersect_sse2(node: &WideNode, ray: &RayLanes, t_min: f64, t_max: f64) -> (u32, [f64; WIDTH]) {
+    use std::arch::x86_64::*;
+
+    // SAFETY: every x86-64 processor has SSE2, and t has room for two f64s from 2 * half
+    unsafe {
+        let mut near = [_mm_set1_pd(t_min); WIDTH / 2];
+        let mut far = [_mm_set1_pd(t_max); WIDTH / 2];
+        for axis in 0..3 {
+            let (into, out_of) = node.slabs(axis, ray.negative[axis]);
+            let origin = _mm_set1_pd(ray.origin[axis]);
+            let inv_direction = _mm_set1_pd(ray.inv_direction[axis]);
+            for half in 0..WIDTH / 2 {
+                let into = _mm_set_pd(into[2 * half + 1], into[2 * half]);
+                let out_of = _mm_set_pd(out_of[2 * half + 1], out_of[2 * half]);
+                // like f64::max and f64::min, these keep the second operand if the first is NaN,
+                // as it is when the origin is on a slab the ray runs along
+                let t0 = _mm_mul_pd(_mm_sub_pd(into, origin), inv_direction);
+                let t1 = _mm_mul_pd(_mm_sub_pd(out_of, origin), inv_direction);
+                near[half] = _mm_max_pd(t0, near[half]);
+                far[half] = _mm_min_pd(t1, far[half]);
+            }
+        }
+
+        let mut mask = 0;
+        let mut t = [0.; WIDTH];
+        for half in 0..WIDTH / 2 {
+            mask |= (_mm_movemask_pd(_mm_cmpgt_pd(far[half], near[half])) as u32) << (2 * half);
+            _mm_storeu_pd(t.as_mut_ptr().add(2 * half), near[half]);
+        }
+        (mask, t)
+    }
+}
+
+/// `intersect` a lane at a time, for processors without SSE2.
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+fn intersect_scalar(
+    node: &WideNode,
+    ray: &RayLanes,
+    t_min: f64,
+    t_max: f64,
+) -> (u32, [f64; WIDTH]) {
+    let mut near = [t_min; WIDTH];
+    let mut far = [t_max; WIDTH];
+    for axis in 0..3 {
+        let (into, out_of) = node.slabs(axis, ray.negative[axis]);
+        for lane in 0..WIDTH {
+            let t0 = (into[lane] - ray.origin[axis]) * ray.inv_direction[axis];
+            let t1 = (out_of[lane] - ray.origin[axis]) * ray.inv_direction[axis];
+            near[lane] = f64::max(t0, near[lane]);
+            far[lane] = f64::min(t1, far[lane]);
+        }
+    }
+
+    let mask = (0..WIDTH)
+        .filter(|&lane| far[lane] > near[lane])
+        .fold(0, |mask, lane| mask | 1 << lane);
+    (mask, near)
+}
+
+/// A node or leaf a ray has still to visit, and where along the ray it goes into its box.
+#[derive(Clone, Copy)]
+struct Visit {
+    offset: u32,
+    count: u32,
+    t: f64,
+}
+
+impl Hittable for WideBVH {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let lanes = RayLanes::new(ray);
+
+        let mut closest_so_far = t_max;
+        let mut rec = None;
+        let root = Visit {
+            offset: 0,
+            count: 0,
+            t: t_min,
+        };
+        let mut stack = [root; WideBVH::STACK_SIZE];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let visit = stack[stack_len];
+            // something's already been hit before the ray gets to its box
+            if visit.t >= closest_so_far {
+                continue;
+            }
+
+            if visit.count > 0 {
+                let first = visit.offset as usize;
+                stats::count_primitive_tests(visit.count as usize);
+                for object in &self.objects[first..first + visit.count as usize] {
+                    if let Some(temp_rec) = object.hit(ray, t_min, closest_so_far) {
+                        closest_so_far = temp_rec.t;
+                        rec = Some(temp_rec);
+                    }
+                }
+                continue;
+            }
+
+            // push the children it goes through furthest first, so the nearest comes off next
+            let node = &self.nodes[visit.offset as usize];
+            stats::count_box_test();
+            let (mask, t) = intersect(node, &lanes, t_min, closest_so_far);
+            let start = stack_len;
+            for lane in (0..WIDTH).filter(|lane| mask & 1 << lane != 0) {
+                let mut i = stack_len;
+                while i > start && stack[i - 1].t < t[lane] {
+                    stack[i] = stack[i - 1];
+                    i -= 1;
+                }
+                stack[i] = Visit {
+                    offset: node.offsets[lane],
+                    count: node.counts[lane],
+                    t: t[lane],
+                };
+                stack_len += 1;
+            }
+        }
+        rec
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let lanes = RayLanes::new(ray);
+
+        // with any hit good enough, the order the children are visited in doesn't matter
+        let mut stack = [0u32; WideBVH::STACK_SIZE];
+        let mut stack_len = 1;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let node = &self.nodes[stack[stack_len] as usize];
+            stats::count_box_test();
+            let (mask, _) = intersect(node, &lanes, t_min, t_max);
+            for lane in (0..WIDTH).filter(|lane| mask & 1 << lane != 0) {
+                if node.counts[lane] == 0 {
+                    stack[stack_len] = node.offsets[lane];
+                    stack_len += 1;
+                    continue;
+                }
+                let first = node.offsets[lane] as usize;
+                if self.objects[first..first + node.counts[lane] as usize]
+                    .iter()
+                    .any(|object| {
+                        stats::count_primitive_tests(1);
+                        object.occluded(ray, t_min, t_max)
+                    })
+                {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        Some(&self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::Vec3;
+    use crate::hittable::test_util::{random_ray, random_spheres, sphere_row};
+    use crate::hittable::{LinearBVH, SplitStrategy};
+
+    #[test]
+    fn test_wide_bvh_hits_match_linear() {
+        for &n in [1, 3, 500].iter() {
+            let spheres = random_spheres(n);
+            for strategy in [SplitStrategy::Median, SplitStrategy::Sah].iter() {
+                let options = BVHOptions {
+                    strategy: *strategy,
+                    ..BVHOptions::default()
+                };
+                let linear = LinearBVH::new(&spheres, &options);
+                let wide = WideBVH::new(&spheres, &options);
+                assert_eq!(wide.objects.len(), n);
+                assert_eq!(wide.bounding_box(), linear.bounding_box());
+                assert_eq!(wide.stats().object_count, n);
+
+                for _ in 0..2000 {
+                    let ray = random_ray();
+                    let expected = linear.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                    let actual = wide.hit(&ray, 0.001, f64::INFINITY).map(|rec| rec.t);
+                    assert_eq!(expected, actual);
+                    assert_eq!(
+                        wide.occluded(&ray, 0.001, 60.),
+                        linear.occluded(&ray, 0.001, 60.)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_bvh_is_shallower() {
+        let spheres = random_spheres(2000);
+        let options = BVHOptions::default();
+        let linear = LinearBVH::new(&spheres, &options).stats();
+        let wide = WideBVH::new(&spheres, &options).stats();
+        assert_eq!(wide.leaf_count, linear.leaf_count);
+        // a full node of four takes the place of three branches, though near the leaves there
+        // aren't always four children to go round
+        let branches = |stats: &BVHStats| stats.node_count - stats.leaf_count;
+        assert!(3 * branches(&wide) < 2 * branches(&linear));
+        assert!(wide.max_depth() <= linear.max_depth() / 2 + 1);
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_intersect_sse2_matches_scalar() {
+        use rand::Rng;
+
+        use crate::hittable::test_util::random_point;
+
+        let mut rng = rand::thread_rng();
+        let random_node = || {
+            let mut node = WideNode::empty();
+            // leave the last lane unused
+            for lane in 0..WIDTH - 1 {
+                let a = random_point(10.);
+                let b = random_point(10.);
+                node.set_bbox(
+                    lane,
+                    &AABB {
+                        minimum: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+                        maximum: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+                    },
+                );
+            }
+            node
+        };
+
+        for i in 0..10000 {
+            let node = random_node();
+            let mut direction = random_point(1.);
+            let mut origin = random_point(15.);
+            // rays along the axes, some starting on a slab, where the slab test divides zero by
+            // zero
+            if i % 4 == 0 {
+                let axis = rng.gen_range(0..3);
+                direction = Vec3::new(0., 0., 0.);
+                direction[axis] = if rng.gen() { 1. } else { -1. };
+                if i % 8 == 0 {
+                    origin[(axis + 1) % 3] = node.minimum[(axis + 1) % 3][0];
+                }
+            }
+            let lanes = RayLanes::new(&Ray::new(origin, direction, 0.));
+            let t_max = rng.gen_range(1. ..30.);
+
+            let (mask, near) = intersect_scalar(&node, &lanes, 0., t_max);
+            let (sse2_mask, sse2_near) = intersect_sse2(&node, &lanes, 0., t_max);
+            assert_eq!(mask, sse2_mask);
+            assert_eq!(mask & 1 << (WIDTH - 1), 0);
+            for lane in (0..WIDTH).filter(|lane| mask & 1 << lane != 0) {
+                assert_eq!(near[lane], sse2_near[lane]);
+            }
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_wide_bvh() {
+        // cargo test --release bench_wide_bvh -- --ignored --nocapture
+        use std::time::Instant;
+
+        use crate::camera::{Camera, CameraOrientation, CameraSettings};
+        use crate::hittable::{Accelerator, World};
+        use crate::scene::FinalScene;
+
+        let time = |name: &str, hittable: &dyn Hittable, rays: &[Ray]| {
+            let start = Instant::now();
+            let hits = rays
+                .iter()
+                .filter(|ray| hittable.hit(ray, 0.001, f64::INFINITY).is_some())
+                .count();
+            let elapsed = start.elapsed();
+            println!(
+                "{}: {} rays, {} hits, in {:?} ({:.2} Mrays/s)",
+                name,
+                rays.len(),
+                hits,
+                elapsed,
+                rays.len() as f64 / elapsed.as_secs_f64() / 1e6
+            );
+        };
+
+        // the final scene's primary rays, as main sets up its camera; most of its objects are in
+        // BVHNodes of their own, so how its top level is built makes little difference
+        let camera = Camera::new(
+            CameraSettings {
+                vfov: 40.,
+                aspect_ratio: 1.,
+                aperture: 0.,
+                focus_dist: 10.,
+                time0: 0.,
+                time1: 1.,
+            },
+            CameraOrientation {
+                lookfrom: Point3::new(478., 278., -600.),
+                lookat: Point3::new(278., 278., 0.),
+                vup: Vec3::new(0., 1., 0.),
+            },
+        );
+        let rays: Vec<Ray> = (0..1000 * 1000)
+            .map(|i| camera.get_ray((i % 1000) as f64 / 999., (i / 1000) as f64 / 999.))
+            .collect();
+        let options = BVHOptions::default();
+        for (name, accelerator) in [
+            (
+                "FinalScene, LinearBVH",
+                Accelerator::Linear(options.clone()),
+            ),
+            ("FinalScene, WideBVH", Accelerator::Wide(options.clone())),
+        ]
+        .iter()
+        {
+            time(
+                name,
+                &World::with_accelerator(FinalScene::new(), accelerator),
+                &rays,
+            );
+        }
+
+        // and random rays through a big pile of spheres, where the traversal matters more
+        let spheres = random_spheres(200_000);
+        let rays: Vec<Ray> = (0..1_000_000).map(|_| random_ray()).collect();
+        time(
+            "spheres, LinearBVH",
+            &LinearBVH::new(&spheres, &options),
+            &rays,
+        );
+        time("spheres, WideBVH", &WideBVH::new(&spheres, &options), &rays);
+    }
+
+    #[test]
+    fn test_wide_bvh_big_leaf() {
+        // max_depth stopping the splitting two levels down, leaving leaves far bigger than
+        // max_leaf_size
+        let spheres = sphere_row(300);
+        let options = BVHOptions {
+            max_depth: 2,
+            ..BVHOptions::default()
+        };
+        let wide = WideBVH::new(&spheres, &options);
+        let stats = wide.stats();
+        assert_eq!(stats.leaf_count, 4);
+        assert_eq!(stats.object_count, 300);
+        assert!(stats
+            .leaves_by_size
+            .keys()
+            .all(|&size| size > options.max_leaf_size));
+
+        for i in 0..300 {
+            let ray = Ray::new(
+                Point3::new(3. * i as f64, 5., 0.),
+                Vec3::new(0., -1., 0.),
+                0.,
+            );
+            let rec = wide.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            assert!(f64::abs(rec.t - 4.) < Vec3::TOL);
+        }
+    }
+}
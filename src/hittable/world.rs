@@ -0,0 +1,500 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use super::instance::Transform;
+use super::{
+    BVHNode, BVHOptions, BVHStats, CacheStatus, GridOptions, HitRecord, Hittable, HittableList,
+    KdTree, KdTreeOptions, LinearBVH, UniformGrid, WideBVH, AABB,
+};
+use crate::geometry::{Mat4, Ray};
+
+/// How a `World` organises its bounded objects to find what a ray hits.
+#[derive(Clone, Debug)]
+pub enum Accelerator {
+    /// A `BVHNode`, a tree of boxed nodes.
+    Tree(BVHOptions),
+    /// A `LinearBVH`, the same tree laid out flat.
+    Linear(BVHOptions),
+    /// A `WideBVH`, the same tree with four children to a node.
+    Wide(BVHOptions),
+    /// A `UniformGrid` of cells.
+    Grid(GridOptions),
+    /// A `KdTree`, splitting space rather than objects.
+    KdTree(KdTreeOptions),
+}
+
+impl Default for Accelerator {
+    fn default() -> Self {
+        Accelerator::Linear(BVHOptions::default())
+    }
+}
+
+/// The acceleration structure a `World` has built, kept as what it is so that it can be refitted
+/// or asked for its statistics.
+enum Structure {
+    Tree(BVHNode),
+    Linear(LinearBVH),
+    Wide(WideBVH),
+    Grid(UniformGrid),
+    KdTree(KdTree),
+}
+
+impl Structure {
+    fn new(bounded: &[Rc<dyn Hittable>], accelerator: &Accelerator) -> Structure {
+        match accelerator {
+            Accelerator::Tree(options) => Structure::Tree(BVHNode::with_options(bounded, options)),
+            Accelerator::Linear(options) => Structure::Linear(LinearBVH::new(bounded, options)),
+            Accelerator::Wide(options) => Structure::Wide(WideBVH::new(bounded, options)),
+            Accelerator::Grid(options) => Structure::Grid(UniformGrid::new(bounded, options)),
+            Accelerator::KdTree(options) => Structure::KdTree(KdTree::new(bounded, options)),
+        }
+    }
+
+    fn hittable(&self) -> &dyn Hittable {
+        match self {
+            Structure::Tree(bvh) => bvh,
+            Structure::Linear(bvh) => bvh,
+            Structure::Wide(bvh) => bvh,
+            Structure::Grid(grid) => grid,
+            Structure::KdTree(tree) => tree,
+        }
+    }
+
+    fn stats(&self) -> Option<BVHStats> {
+        match self {
+            Structure::Tree(bvh) => Some(bvh.stats()),
+            Structure::Linear(bvh) => Some(bvh.stats()),
+            Structure::Wide(bvh) => Some(bvh.stats()),
+            // a grid has no tree to take statistics of
+            Structure::Grid(_) => None,
+            Structure::KdTree(tree) => Some(tree.stats()),
+        }
+    }
+}
+
+/// The top level of a scene. Objects with a bounding box go into an acceleration structure, and
+/// the ones without, like a `Plane`, are tested one by one alongside it.
+pub struct World {
+    structure: Option<Structure>,
+    accelerator: Accelerator,
+    // the objects in the structure, as they were when the world was built
+    bounded: Vec<Rc<dyn Hittable>>,
+    unbounded: Vec<Box<dyn Hittable>>,
+}
+
+impl World {
+    #[allow(dead_code)]
+    pub fn new(list: HittableList) -> World {
+        World::with_accelerator(list, &Accelerator::default())
+    }
+
+    pub fn with_accelerator(list: HittableList, accelerator: &Accelerator) -> World {
+        let mut world = World::without_structure(list, accelerator);
+        if !world.bounded.is_empty() {
+            world.structure = Some(Structure::new(&world.bounded, accelerator));
+        }
+        world
+    }
+
+    /// A world whose bounded objects go in a `LinearBVH` that's loaded from the cache at `path`,
+    /// or built and saved there if the cache is missing or was made for a different scene. The
+    /// status is `None` if there are no bounded objects, and so no tree.
+    pub fn with_cache<P: AsRef<Path>>(
+        list: HittableList,
+        options: &BVHOptions,
+        path: P,
+    ) -> (World, Option<CacheStatus>) {
+        let mut world = World::without_structure(list, &Accelerator::Linear(options.clone()));
+        if world.bounded.is_empty() {
+            return (world, None);
+        }
+        let (bvh, status) = LinearBVH::load_or_build(path, &world.bounded, options);
+        world.structure = Some(Structure::Linear(bvh));
+        (world, Some(status))
+    }
+
+    /// A world with the objects from `list`, sorted into the ones with a bounding box and the
+    /// ones without, that has yet to build its acceleration structure.
+    fn without_structure(list: HittableList, accelerator: &Accelerator) -> World {
+        let mut bounded: Vec<Rc<dyn Hittable>> = Vec::new();
+        let mut unbounded = Vec::new();
+        for object in list.into_objects() {
+            if object.bounding_box().is_some() {
+                bounded.push(Rc::from(object));
+            } else {
+                unbounded.push(object);
+            }
+        }
+        World {
+            structure: None,
+            accelerator: accelerator.clone(),
+            bounded,
+            unbounded,
+        }
+    }
+
+    /// Moves the objects with a bounding box to where `matrix` puts them from where they were
+    /// when the world was built, as for a frame of an animation, and fits the acceleration
+    /// structure to them. The objects without a bounding box stay where they are.
+    ///
+    /// A `LinearBVH` is refitted, and only rebuilt if that's left it too slow. The other
+    /// structures can't be refitted, so they're built again. Returns whether it was rebuilt.
+    pub fn transform(&mut self, matrix: &Mat4) -> bool {
+        let moved = self.bounded.iter().map(|object| {
+            Rc::new(Transform::shared(object.clone(), matrix.clone())) as Rc<dyn Hittable>
+        });
+        match &mut self.structure {
+            Some(Structure::Linear(bvh)) => {
+                for (index, object) in moved.enumerate() {
+                    bvh.replace(index, object);
+                }
+                bvh.update()
+            }
+            Some(structure) => {
+                let moved: Vec<_> = moved.collect();
+                *structure = Structure::new(&moved, &self.accelerator);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How the acceleration structure over the bounded objects turned out, if there are any and
+    /// it's a tree. It's worked out by walking the whole tree, so only when asked for.
+    pub fn stats(&self) -> Option<BVHStats> {
+        self.structure.as_ref().and_then(Structure::stats)
+    }
+}
+
+impl Hittable for World {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut rec = match &self.structure {
+            Some(structure) => structure.hittable().hit(ray, t_min, t_max),
+            None => None,
+        };
+
+        for object in &self.unbounded {
+            let closest_so_far = rec.as_ref().map_or(t_max, |rec| rec.t);
+            if let Some(temp_rec) = object.hit(ray, t_min, closest_so_far) {
+                rec = Some(temp_rec);
+            }
+        }
+
+        rec
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.structure
+            .as_ref()
+            .is_some_and(|structure| structure.hittable().occluded(ray, t_min, t_max))
+            || self
+                .unbounded
+                .iter()
+                .any(|object| object.occluded(ray, t_min, t_max))
+    }
+
+    fn bounding_box(&self) -> Option<&AABB> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+
+        self.structure
+            .as_ref()
+            .and_then(|structure| structure.hittable().bounding_box())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use rand::Rng;
+
+    use crate::geometry::{Mat4, Plane, Point3, Quad, Sphere, Triangle, Vec3};
+    use crate::hittable::test_util::{grey, random_point, RcHittable};
+    use crate::hittable::SplitStrategy;
+
+    #[test]
+    fn test_world_hit() {
+        let material_rc = grey();
+        let mut list = HittableList::new();
+        list.add(Box::new(Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            material_rc.clone(),
+        )));
+        list.add(Box::new(Sphere::new(
+            Point3::new(0., 1., 0.),
+            0.5,
+            material_rc.clone(),
+        )));
+        list.add(Box::new(Sphere::new(
+            Point3::new(3., 1., 0.),
+            0.5,
+            material_rc,
+        )));
+        let world = World::new(list);
+        assert!(world.bounding_box().is_none());
+
+        // the sphere in front of the plane
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = world.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 3.5) < Vec3::TOL);
+
+        // the plane, beside the spheres
+        let r = Ray::new(Point3::new(-3., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let rec = world.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 5.) < Vec3::TOL);
+
+        // the plane in front of a sphere, seen from below
+        let r = Ray::new(Point3::new(3., -1., 0.), Vec3::new(0., 1., 0.), 0.);
+        let rec = world.hit(&r, 0., f64::INFINITY).unwrap();
+        assert!(f64::abs(rec.t - 1.) < Vec3::TOL);
+
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., 1., 0.), 0.);
+        assert!(world.hit(&r, 0., f64::INFINITY).is_none());
+        assert!(!world.occluded(&r, 0., f64::INFINITY));
+
+        // the plane, or the sphere behind it, from below
+        let r = Ray::new(Point3::new(3., -1., 0.), Vec3::new(0., 1., 0.), 0.);
+        assert!(!world.occluded(&r, 0., 0.5));
+        assert!(world.occluded(&r, 0., 1.5));
+        assert!(world.occluded(&r, 1.5, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_world_transform() {
+        // a row of spheres on a plane, turned a quarter of the way round the y axis, then half
+        let material_rc = grey();
+        let get_list = || {
+            let mut list = HittableList::new();
+            list.add(Box::new(Plane::new(
+                Point3::new(0., 0., 0.),
+                Vec3::new(0., 1., 0.),
+                material_rc.clone(),
+            )));
+            for i in 1..=20 {
+                list.add(Box::new(Sphere::new(
+                    Point3::new(i as f64, 1., 0.),
+                    0.25,
+                    material_rc.clone(),
+                )));
+            }
+            list
+        };
+        let down = |x: f64, z: f64| Ray::new(Point3::new(x, 5., z), Vec3::new(0., -1., 0.), 0.);
+        let options = BVHOptions::default();
+        for accelerator in [
+            Accelerator::Linear(options.clone()),
+            Accelerator::Tree(options.clone()),
+        ]
+        .iter()
+        {
+            let mut world = World::with_accelerator(get_list(), accelerator);
+            let rebuilt = world.transform(&Mat4::rotation(&Vec3::new(0., 1., 0.), 90.));
+            // only a LinearBVH can be refitted, and turning a row on the spot doesn't spoil it
+            assert_eq!(rebuilt, matches!(accelerator, Accelerator::Tree(_)));
+            for i in 1..=20 {
+                let rec = world
+                    .hit(&down(0., -(i as f64)), 0., f64::INFINITY)
+                    .unwrap();
+                assert!(f64::abs(rec.t - 3.75) < Vec3::TOL);
+                // the plane's still there where the sphere was
+                let rec = world.hit(&down(i as f64, 0.), 0., f64::INFINITY).unwrap();
+                assert!(f64::abs(rec.t - 5.) < Vec3::TOL);
+            }
+
+            // from where they were built, not from the last frame
+            world.transform(&Mat4::rotation(&Vec3::new(0., 1., 0.), 180.));
+            let rec = world.hit(&down(-20., 0.), 0., f64::INFINITY).unwrap();
+            assert!(f64::abs(rec.t - 3.75) < Vec3::TOL);
+        }
+    }
+
+    #[test]
+    fn test_world_bounding_box() {
+        let material_rc = grey();
+        let mut list = HittableList::new();
+        list.add(Box::new(Sphere::new(
+            Point3::new(0., 1., 0.),
+            0.5,
+            material_rc,
+        )));
+        let world = World::new(list);
+        assert_eq!(
+            *world.bounding_box().unwrap(),
+            AABB {
+                minimum: Vec3::new(-0.5, 0.5, -0.5),
+                maximum: Vec3::new(0.5, 1.5, 0.5),
+            }
+        );
+
+        assert!(World::new(HittableList::new()).bounding_box().is_none());
+    }
+
+    #[test]
+    fn test_accelerators_agree() {
+        let material = grey();
+
+        // a tight cluster and a sparse spread of spheres, which a grid finds hard, and triangles
+        // and quads with flat boxes, some lying along the axes
+        let mut shapes: Vec<Rc<dyn Hittable>> = Vec::new();
+        for i in 0..600 {
+            let (center, radius) = if i % 3 == 0 {
+                (random_point(20.), 0.5)
+            } else {
+                (random_point(2.), 0.05)
+            };
+            shapes.push(Rc::new(Sphere::new(center, radius, material.clone())));
+        }
+        for _ in 0..100 {
+            let a = random_point(15.);
+            let b = &a + &random_point(2.);
+            let c = &a + &random_point(2.);
+            shapes.push(Rc::new(Triangle::new(a, b, c, material.clone())));
+        }
+        for i in 0..30 {
+            let q = random_point(15.);
+            let (u, v) = match i % 3 {
+                0 => (Vec3::new(3., 0., 0.), Vec3::new(0., 0., 3.)),
+                1 => (Vec3::new(0., 3., 0.), Vec3::new(0., 0., 3.)),
+                _ => (random_point(3.), random_point(3.)),
+            };
+            shapes.push(Rc::new(Quad::new(q, u, v, material.clone())));
+        }
+
+        let make_list = || {
+            let mut list = HittableList::new();
+            for shape in shapes.iter() {
+                list.add(Box::new(RcHittable(shape.clone())));
+            }
+            list.add(Box::new(Plane::new(
+                Point3::new(0., -20., 0.),
+                Vec3::new(0., 1., 0.),
+                material.clone(),
+            )));
+            list
+        };
+        let median = BVHOptions {
+            strategy: SplitStrategy::Median,
+            ..BVHOptions::default()
+        };
+        let accelerators = [
+            Accelerator::Tree(BVHOptions::default()),
+            Accelerator::Tree(median.clone()),
+            Accelerator::Linear(BVHOptions::default()),
+            Accelerator::Linear(median),
+            Accelerator::Wide(BVHOptions::default()),
+            Accelerator::Grid(GridOptions::default()),
+            Accelerator::KdTree(KdTreeOptions::default()),
+        ];
+        let worlds: Vec<World> = accelerators
+            .iter()
+            .map(|accelerator| World::with_accelerator(make_list(), accelerator))
+            .collect();
+        let brute_force = make_list();
+
+        let mut rng = rand::thread_rng();
+        for i in 0..2000 {
+            let origin = random_point(30.);
+            let target = random_point(if i % 2 == 0 { 2. } else { 20. });
+            let ray = Ray::new(origin.clone(), target - origin, 0.);
+            let expected = brute_force.hit(&ray, 0.001, f64::INFINITY);
+            let t_max = rng.gen_range(0. ..2.);
+            let expected_occluded = brute_force.occluded(&ray, 0.001, t_max);
+
+            for (world, accelerator) in worlds.iter().zip(accelerators.iter()) {
+                let actual = world.hit(&ray, 0.001, f64::INFINITY);
+                assert_eq!(
+                    expected.as_ref().map(|rec| (rec.t, &rec.normal)),
+                    actual.as_ref().map(|rec| (rec.t, &rec.normal)),
+                    "{:?}",
+                    accelerator
+                );
+                assert_eq!(
+                    world.occluded(&ray, 0.001, t_max),
+                    expected_occluded,
+                    "{:?}",
+                    accelerator
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_accelerators_test_objects_once() {
+        // big quads across lots of small spheres, so each quad is in many cells and leaves
+        let material = grey();
+        let mut shapes: Vec<Rc<dyn Hittable>> = Vec::new();
+        for i in 0..200 {
+            let center = Point3::new((i % 10) as f64, (i / 10 % 10) as f64, (i / 100) as f64);
+            shapes.push(Rc::new(Sphere::new(center, 0.1, material.clone())));
+        }
+        for i in 0..3 {
+            shapes.push(Rc::new(Quad::new(
+                Point3::new(-1., -1., 0.5 + i as f64 * 0.2),
+                Vec3::new(11., 0., 0.),
+                Vec3::new(0., 11., 0.),
+                material.clone(),
+            )));
+        }
+        let tests: Vec<Rc<Cell<usize>>> = shapes.iter().map(|_| Rc::new(Cell::new(0))).collect();
+        let make_list = || {
+            let mut list = HittableList::new();
+            for (shape, tests) in shapes.iter().zip(tests.iter()) {
+                list.add(Box::new(CountingHittable(shape.clone(), tests.clone())));
+            }
+            list
+        };
+        let tested_once = || {
+            let once = tests.iter().all(|tests| tests.get() <= 1);
+            tests.iter().for_each(|tests| tests.set(0));
+            once
+        };
+
+        for accelerator in [
+            Accelerator::Tree(BVHOptions::default()),
+            Accelerator::Linear(BVHOptions::default()),
+            Accelerator::Wide(BVHOptions::default()),
+            Accelerator::Grid(GridOptions::default()),
+            Accelerator::KdTree(KdTreeOptions::default()),
+        ]
+        .iter()
+        {
+            let world = World::with_accelerator(make_list(), accelerator);
+            // grazing along the quads, and through all of them
+            for ray in [
+                Ray::new(Point3::new(-2., 4.5, 0.7), Vec3::new(1., 0.01, 0.), 0.),
+                Ray::new(Point3::new(4.5, 4.5, 5.), Vec3::new(0.01, 0.01, -1.), 0.),
+            ]
+            .iter()
+            {
+                world.hit(ray, 0.001, f64::INFINITY);
+                assert!(tested_once(), "{:?}", accelerator);
+                world.occluded(ray, 0.001, f64::INFINITY);
+                assert!(tested_once(), "{:?}", accelerator);
+            }
+        }
+    }
+
+    // counts how many times the object is tested against a ray
+    struct CountingHittable(Rc<dyn Hittable>, Rc<Cell<usize>>);
+
+    impl Hittable for CountingHittable {
+        fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+            self.1.set(self.1.get() + 1);
+            self.0.hit(ray, t_min, t_max)
+        }
+
+        fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+            self.1.set(self.1.get() + 1);
+            self.0.occluded(ray, t_min, t_max)
+        }
+
+        fn bounding_box(&self) -> Option<&AABB> {
+            self.0.bounding_box()
+        }
+    }
+}
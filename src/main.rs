@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use rand::Rng;
@@ -13,17 +13,23 @@ mod color;
 use color::Color;
 
 mod geometry;
-use geometry::{Point3, Vec3};
+use geometry::{Atmosphere, Mat4, Point3, Vec3};
 
 mod hittable;
-use hittable::HittableList;
+use hittable::{
+    Accelerator, BVHOptions, CacheStatus, GridOptions, Hittable, HittableList, KdTreeOptions,
+    SplitStrategy, TraversalCounts, World,
+};
 
 mod material;
+use material::types::PhaseFunction;
 
 mod scene;
 use scene::{
-    CornellBox, CornellSmoke, Earth, FinalScene, PerlinSpheres, RandomScene, SimpleColoredLights,
-    SimpleLight,
+    BouncingSpheres, Campfire, CornellBox, CornellClouds, CornellSmoke, CornellTilted,
+    CornellTransformed, CsgShapes, CurvedShapes, Earth, FinalScene, Forest, FurBall, LightShafts,
+    Metals, PerlinSpheres, PlanarShapes, RandomScene, SdfShapes, SimpleColoredLights, SimpleLight,
+    Terrain, VoxelIsland,
 };
 
 mod texture;
@@ -36,7 +42,6 @@ fn main() {
     }
 
     let path = Path::new(&args[1]);
-    let mut file = File::create(&path).expect("could not open file for writing");
 
     // Image
     let (image_width, image_height) = match parse_dimensions(&args[2]) {
@@ -49,6 +54,63 @@ fn main() {
     let samples_per_pixel: i32 = args[3].parse().expect("invalid SAMPLES_PER_PIXEL param");
     let max_depth = args[4].parse().expect("invalid MAX_RAYTRACE_DEPTH param");
 
+    // Options, in any order
+    let mut layout = "linear";
+    let mut strategy = SplitStrategy::Sah;
+    let mut ambient_occlusion = false;
+    let mut heatmap = false;
+    let mut stats = false;
+    let mut cache = None;
+    let mut volume = None;
+    let mut vox = None;
+    let mut frames = 1;
+    for option in &args[5..] {
+        match option.as_str() {
+            "linear" | "tree" | "wide" | "grid" | "kdtree" => layout = option,
+            "sah" => strategy = SplitStrategy::Sah,
+            "median" => strategy = SplitStrategy::Median,
+            "ao" => ambient_occlusion = true,
+            "heatmap" if cfg!(feature = "heatmap") => heatmap = true,
+            "heatmap" => {
+                eprintln!("the heatmap needs the counts from building with `--features heatmap`");
+                std::process::exit(1);
+            }
+            "stats" => stats = true,
+            _ if option.starts_with("cache=") => cache = Some(PathBuf::from(&option[6..])),
+            _ if option.starts_with("volume=") => volume = Some(PathBuf::from(&option[7..])),
+            _ if option.starts_with("vox=") => vox = Some(PathBuf::from(&option[4..])),
+            _ if option.starts_with("frames=") => match option[7..].parse() {
+                Ok(n) if n > 0 => frames = n,
+                _ => {
+                    print_usage(&args[0]);
+                    std::process::exit(1);
+                }
+            },
+            _ => {
+                print_usage(&args[0]);
+                std::process::exit(1);
+            }
+        }
+    }
+    if cache.is_some() && layout != "linear" {
+        eprintln!(
+            "cache=FILE only works with the flat BVH, not with {}",
+            layout
+        );
+        std::process::exit(1);
+    }
+    let bvh_options = BVHOptions {
+        strategy,
+        ..BVHOptions::default()
+    };
+    let accelerator = match layout {
+        "tree" => Accelerator::Tree(bvh_options.clone()),
+        "wide" => Accelerator::Wide(bvh_options.clone()),
+        "grid" => Accelerator::Grid(GridOptions::default()),
+        "kdtree" => Accelerator::KdTree(KdTreeOptions::default()),
+        _ => Accelerator::Linear(bvh_options.clone()),
+    };
+
     // World
     let world: HittableList;
     let mut aperture = 0.0;
@@ -57,6 +119,7 @@ fn main() {
     let mut lookat = Point3::new(0., 0., 0.);
     let mut lookfrom = Point3::new(13., 2., 3.);
     let mut vfov = 20.;
+    let mut atmosphere = None;
 
     let scene = 7;
     match scene {
@@ -96,16 +159,155 @@ fn main() {
             vfov = 40.;
             world = FinalScene::new();
         }
+        8 => {
+            aperture = 0.1;
+            world = BouncingSpheres::new();
+        }
+        9 => {
+            aspect_ratio = 1.;
+            lookat = Point3::new(278., 278., 0.);
+            lookfrom = Point3::new(278., 278., -800.);
+            vfov = 40.;
+            world = CornellTransformed::new();
+        }
+        10 => {
+            aspect_ratio = 1.;
+            lookat = Point3::new(278., 278., 0.);
+            lookfrom = Point3::new(278., 278., -800.);
+            vfov = 40.;
+            world = CornellTilted::new();
+        }
+        11 => {
+            aspect_ratio = 1.;
+            lookat = Point3::new(0., 0., 0.);
+            lookfrom = Point3::new(0., 0., 9.);
+            vfov = 80.;
+            world = PlanarShapes::new();
+        }
+        12 => {
+            lookat = Point3::new(0., 1., 0.);
+            lookfrom = Point3::new(0., 3., 10.);
+            vfov = 35.;
+            world = CurvedShapes::new();
+        }
+        13 => {
+            lookat = Point3::new(1., 1., 0.);
+            lookfrom = Point3::new(1., 4., 12.);
+            vfov = 40.;
+            world = CsgShapes::new();
+        }
+        14 => {
+            lookat = Point3::new(0., 1., 0.);
+            lookfrom = Point3::new(0., 4., 12.);
+            vfov = 40.;
+            world = SdfShapes::new();
+        }
+        15 => {
+            lookat = Point3::new(0., 0., -2.);
+            lookfrom = Point3::new(0., 12., 14.);
+            vfov = 45.;
+            world = Terrain::new();
+        }
+        16 => {
+            aspect_ratio = 1.;
+            lookat = Point3::new(278., 278., 0.);
+            lookfrom = Point3::new(278., 278., -800.);
+            vfov = 40.;
+            world = CornellClouds::new();
+        }
+        17 => {
+            aspect_ratio = 1.;
+            background = Color::new(0., 0., 0.);
+            lookat = Point3::new(400., 200., 350.);
+            lookfrom = Point3::new(30., 300., 20.);
+            vfov = 80.;
+            atmosphere = Some(Atmosphere::new(
+                0.0005,
+                0.003,
+                PhaseFunction::HenyeyGreenstein(0.3),
+            ));
+            world = LightShafts::new();
+        }
+        18 => {
+            background = Color::new(0., 0., 0.);
+            lookat = Point3::new(0., 1.2, 0.);
+            lookfrom = Point3::new(0., 2., 6.);
+            vfov = 40.;
+            world = match &volume {
+                Some(path) => Campfire::load(path).unwrap_or_else(|error| {
+                    eprintln!("could not load the volume {}: {}", path.display(), error);
+                    std::process::exit(1);
+                }),
+                None => Campfire::new(),
+            };
+        }
+        19 => {
+            lookat = Point3::new(0., 3., 0.);
+            lookfrom = Point3::new(40., 35., 50.);
+            vfov = 40.;
+            world = match &vox {
+                Some(path) => VoxelIsland::load(path).unwrap_or_else(|error| {
+                    eprintln!("could not load the model {}: {}", path.display(), error);
+                    std::process::exit(1);
+                }),
+                None => VoxelIsland::new(),
+            };
+        }
+        20 => {
+            lookat = Point3::new(0., 0.9, 0.);
+            lookfrom = Point3::new(0.5, 1.8, 4.5);
+            vfov = 35.;
+            world = FurBall::new();
+        }
+        21 => {
+            lookat = Point3::new(20., 3., 60.);
+            lookfrom = Point3::new(0., 6., -5.);
+            vfov = 50.;
+            world = Forest::new();
+        }
+        22 => {
+            lookat = Point3::new(0., 0.5, 0.);
+            lookfrom = Point3::new(0., 7., 15.);
+            vfov = 40.;
+            world = Metals::new();
+        }
         _ => {
             aperture = 0.1;
             world = RandomScene::new();
         }
     };
+    // objects without a bounding box, like planes, are kept out of the BVH
+    let mut world = match &cache {
+        Some(path) => {
+            let (world, status) = World::with_cache(world, &bvh_options, path);
+            match status {
+                Some(CacheStatus::Loaded) => eprintln!("loaded the BVH from {}", path.display()),
+                Some(CacheStatus::Built { reason, saved }) => {
+                    eprintln!("built the BVH, as the cache couldn't be used: {}", reason);
+                    if let Err(error) = saved {
+                        eprintln!("could not save the BVH to {}: {}", path.display(), error);
+                    }
+                }
+                None => (),
+            }
+            world
+        }
+        None => World::with_accelerator(world, &accelerator),
+    };
+    if stats {
+        match world.stats() {
+            Some(stats) => eprint!("{}", stats),
+            None => eprintln!("no statistics, as there's no tree or nothing has a bounding box"),
+        }
+    }
+
+    // a scale for how far away things can shade each other, from how far away the camera is
+    let ao_distance = (&lookfrom - &lookat).length() / 4.;
 
     // Camera
     let orientation = CameraOrientation {
         lookfrom,
-        lookat,
+        lookat: lookat.clone(),
         vup: Vec3::new(0.0, 1.0, 0.0),
     };
     let settings = CameraSettings {
@@ -113,48 +315,116 @@ fn main() {
         aspect_ratio: aspect_ratio,
         aperture,
         focus_dist: 10.,
+        time0: 0.,
+        time1: 1.,
     };
     let camera = Camera::new(settings, orientation);
 
-    // Render
-    // write the PPM header to file
-    let header = format!("P3\n{} {}\n255\n", image_width, image_height);
-    file.write_all(header.as_bytes())
-        .expect("could not write to ppm file");
-
-    let mut rng = rand::thread_rng();
-    for j in (0..image_height).rev() {
-        print!("Scan lines remaining: {} \r", j);
-        io::stdout().flush().unwrap();
-        for i in 0..image_width {
-            let mut pixel_color = Color::new(0., 0., 0.);
-            for _ in 0..samples_per_pixel {
-                let u = ((i as f64) + rng.gen::<f64>()) / ((image_width - 1) as f64);
-                let v = ((j as f64) + rng.gen::<f64>()) / ((image_height - 1) as f64);
-                let r = camera.get_ray(u, v);
-                pixel_color += r.color(&world, max_depth, &background);
+    for (frame, path) in frame_paths(path, frames).iter().enumerate() {
+        if frame > 0 {
+            // a turntable: the scene turns a step at a time about the upright through lookat
+            let angle = 360. * frame as f64 / frames as f64;
+            let turn = Mat4::translation(&-&lookat)
+                .rotate(&Vec3::new(0., 1., 0.), angle)
+                .translate(&lookat);
+            if world.transform(&turn) {
+                eprintln!("rebuilt the acceleration structure for frame {}", frame);
+            }
+        }
+        let mut file = File::create(path).expect("could not open file for writing");
+
+        // Render
+        // write the PPM header to file
+        let header = format!("P3\n{} {}\n255\n", image_width, image_height);
+        file.write_all(header.as_bytes())
+            .expect("could not write to ppm file");
+
+        if heatmap {
+            // how many boxes and objects a ray through the middle of each pixel is tested against
+            let mut counts = Vec::with_capacity((image_width * image_height) as usize);
+            for j in (0..image_height).rev() {
+                for i in 0..image_width {
+                    let u = (i as f64 + 0.5) / ((image_width - 1) as f64);
+                    let v = (j as f64 + 0.5) / ((image_height - 1) as f64);
+                    TraversalCounts::take();
+                    world.hit(&camera.get_ray(u, v), 0.001, f64::INFINITY);
+                    counts.push(TraversalCounts::take().total());
+                }
             }
 
-            let pixel = color::get_pixel(pixel_color, samples_per_pixel);
-            file.write_all(pixel.as_bytes())
-                .expect("could not write to .ppm file");
+            let most = counts.iter().copied().max().unwrap_or(0).max(1);
+            eprintln!(
+                "tests per ray: {:.1} on average, {} at most",
+                counts.iter().sum::<u64>() as f64 / counts.len().max(1) as f64,
+                most
+            );
+            for count in counts {
+                // squared to undo the gamma correction, so the colours go evenly with the count
+                let color = Color::heat(count as f64 / most as f64);
+                let pixel = color::get_pixel(&color * &color, 1);
+                file.write_all(pixel.as_bytes())
+                    .expect("could not write to .ppm file");
+            }
+        } else {
+            let mut rng = rand::thread_rng();
+            for j in (0..image_height).rev() {
+                print!("Scan lines remaining: {} \r", j);
+                io::stdout().flush().unwrap();
+                for i in 0..image_width {
+                    let mut pixel_color = Color::new(0., 0., 0.);
+                    for _ in 0..samples_per_pixel {
+                        let u = ((i as f64) + rng.gen::<f64>()) / ((image_width - 1) as f64);
+                        let v = ((j as f64) + rng.gen::<f64>()) / ((image_height - 1) as f64);
+                        let r = camera.get_ray(u, v);
+                        pixel_color += if ambient_occlusion {
+                            r.ambient_occlusion(&world, ao_distance)
+                        } else {
+                            r.color(&world, max_depth, &background, atmosphere.as_ref())
+                        };
+                    }
+
+                    let pixel = color::get_pixel(pixel_color, samples_per_pixel);
+                    file.write_all(pixel.as_bytes())
+                        .expect("could not write to .ppm file");
+                }
+            }
         }
     }
 }
 
 fn print_usage(name: &str) {
-    writeln!(
-        std::io::stderr(),
-        "Usage: {} FILE DIMENSIONS SAMPLES_PER_PIXEL MAX_RAYTRACE_DEPTH",
-        name
-    )
-    .unwrap();
-    writeln!(
-        std::io::stderr(),
-        "Example: {} ./image.ppm 256x256 100 50",
+    eprintln!(
+        "Usage: {} FILE DIMENSIONS SAMPLES_PER_PIXEL MAX_RAYTRACE_DEPTH [OPTION...]",
         name
-    )
-    .unwrap();
+    );
+    eprintln!("Options:");
+    eprintln!("  linear | tree | wide | grid | kdtree");
+    eprintln!(
+        "                 store the scene in a flat BVH (the default), a tree of nodes, a flat"
+    );
+    eprintln!("                 BVH with four children to a node, a uniform grid, or a kd-tree");
+    eprintln!("  sah | median   build the BVH with the surface area heuristic (the default) or median splits");
+    eprintln!("  ao             render ambient occlusion instead of full shading");
+    eprintln!(
+        "  heatmap        render how many boxes and objects each pixel's ray is tested against"
+    );
+    eprintln!("                 (needs building with `--features heatmap`)");
+    eprintln!(
+        "  stats          print how the BVH turned out: its size, depths, leaf sizes and SAH cost"
+    );
+    eprintln!(
+        "  cache=FILE     load the flat BVH from FILE if it was saved there for this scene, or"
+    );
+    eprintln!("                 build it and save it there");
+    eprintln!(
+        "  volume=FILE    burn the density and temperature in the volume file FILE in the campfire"
+    );
+    eprintln!("  vox=FILE       show the MagicaVoxel model in FILE in place of the voxel island");
+    eprintln!(
+        "  frames=N       render N frames of the scene turning once round, to FILE with each"
+    );
+    eprintln!("                 frame's number added to its name");
+    eprintln!("Example: {} ./image.ppm 256x256 100 50", name);
 }
 
 /// Parse the string `s` as a coordinate pair, like `"400x600"`.
@@ -174,6 +444,26 @@ fn parse_dimensions(s: &str) -> Option<(i32, i32)> {
     }
 }
 
+/// Where to write each of `frames` frames going to `path`: `path` itself for a still, and
+/// otherwise with each frame's number after the file's name, before its extension, so
+/// `"turn.ppm"` gives `"turn-0003.ppm"` for frame 3.
+fn frame_paths(path: &Path, frames: usize) -> Vec<PathBuf> {
+    if frames == 1 {
+        return vec![path.to_path_buf()];
+    }
+    (0..frames)
+        .map(|frame| {
+            let mut name = path.file_stem().unwrap_or_default().to_os_string();
+            name.push(format!("-{:04}", frame));
+            if let Some(extension) = path.extension() {
+                name.push(".");
+                name.push(extension);
+            }
+            path.with_file_name(name)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -186,4 +476,21 @@ mod test {
         assert_eq!(parse_dimensions("10x20foo"), None);
         assert_eq!(parse_dimensions("10x20"), Some((10, 20)));
     }
+
+    #[test]
+    fn test_frame_paths() {
+        assert_eq!(
+            frame_paths(Path::new("out/still.ppm"), 1),
+            vec![PathBuf::from("out/still.ppm")]
+        );
+
+        // every frame gets a file of its own
+        let paths = frame_paths(Path::new("out/turn.ppm"), 12);
+        assert_eq!(paths.len(), 12);
+        assert_eq!(paths[3], PathBuf::from("out/turn-0003.ppm"));
+        assert_eq!(
+            frame_paths(Path::new("turn"), 12)[11],
+            PathBuf::from("turn-0011")
+        );
+    }
 }
@@ -0,0 +1,200 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::color::Color;
+use crate::geometry::{Onb, Ray, Vec3};
+use crate::hittable::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::{SolidColor, Texture};
+
+/// How likely light travelling through a medium is to scatter into each direction, as a function
+/// of the angle it's turned through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhaseFunction {
+    /// Every direction is equally likely.
+    Isotropic,
+    /// Henyey–Greenstein, with an asymmetry `g` between -1 and 1: positive values favour carrying
+    /// on forwards, like fog and cloud, negative ones scattering back, and 0 is isotropic.
+    HenyeyGreenstein(f64),
+    /// A mix of two Henyey–Greenstein lobes, chosen between with probability `weight` for the
+    /// first, usually to give a strong forward peak with a little back scattering as well.
+    DoubleHenyeyGreenstein { g1: f64, g2: f64, weight: f64 },
+    /// Scattering off particles much smaller than the wavelength, like the molecules in air, which
+    /// is symmetric between forwards and backwards and weakest to the sides.
+    Rayleigh,
+}
+
+impl PhaseFunction {
+    /// The probability density of scattering through an angle with the given cosine, per unit
+    /// solid angle.
+    #[cfg(test)]
+    pub fn pdf(&self, cos_theta: f64) -> f64 {
+        match *self {
+            PhaseFunction::Isotropic => 1. / (4. * PI),
+            PhaseFunction::HenyeyGreenstein(g) => henyey_greenstein(g, cos_theta),
+            PhaseFunction::DoubleHenyeyGreenstein { g1, g2, weight } => {
+                weight * henyey_greenstein(g1, cos_theta)
+                    + (1. - weight) * henyey_greenstein(g2, cos_theta)
+            }
+            PhaseFunction::Rayleigh => 3. / (16. * PI) * (1. + cos_theta * cos_theta),
+        }
+    }
+
+    /// A random scattered direction for light travelling along `direction`, drawn from this
+    /// distribution.
+    pub fn sample(&self, direction: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let cos_theta = match *self {
+            PhaseFunction::Isotropic => 1. - 2. * rng.gen::<f64>(),
+            PhaseFunction::HenyeyGreenstein(g) => sample_henyey_greenstein(g, rng.gen()),
+            PhaseFunction::DoubleHenyeyGreenstein { g1, g2, weight } => {
+                let g = if rng.gen::<f64>() < weight { g1 } else { g2 };
+                sample_henyey_greenstein(g, rng.gen())
+            }
+            PhaseFunction::Rayleigh => {
+                // invert the cumulative distribution (cos³ + 3 cos + 4) / 8 with Cardano's formula
+                let q = 4. - 8. * rng.gen::<f64>();
+                let root = f64::sqrt(q * q / 4. + 1.);
+                f64::cbrt(-q / 2. + root) + f64::cbrt(-q / 2. - root)
+            }
+        };
+        let cos_theta = cos_theta.clamp(-1., 1.);
+        let sin_theta = f64::sqrt(1. - cos_theta * cos_theta);
+        let phi = 2. * PI * rng.gen::<f64>();
+
+        Onb::from_w(direction).local(&Vec3::new(
+            sin_theta * f64::cos(phi),
+            sin_theta * f64::sin(phi),
+            cos_theta,
+        ))
+    }
+}
+
+#[cfg(test)]
+fn henyey_greenstein(g: f64, cos_theta: f64) -> f64 {
+    let denom = 1. + g * g - 2. * g * cos_theta;
+    (1. - g * g) / (4. * PI * denom * f64::sqrt(denom))
+}
+
+fn sample_henyey_greenstein(g: f64, random: f64) -> f64 {
+    if f64::abs(g) < 1e-3 {
+        return 1. - 2. * random;
+    }
+    let s = (1. - g * g) / (1. - g + 2. * g * random);
+    (1. + g * g - s * s) / (2. * g)
+}
+
+/// A material for the inside of a volume which scatters light according to a `PhaseFunction`.
+///
+/// The albedo is the fraction of light that survives each scattering, and can be different for
+/// each colour channel, or vary through space, to tint the medium.
+pub struct Anisotropic {
+    albedo: Rc<dyn Texture>,
+    phase_function: PhaseFunction,
+}
+
+impl Anisotropic {
+    pub fn new(albedo: Rc<dyn Texture>, phase_function: PhaseFunction) -> Self {
+        Self {
+            albedo,
+            phase_function,
+        }
+    }
+
+    pub fn from_color(color: Color, phase_function: PhaseFunction) -> Self {
+        Self::new(Rc::new(SolidColor { color }), phase_function)
+    }
+}
+
+impl Material for Anisotropic {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        // every direction is sampled in proportion to the phase function, so that's the whole
+        // weight there is and only the albedo is left
+        Some(Scatter {
+            ray: Ray::new(
+                rec.p.clone(),
+                self.phase_function.sample(&ray_in.direction),
+                ray_in.time,
+            ),
+            attenuation: self.albedo.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mean_cosines(phase_function: PhaseFunction) -> (f64, f64) {
+        let direction = Vec3::new(1., 2., -1.);
+        let n = 100000;
+        let (mut sum, mut sum_squares) = (0., 0.);
+        for _ in 0..n {
+            let scattered = phase_function.sample(&direction);
+            assert!(f64::abs(scattered.length() - 1.) < 1e-6);
+            let cos_theta = Vec3::dot(&scattered, &direction.unit_vector());
+            sum += cos_theta;
+            sum_squares += cos_theta * cos_theta;
+        }
+        (sum / n as f64, sum_squares / n as f64)
+    }
+
+    #[test]
+    fn test_phase_function_pdf() {
+        let phase_functions = [
+            PhaseFunction::Isotropic,
+            PhaseFunction::HenyeyGreenstein(0.7),
+            PhaseFunction::HenyeyGreenstein(-0.3),
+            PhaseFunction::DoubleHenyeyGreenstein {
+                g1: 0.8,
+                g2: -0.4,
+                weight: 0.9,
+            },
+            PhaseFunction::Rayleigh,
+        ];
+        for phase_function in phase_functions.iter() {
+            // integrated over the sphere, which is 2π times the integral over cos θ
+            let n = 100000;
+            let step = 2. / n as f64;
+            let integral = (0..n)
+                .map(|i| phase_function.pdf(-1. + (i as f64 + 0.5) * step) * step)
+                .sum::<f64>()
+                * 2.
+                * PI;
+            assert!(f64::abs(integral - 1.) < 1e-3);
+        }
+
+        let forward = PhaseFunction::HenyeyGreenstein(0.5);
+        assert!(forward.pdf(1.) > forward.pdf(-1.));
+        assert_eq!(
+            PhaseFunction::HenyeyGreenstein(0.).pdf(0.3),
+            PhaseFunction::Isotropic.pdf(0.3)
+        );
+    }
+
+    #[test]
+    fn test_phase_function_sample() {
+        // the mean cosine of Henyey–Greenstein is g
+        let (mean, _) = mean_cosines(PhaseFunction::HenyeyGreenstein(0.6));
+        assert!(f64::abs(mean - 0.6) < 0.01);
+        let (mean, _) = mean_cosines(PhaseFunction::HenyeyGreenstein(-0.3));
+        assert!(f64::abs(mean + 0.3) < 0.01);
+
+        let (mean, _) = mean_cosines(PhaseFunction::DoubleHenyeyGreenstein {
+            g1: 0.8,
+            g2: -0.4,
+            weight: 0.75,
+        });
+        assert!(f64::abs(mean - (0.75 * 0.8 - 0.25 * 0.4)) < 0.01);
+
+        // isotropic has a mean squared cosine of 1/3, and Rayleigh 2/5
+        let (mean, mean_square) = mean_cosines(PhaseFunction::Isotropic);
+        assert!(f64::abs(mean) < 0.01);
+        assert!(f64::abs(mean_square - 1. / 3.) < 0.01);
+        let (mean, mean_square) = mean_cosines(PhaseFunction::Rayleigh);
+        assert!(f64::abs(mean) < 0.01);
+        assert!(f64::abs(mean_square - 0.4) < 0.01);
+    }
+}
@@ -0,0 +1,69 @@
+use std::rc::Rc;
+
+use crate::color::Color;
+use crate::geometry::{DensityField, Point3, RandomVectorType, Ray, Vec3};
+use crate::hittable::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::{SolidColor, Texture};
+
+/// A material for a hot medium, like fire, which glows according to the temperature at each point
+/// as well as scattering light equally in all directions like `PhaseFunction::Isotropic`.
+pub struct Blackbody {
+    temperature: Box<dyn DensityField>,
+    albedo: Rc<dyn Texture>,
+    intensity: f64,
+}
+
+impl Blackbody {
+    /// `temperature` is in kelvin, and the radiance from `Color::blackbody` is multiplied by
+    /// `intensity`.
+    pub fn new(temperature: Box<dyn DensityField>, color: Color, intensity: f64) -> Blackbody {
+        Blackbody {
+            temperature,
+            albedo: Rc::new(SolidColor { color }),
+            intensity,
+        }
+    }
+}
+
+impl Material for Blackbody {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        Some(Scatter {
+            ray: Ray::new(
+                rec.p.clone(),
+                Vec3::random(RandomVectorType::InUnitSphere),
+                ray_in.time,
+            ),
+            attenuation: self.albedo.clone(),
+        })
+    }
+
+    fn emit(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        self.intensity * Color::blackbody(self.temperature.density(p))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::DensityGrid;
+    use crate::hittable::AABB;
+
+    #[test]
+    fn test_blackbody_emit() {
+        let bounds = AABB {
+            minimum: Point3::new(0., 0., 0.),
+            maximum: Point3::new(1., 1., 1.),
+        };
+        let temperature = DensityGrid::from_fn(2, 2, 2, bounds, |p| 1000. + 1000. * p.y);
+        let material = Blackbody::new(Box::new(temperature), Color::new(0.1, 0.1, 0.1), 0.5);
+
+        let p = Point3::new(0.5, 1., 0.5);
+        assert_eq!(material.emit(0., 0., &p), 0.5 * Color::blackbody(2000.));
+
+        // cold outside the grid
+        let p = Point3::new(0.5, 2., 0.5);
+        assert_eq!(material.emit(0., 0., &p), Color::new(0., 0., 0.));
+    }
+}
@@ -0,0 +1,372 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::color::Color;
+use crate::geometry::{Onb, Ray, Vec3};
+use crate::hittable::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::SolidColor;
+
+/// The complex refractive index η + ik of a metal, for red, green and blue light. k is how
+/// strongly the metal absorbs light that gets into it, which for a metal is almost all of it, and
+/// what makes it so reflective.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexIOR {
+    pub eta: [f64; 3],
+    pub k: [f64; 3],
+}
+
+impl ComplexIOR {
+    // measured indices averaged over the red, green and blue parts of the spectrum
+    pub const GOLD: ComplexIOR = ComplexIOR {
+        eta: [0.143, 0.375, 1.442],
+        k: [3.983, 2.386, 1.603],
+    };
+    pub const COPPER: ComplexIOR = ComplexIOR {
+        eta: [0.200, 0.924, 1.102],
+        k: [3.913, 2.453, 2.142],
+    };
+    pub const ALUMINUM: ComplexIOR = ComplexIOR {
+        eta: [1.657, 0.880, 0.521],
+        k: [9.224, 6.270, 4.837],
+    };
+    pub const SILVER: ComplexIOR = ComplexIOR {
+        eta: [0.155, 0.117, 0.138],
+        k: [4.828, 3.122, 2.147],
+    };
+
+    /// The fraction of light reflected when it meets the surface at an angle with cosine
+    /// `cos_theta` to the normal.
+    pub fn fresnel(&self, cos_theta: f64) -> Color {
+        let [r, g, b] = [0, 1, 2].map(|i| fresnel_conductor(cos_theta, self.eta[i], self.k[i]));
+        Color::new(r, g, b)
+    }
+}
+
+/// The exact Fresnel reflectance of a conductor for unpolarised light, averaging the two
+/// polarisations.
+fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = (cos_theta * cos_theta).min(1.);
+    let sin2 = 1. - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = f64::sqrt(t0 * t0 + 4. * eta2 * k2);
+    let a = f64::sqrt((0.5 * (a2_plus_b2 + t0)).max(0.));
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2. * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+/// The GGX, or Trowbridge–Reitz, distribution of microfacet normals, in a frame with z along the
+/// surface normal. `alpha_x` and `alpha_y` are the roughness along x and y, roughly the slope of a
+/// typical facet.
+struct Ggx {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl Ggx {
+    /// How densely the facets have normal `m`, per unit solid angle, such that their areas
+    /// projected onto the surface add up to 1.
+    #[cfg(test)]
+    fn d(&self, m: &Vec3) -> f64 {
+        if m.z <= 0. {
+            return 0.;
+        }
+        let x = m.x / self.alpha_x;
+        let y = m.y / self.alpha_y;
+        let e = x * x + y * y + m.z * m.z;
+        1. / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's Λ for the direction `w`, such that 1 / (1 + Λ) of the facets facing `w` can be
+    /// seen from it, and the rest are hidden behind others.
+    fn lambda(&self, w: &Vec3) -> f64 {
+        if w.z <= 0. {
+            return f64::INFINITY;
+        }
+        let alpha2_tan2 = (self.alpha_x * self.alpha_x * w.x * w.x
+            + self.alpha_y * self.alpha_y * w.y * w.y)
+            / (w.z * w.z);
+        (f64::sqrt(1. + alpha2_tan2) - 1.) / 2.
+    }
+
+    /// A facet normal seen from `wo`, chosen in proportion to how much of it can be seen, from
+    /// two uniform random numbers, following Heitz's "Sampling the GGX Distribution of Visible
+    /// Normals".
+    fn sample_visible_normal(&self, wo: &Vec3, u1: f64, u2: f64) -> Vec3 {
+        // stretch the view so the facets are those of a hemisphere
+        let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).unit_vector();
+
+        // pick a point on the disk the hemisphere shows to vh, squashing the half of it that's
+        // partly hidden behind the hemisphere's rim
+        let length2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length2 > 0. {
+            Vec3::new(-vh.y, vh.x, 0.) / f64::sqrt(length2)
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let t2 = Vec3::cross(&vh, &t1);
+        let r = f64::sqrt(u1);
+        let phi = 2. * PI * u2;
+        let p1 = r * f64::cos(phi);
+        let p2 = r * f64::sin(phi);
+        let s = 0.5 * (1. + vh.z);
+        let p2 = (1. - s) * f64::sqrt(1. - p1 * p1) + s * p2;
+
+        // up onto the hemisphere, and back out of the stretched space
+        let nh = p1 * t1 + p2 * t2 + f64::sqrt((1. - p1 * p1 - p2 * p2).max(0.)) * vh;
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit_vector()
+    }
+}
+
+/// A rough metal: a surface of tiny mirror facets, tilted according to the GGX distribution,
+/// that reflect with the Fresnel factor of a conductor with a complex refractive index.
+///
+/// Reflections are sampled from the facets the incoming ray can see, and weighted by Smith's
+/// height-correlated masking and shadowing, so unlike `Metal`'s fuzz, the light a rough surface
+/// reflects is right on average and matches the usual references. Light only bounces off one
+/// facet, so very rough metals come out a little darker than they should.
+///
+/// Roughness is GGX's α: 0 is a perfect mirror, around 0.1 polished and 0.5 or more quite dull.
+/// An anisotropic surface has a different roughness along and across the hit's tangent, or
+/// around and along the vertical for hits without one, as if the surface had been brushed on a
+/// lathe.
+pub struct Conductor {
+    ior: ComplexIOR,
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl Conductor {
+    /// Below this roughness a surface is treated as a perfect mirror.
+    const SMOOTH: f64 = 1e-3;
+
+    pub fn new(ior: ComplexIOR, roughness: f64) -> Self {
+        Conductor::anisotropic(ior, roughness, roughness)
+    }
+
+    /// `roughness_x` is along the tangent, and `roughness_y` across it.
+    pub fn anisotropic(ior: ComplexIOR, roughness_x: f64, roughness_y: f64) -> Self {
+        debug_assert!(roughness_x >= 0. && roughness_y >= 0.);
+        Self {
+            ior,
+            alpha_x: roughness_x,
+            alpha_y: roughness_y,
+        }
+    }
+
+    /// A frame with w along the normal and u along the direction `alpha_x` applies to.
+    fn frame(rec: &HitRecord) -> Onb {
+        let w = rec.normal.unit_vector();
+        let tangent = rec
+            .tangent
+            .clone()
+            .unwrap_or_else(|| Vec3::cross(&Vec3::new(0., 1., 0.), &w));
+        let u = &tangent - Vec3::dot(&tangent, &w) * &w;
+        if u.near_zero() {
+            return Onb::from_w(&w);
+        }
+        let u = u.unit_vector();
+        let v = Vec3::cross(&w, &u);
+        Onb { u, v, w }
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        let onb = Conductor::frame(rec);
+        let wo = onb.to_local(&-ray_in.direction.unit_vector());
+        if wo.z <= 0. {
+            return None;
+        }
+
+        let (m, weight) = if self.alpha_x.max(self.alpha_y) < Conductor::SMOOTH {
+            (Vec3::new(0., 0., 1.), 1.)
+        } else {
+            let ggx = Ggx {
+                alpha_x: self.alpha_x.max(Conductor::SMOOTH),
+                alpha_y: self.alpha_y.max(Conductor::SMOOTH),
+            };
+            let mut rng = rand::thread_rng();
+            let m = ggx.sample_visible_normal(&wo, rng.gen(), rng.gen());
+            let wi = 2. * Vec3::dot(&wo, &m) * &m - &wo;
+            if wi.z <= 0. {
+                // reflected into the surface, where it would hit another facet
+                return None;
+            }
+            // the facets are already chosen in proportion to how much of them wo sees, which
+            // leaves the fraction of those that wi sees too
+            let lambda_o = ggx.lambda(&wo);
+            (m, (1. + lambda_o) / (1. + lambda_o + ggx.lambda(&wi)))
+        };
+
+        let cos_theta = Vec3::dot(&wo, &m);
+        let wi = 2. * cos_theta * &m - &wo;
+        Some(Scatter {
+            ray: Ray::new(rec.p.clone(), onb.local(&wi), ray_in.time),
+            attenuation: Rc::new(SolidColor {
+                color: weight * self.ior.fresnel(cos_theta),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Point3, Quad, Sphere};
+    use crate::hittable::Hittable;
+
+    #[test]
+    fn test_fresnel_conductor() {
+        // at normal incidence, ((η - 1)² + k²) / ((η + 1)² + k²)
+        for ior in [
+            ComplexIOR::GOLD,
+            ComplexIOR::COPPER,
+            ComplexIOR::ALUMINUM,
+            ComplexIOR::SILVER,
+        ]
+        .iter()
+        {
+            for i in 0..3 {
+                let (eta, k) = (ior.eta[i], ior.k[i]);
+                let expected =
+                    ((eta - 1.) * (eta - 1.) + k * k) / ((eta + 1.) * (eta + 1.) + k * k);
+                assert!(f64::abs(fresnel_conductor(1., eta, k) - expected) < Vec3::TOL);
+                assert!(f64::abs(fresnel_conductor(0., eta, k) - 1.) < Vec3::TOL);
+                assert!(fresnel_conductor(0.5, eta, k) > 0.);
+                assert!(fresnel_conductor(0.5, eta, k) < 1.);
+            }
+        }
+        // gold and copper reflect red most and blue least
+        for ior in [ComplexIOR::GOLD, ComplexIOR::COPPER].iter() {
+            let [r, g, b] = [0, 1, 2].map(|i| fresnel_conductor(1., ior.eta[i], ior.k[i]));
+            assert!(r > g && g > b);
+        }
+
+        // with no absorption, it's the Fresnel reflectance of a dielectric
+        let (eta, cos_i) = (1.5, 0.6);
+        let cos_t = f64::sqrt(1. - (1. - cos_i * cos_i) / (eta * eta));
+        let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+        let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+        let expected = 0.5 * (rs * rs + rp * rp);
+        assert!(f64::abs(fresnel_conductor(cos_i, eta, 0.) - expected) < Vec3::TOL);
+    }
+
+    #[test]
+    fn test_ggx() {
+        let n = 1000;
+        let d_theta = PI / 2. / n as f64;
+        let d_phi = 2. * PI / n as f64;
+        let direction = |theta: f64, phi: f64| {
+            Vec3::new(
+                f64::sin(theta) * f64::cos(phi),
+                f64::sin(theta) * f64::sin(phi),
+                f64::cos(theta),
+            )
+        };
+        let wo = Vec3::new(0.6, 0.2, 0.5).unit_vector();
+        let mut rng = rand::thread_rng();
+
+        for ggx in [
+            Ggx {
+                alpha_x: 0.3,
+                alpha_y: 0.3,
+            },
+            Ggx {
+                alpha_x: 0.8,
+                alpha_y: 0.2,
+            },
+        ]
+        .iter()
+        {
+            // the facets' areas, projected onto the surface, add up to the surface's
+            let mut projected = 0.;
+            // and the light reflected towards wo from a white sky, were the facets perfect
+            // mirrors, integrated over the directions it comes from
+            let mut albedo = 0.;
+            for i in 0..n {
+                let theta = (i as f64 + 0.5) * d_theta;
+                let solid_angle = f64::sin(theta) * d_theta * d_phi;
+                for j in 0..n {
+                    let w = direction(theta, (j as f64 + 0.5) * d_phi);
+                    projected += ggx.d(&w) * w.z * solid_angle;
+
+                    let m = (&w + &wo).unit_vector();
+                    let g2 = 1. / (1. + ggx.lambda(&wo) + ggx.lambda(&w));
+                    albedo += ggx.d(&m) * g2 / (4. * wo.z) * solid_angle;
+                }
+            }
+            assert!(f64::abs(projected - 1.) < 1e-3);
+
+            // which is also the mean weight of the visible facets, sampled
+            let samples = 200000;
+            let mut mean_weight = 0.;
+            for _ in 0..samples {
+                let m = ggx.sample_visible_normal(&wo, rng.gen(), rng.gen());
+                assert!(f64::abs(m.length() - 1.) < 1e-6);
+                assert!(m.z > 0. && Vec3::dot(&wo, &m) > 0.);
+                let wi = 2. * Vec3::dot(&wo, &m) * &m - &wo;
+                if wi.z > 0. {
+                    let lambda_o = ggx.lambda(&wo);
+                    mean_weight += (1. + lambda_o) / (1. + lambda_o + ggx.lambda(&wi));
+                }
+            }
+            mean_weight /= samples as f64;
+            assert!(albedo < 1.);
+            assert!(f64::abs(mean_weight - albedo) < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_conductor_scatter() {
+        // a smooth one is a mirror, tinted by the Fresnel factor
+        let material = Rc::new(Conductor::new(ComplexIOR::SILVER, 0.));
+        let sphere = Sphere::new(Point3::new(0., 0., -1.), 0.5, material);
+        let ray = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.), 0.);
+        let rec = sphere.hit(&ray, 0., f64::INFINITY).unwrap();
+        let scatter = rec.material_rc.scatter(&ray, &rec).unwrap();
+        assert_eq!(scatter.ray.origin, Point3::new(0., 0., -0.5));
+        assert!((scatter.ray.direction - Vec3::new(0., 0., 1.)).length() < Vec3::TOL);
+        assert_eq!(
+            scatter.attenuation.value(rec.u, rec.v, &rec.p),
+            ComplexIOR::SILVER.fresnel(1.)
+        );
+
+        // a brushed one, on a wall facing z, spreads reflections more along x than along y
+        let material = Rc::new(Conductor::anisotropic(ComplexIOR::ALUMINUM, 0.5, 0.05));
+        let wall = Quad::new(
+            Point3::new(-1., -1., 0.),
+            Vec3::new(2., 0., 0.),
+            Vec3::new(0., 2., 0.),
+            material,
+        );
+        let ray = Ray::new(Point3::new(0., 0., 1.), Vec3::new(0., 0., -1.), 0.);
+        let rec = wall.hit(&ray, 0., f64::INFINITY).unwrap();
+        let (mut spread_x, mut spread_y) = (0., 0.);
+        for _ in 0..10000 {
+            if let Some(scatter) = rec.material_rc.scatter(&ray, &rec) {
+                let direction = scatter.ray.direction.unit_vector();
+                assert!(direction.z > 0.);
+                let color = scatter.attenuation.value(rec.u, rec.v, &rec.p);
+                assert!(color.luminance() > 0. && color.luminance() <= 1.);
+                spread_x += direction.x * direction.x;
+                spread_y += direction.y * direction.y;
+            }
+        }
+        assert!(spread_x > 10. * spread_y);
+    }
+}
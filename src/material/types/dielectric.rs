@@ -50,7 +50,7 @@ impl Material for Dielectric {
         };
 
         Some(Scatter {
-            ray: Ray::new(rec.p.clone(), direction),
+            ray: Ray::new(rec.p.clone(), direction, ray_in.time),
             attenuation: self.albedo.clone(),
         })
     }
@@ -74,7 +74,7 @@ mod test {
         let t_min = 0.;
         let t_max = std::f64::INFINITY;
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         let rec = sphere.hit(&r, t_min, t_max).unwrap();
 
         let scatter = rec.material_rc.scatter(&r, &rec).unwrap();
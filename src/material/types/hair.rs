@@ -0,0 +1,175 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::color::Color;
+use crate::geometry::{Onb, Ray, Vec3};
+use crate::hittable::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::{SolidColor, Texture};
+
+/// A simplified version of Marschner et al.'s model of light scattering from a hair fibre, for
+/// `Curve`s.
+///
+/// A fibre is treated as a glass-like cylinder which light either reflects off (R), passes
+/// straight through (TT), or passes into, reflects off the inside of the far wall and comes back
+/// out of (TRT). Each of these bounces the light out at a different angle around the fibre, and
+/// the scales on a hair's cuticle tilt them along it: R towards the root, TT and TRT towards the
+/// tip. The light is spread along the fibre by `roughness`, more so for TRT and less for TT.
+///
+/// The fibre's colour is how much light gets through it once, so TT is tinted by it once and TRT
+/// twice, while R stays white. Directions along the fibre come from the hit's tangent.
+pub struct Hair {
+    color: Rc<dyn Texture>,
+    // the width, in radians, of the R lobe along the fibre
+    roughness: f64,
+    // the tilt of the cuticle scales, in radians
+    scale_angle: f64,
+}
+
+impl Hair {
+    /// The refractive index of hair.
+    const IOR: f64 = 1.55;
+
+    /// `roughness` and `scale_angle` are in degrees; typical values are 5 to 10, and 2 to 3.
+    pub fn new(color: Rc<dyn Texture>, roughness: f64, scale_angle: f64) -> Self {
+        Self {
+            color,
+            roughness: roughness.to_radians(),
+            scale_angle: scale_angle.to_radians(),
+        }
+    }
+
+    pub fn from_color(color: Color, roughness: f64, scale_angle: f64) -> Self {
+        Self::new(Rc::new(SolidColor { color }), roughness, scale_angle)
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+        let mut rng = rand::thread_rng();
+
+        // a frame with w along the fibre, and angles measured from the incoming direction
+        let tangent = rec.tangent.clone().unwrap_or_else(|| rec.normal.clone());
+        let onb = Onb::from_w(&tangent);
+        let to_eye = onb.to_local(&-ray_in.direction.unit_vector());
+        let sin_theta_i = to_eye.z.clamp(-1., 1.);
+        let cos_theta_i = f64::sqrt(1. - sin_theta_i * sin_theta_i);
+        let phi_i = f64::atan2(to_eye.y, to_eye.x);
+
+        // where across the fibre the light comes in, and so the angles it meets the surface at
+        // and is bent to inside, seen end on
+        let h: f64 = rng.gen_range(-1. ..1.);
+        let gamma_o = f64::asin(h);
+        let eta = f64::sqrt(Hair::IOR * Hair::IOR - sin_theta_i * sin_theta_i) / cos_theta_i;
+        let gamma_t = f64::asin(h / eta);
+        let fresnel = super::super::reflectance(cos_theta_i * f64::cos(gamma_o), Hair::IOR);
+
+        // how much each lobe carries, and picking one in proportion to that
+        let color = self.color.value(rec.u, rec.v, &rec.p);
+        let transmitted = (1. - fresnel) * (1. - fresnel);
+        let weights = [
+            fresnel * Color::new(1., 1., 1.),
+            transmitted * color.clone(),
+            (transmitted * fresnel * color.clone()) * &color,
+        ];
+        let luminances: Vec<f64> = weights.iter().map(|w| w.luminance()).collect();
+        let total: f64 = luminances.iter().sum();
+        if total <= 0. {
+            return None;
+        }
+        let mut pick = rng.gen::<f64>() * total;
+        let mut p = 0;
+        while p < 2 && pick >= luminances[p] {
+            pick -= luminances[p];
+            p += 1;
+        }
+        let attenuation = (total / luminances[p]) * weights[p].clone();
+
+        // the lobe's tilt and spread along the fibre, as offsets to the mirror angle
+        let (shift, width) = match p {
+            0 => (-2. * self.scale_angle, self.roughness),
+            1 => (self.scale_angle, self.roughness / 2.),
+            _ => (3. * self.scale_angle, 2. * self.roughness),
+        };
+        let theta_o = -f64::asin(sin_theta_i) + shift + width * standard_normal(&mut rng);
+        let theta_o = theta_o.clamp(-PI / 2. + 1e-4, PI / 2. - 1e-4);
+
+        // each pass through the fibre turns the light by a little more
+        let p = p as f64;
+        let phi_o = phi_i + 2. * p * gamma_t - 2. * gamma_o + p * PI;
+
+        let direction = onb.local(&Vec3::new(
+            f64::cos(theta_o) * f64::cos(phi_o),
+            f64::cos(theta_o) * f64::sin(phi_o),
+            f64::sin(theta_o),
+        ));
+        Some(Scatter {
+            ray: Ray::new(rec.p.clone(), direction, ray_in.time),
+            attenuation: Rc::new(SolidColor { color: attenuation }),
+        })
+    }
+}
+
+/// A normally distributed random number, with mean 0 and standard deviation 1.
+fn standard_normal<R: Rng>(rng: &mut R) -> f64 {
+    // Box–Muller
+    let u1: f64 = rng.gen();
+    let u2: f64 = rng.gen();
+    f64::sqrt(-2. * f64::ln(1. - u1)) * f64::cos(2. * PI * u2)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::geometry::{Curve, CurveShape, Point3};
+    use crate::hittable::Hittable;
+
+    #[test]
+    fn test_hair_scatter() {
+        let material = Rc::new(Hair::from_color(Color::new(0.8, 0.5, 0.2), 5., 0.));
+        // a straight fibre along x
+        let fibre = Curve::new(
+            [
+                Point3::new(-1., 0., 0.),
+                Point3::new(-0.3, 0., 0.),
+                Point3::new(0.3, 0., 0.),
+                Point3::new(1., 0., 0.),
+            ],
+            0.1,
+            0.1,
+            CurveShape::Ribbon,
+            material,
+        );
+
+        // looking at the fibre side on, from a little towards its end
+        let ray = Ray::new(Point3::new(-0.5, 0., 2.), Vec3::new(0.5, 0., -2.), 0.);
+        let rec = fibre.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let sin_theta_i = Vec3::dot(&-ray.direction.unit_vector(), &Vec3::new(1., 0., 0.));
+
+        let n = 20000;
+        let (mut forwards, mut mean_sin_theta_o) = (0, 0.);
+        let mut mean = Color::new(0., 0., 0.);
+        for _ in 0..n {
+            let scatter = rec.material_rc.scatter(&ray, &rec).unwrap();
+            let direction = scatter.ray.direction.unit_vector();
+            assert!(f64::abs(scatter.ray.direction.length() - 1.) < 1e-6);
+            if direction.z < 0. {
+                forwards += 1;
+            }
+            mean_sin_theta_o += direction.x / n as f64;
+            mean += scatter.attenuation.value(0., 0., &rec.p) * (1. / n as f64);
+        }
+
+        // with no tilt from the scales, the light leaves at the mirror angle along the fibre on
+        // average, and most of it goes on through the fibre as TT
+        assert!(f64::abs(mean_sin_theta_o + sin_theta_i) < 0.02);
+        assert!(forwards as f64 / n as f64 > 0.5);
+
+        // and no light is made
+        let mean = mean.luminance();
+        assert!(0. < mean && mean < 1.);
+    }
+}
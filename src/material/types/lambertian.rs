@@ -23,7 +23,7 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
+    fn scatter(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Scatter> {
         let mut scatter_direction = &rec.normal + Vec3::random(RandomVectorType::Unit);
 
         // Catch degenerate scatter direction
@@ -32,7 +32,7 @@ impl Material for Lambertian {
         }
 
         Some(Scatter {
-            ray: Ray::new(rec.p.clone(), scatter_direction),
+            ray: Ray::new(rec.p.clone(), scatter_direction, ray_in.time),
             attenuation: self.albedo.clone(),
         })
     }
@@ -61,7 +61,7 @@ mod test {
         let t_min = 0.;
         let t_max = std::f64::INFINITY;
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         let rec = sphere.hit(&r, t_min, t_max).unwrap();
 
         let scatter = rec.material_rc.scatter(&r, &rec).unwrap();
@@ -24,6 +24,7 @@ impl Material for Metal {
         let scattered = Ray::new(
             rec.p.clone(),
             reflected + self.fuzz * Vec3::random(RandomVectorType::InUnitSphere),
+            ray_in.time,
         );
         if Vec3::dot(&scattered.direction, &rec.normal) <= 0. {
             return None;
@@ -59,7 +60,7 @@ mod test {
         let t_min = 0.;
         let t_max = std::f64::INFINITY;
 
-        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0));
+        let r = Ray::new(origin.clone(), Vec3::new(0.0, 0.0, -1.0), 0.);
         let rec = sphere.hit(&r, t_min, t_max).unwrap();
 
         let scatter = rec.material_rc.scatter(&r, &rec).unwrap();
@@ -1,11 +1,20 @@
+mod anisotropic;
+pub use anisotropic::{Anisotropic, PhaseFunction};
+
+mod blackbody;
+pub use blackbody::Blackbody;
+
+mod conductor;
+pub use conductor::{ComplexIOR, Conductor};
+
 mod dielectric;
 pub use dielectric::Dielectric;
 
 mod diffuse_light;
 pub use diffuse_light::DiffuseLight;
 
-mod isotropic;
-pub use isotropic::Isotropic;
+mod hair;
+pub use hair::Hair;
 
 mod lambertian;
 pub use lambertian::Lambertian;
@@ -1,3 +1,6 @@
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
 use std::rc::Rc;
 
 use rand::Rng;
@@ -5,21 +8,35 @@ use rand::Rng;
 use crate::color::Color;
 
 use crate::geometry::{
-    AxisAlignment, Block, ConstantMedium, Point3, Rect, RectCorner, Sphere, Vec3,
+    Annulus, AxisAlignment, Block, Capsule, Cone, ConstantMedium, Csg, Curve, CurveShape, Cylinder,
+    DensityGrid, Disk, Heightfield, HeterogeneousMedium, Mat4, MovingSphere, NoiseDensity, Plane,
+    Point3, Quad, RandomVectorType, Rect, RectCorner, Sphere, TextureDensity, Torus, Triangle,
+    Vec3, VolumeFile, VoxelGrid,
 };
 
-use crate::hittable::instance::{Rotate, Translate};
-use crate::hittable::{BVHNode, Hittable, HittableList};
+use crate::geometry::sdf::{self, Mandelbulb, Repeat, RoundBox, SdfObject, SmoothUnion, Twist};
 
-use crate::material::types::{Dielectric, DiffuseLight, Lambertian, Metal};
+use crate::hittable::instance::{Rotate, Scale, Transform, Translate};
+use crate::hittable::{BVHNode, BVHOptions, Hittable, HittableList, LinearBVH, Tlas, AABB};
+
+use crate::material::types::{
+    Anisotropic, Blackbody, ComplexIOR, Conductor, Dielectric, DiffuseLight, Hair, Lambertian,
+    Metal, PhaseFunction,
+};
 use crate::material::Material;
 
-use crate::texture::{Checker, ImageTexture, Noise, NoiseStrategy, SolidColor};
+use crate::texture::{Checker, ImageTexture, Noise, NoiseStrategy, Perlin, SolidColor};
 
 pub struct RandomScene;
 
 impl RandomScene {
     pub fn new() -> HittableList {
+        RandomScene::build(false)
+    }
+
+    /// If `bouncing` is set, the small diffuse spheres move upwards by a random amount over the
+    /// shutter interval `[0, 1]`.
+    fn build(bouncing: bool) -> HittableList {
         let mut world = HittableList::new();
 
         let ground_texture = Checker {
@@ -31,9 +48,12 @@ impl RandomScene {
             }),
         };
         let ground_material = Lambertian::new(Rc::new(ground_texture));
-        let ground_sphere =
-            Sphere::new(Point3::new(0., -1000., 0.), 1000., Rc::new(ground_material));
-        world.add(Box::new(ground_sphere));
+        let ground = Plane::new(
+            Point3::new(0., -1e-4, 0.),
+            Vec3::new(0., 1., 0.),
+            Rc::new(ground_material),
+        );
+        world.add(Box::new(ground));
 
         let mut rng = rand::thread_rng();
         let ref_point = Point3::new(4.0, 0.2, 0.);
@@ -56,6 +76,13 @@ impl RandomScene {
                             color: Color::random(0., 1.) * Color::random(0., 1.),
                         });
                         material = Rc::new(Lambertian::new(albedo));
+                        if bouncing {
+                            let center1 = &center + Vec3::new(0., rng.gen_range(0.0..0.5), 0.);
+                            world.add(Box::new(MovingSphere::new(
+                                center, center1, 0., 1., 0.2, material,
+                            )));
+                            continue;
+                        }
                     } else if random_material < 0.85 {
                         // metal
                         let albedo = Rc::new(SolidColor {
@@ -95,6 +122,14 @@ impl RandomScene {
     }
 }
 
+pub struct BouncingSpheres;
+
+impl BouncingSpheres {
+    pub fn new() -> HittableList {
+        RandomScene::build(true)
+    }
+}
+
 pub struct PerlinSpheres;
 
 impl PerlinSpheres {
@@ -104,12 +139,12 @@ impl PerlinSpheres {
         let texture = Rc::new(Noise::new(NoiseStrategy::PerlinInterpolation, 4.));
         let material = Rc::new(Lambertian::new(texture));
 
-        let sphere1 = Box::new(Sphere::new(
-            Point3::new(0., -1000., 0.),
-            1000.,
+        let ground = Box::new(Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
             material.clone(),
         ));
-        world.add(sphere1);
+        world.add(ground);
 
         let sphere2 = Box::new(Sphere::new(Point3::new(0., 2., 0.), 2., material.clone()));
         world.add(sphere2);
@@ -133,6 +168,343 @@ impl Earth {
     }
 }
 
+pub struct PlanarShapes;
+
+impl PlanarShapes {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let left_red = Rc::new(Lambertian::from_color(Color::new(1., 0.2, 0.2)));
+        let back_green = Rc::new(Lambertian::from_color(Color::new(0.2, 1., 0.2)));
+        let right_blue = Rc::new(Lambertian::from_color(Color::new(0.2, 0.2, 1.)));
+        let upper_orange = Rc::new(Lambertian::from_color(Color::new(1., 0.5, 0.)));
+        let lower_teal = Rc::new(Lambertian::from_color(Color::new(0.2, 0.8, 0.8)));
+
+        world.add(Box::new(Quad::new(
+            Point3::new(-3., -2., 5.),
+            Vec3::new(0., 0., -4.),
+            Vec3::new(0., 4., 0.),
+            left_red,
+        )));
+        world.add(Box::new(Triangle::new(
+            Point3::new(-2., -2., 0.),
+            Point3::new(2., -2., 0.),
+            Point3::new(0., 2., 0.),
+            back_green,
+        )));
+        world.add(Box::new(Quad::new(
+            Point3::new(3., -2., 1.),
+            Vec3::new(0., 0., 4.),
+            Vec3::new(0., 4., 0.),
+            right_blue,
+        )));
+        world.add(Box::new(Disk::new(
+            Point3::new(0., 3., 3.),
+            Vec3::new(0., -1., 0.),
+            2.,
+            upper_orange,
+        )));
+        world.add(Box::new(Annulus::new(
+            Point3::new(0., -3., 3.),
+            Vec3::new(0., 1., 0.),
+            1.,
+            2.,
+            lower_teal,
+        )));
+
+        world
+    }
+}
+
+pub struct CurvedShapes;
+
+impl CurvedShapes {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let checker = Rc::new(Checker {
+            odd: Rc::new(SolidColor {
+                color: Color::new(0.2, 0.3, 0.1),
+            }),
+            even: Rc::new(SolidColor {
+                color: Color::new(0.9, 0.9, 0.9),
+            }),
+        });
+        world.add(Box::new(Plane::new(
+            Point3::new(0., -1e-4, 0.),
+            Vec3::new(0., 1., 0.),
+            Rc::new(Lambertian::new(checker)),
+        )));
+
+        let red = Rc::new(Lambertian::from_color(Color::new(0.8, 0.2, 0.2)));
+        let teal = Rc::new(Lambertian::from_color(Color::new(0.2, 0.7, 0.7)));
+        let gold = Rc::new(Metal::new(
+            Rc::new(SolidColor {
+                color: Color::new(0.8, 0.6, 0.2),
+            }),
+            0.1,
+        ));
+        let steel = Rc::new(Metal::new(
+            Rc::new(SolidColor {
+                color: Color::new(0.7, 0.7, 0.7),
+            }),
+            0.3,
+        ));
+        let glass = Rc::new(Dielectric::new(1.5));
+
+        world.add(Box::new(Cylinder::new(
+            Point3::new(-3., 0., 0.),
+            Point3::new(-3., 2., 0.),
+            0.8,
+            red,
+        )));
+        world.add(Box::new(Cone::new(
+            Point3::new(-1., 0., -1.),
+            0.8,
+            Point3::new(-1., 2.5, -1.),
+            0.,
+            teal,
+        )));
+        world.add(Box::new(Torus::new(
+            Point3::new(1., 1.2, 0.),
+            Vec3::new(0., 1., 1.),
+            0.9,
+            0.3,
+            gold,
+        )));
+        world.add(Box::new(Capsule::new(
+            Point3::new(3., 0.6, 0.5),
+            Point3::new(3.5, 2., -0.5),
+            0.6,
+            glass,
+        )));
+        world.add(Box::new(Cylinder::open(
+            Point3::new(-2., 0.3, 2.),
+            Point3::new(0., 0.3, 2.5),
+            0.3,
+            steel,
+        )));
+
+        world
+    }
+}
+
+pub struct CsgShapes;
+
+impl CsgShapes {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let checker = Rc::new(Checker {
+            odd: Rc::new(SolidColor {
+                color: Color::new(0.2, 0.3, 0.1),
+            }),
+            even: Rc::new(SolidColor {
+                color: Color::new(0.9, 0.9, 0.9),
+            }),
+        });
+        world.add(Box::new(Plane::new(
+            Point3::new(0., -1e-4, 0.),
+            Vec3::new(0., 1., 0.),
+            Rc::new(Lambertian::new(checker)),
+        )));
+
+        let glass = Rc::new(Dielectric::new(1.5));
+        let red = Rc::new(Lambertian::from_color(Color::new(0.8, 0.2, 0.2)));
+        let yellow = Rc::new(Lambertian::from_color(Color::new(0.9, 0.8, 0.2)));
+
+        // a biconvex lens, standing on its edge
+        world.add(Box::new(Csg::intersection(
+            Box::new(Sphere::new(Point3::new(-3., 1., -1.6), 2., glass.clone())),
+            Box::new(Sphere::new(Point3::new(-3., 1., 1.6), 2., glass.clone())),
+        )));
+
+        // a glass bubble
+        world.add(Box::new(Csg::difference(
+            Box::new(Sphere::new(Point3::new(-0.8, 1., 0.), 1., glass.clone())),
+            Box::new(Sphere::new(Point3::new(-0.8, 1., 0.), 0.9, glass.clone())),
+        )));
+
+        // a block with a bite out of its top corner, showing the yellow inside
+        world.add(Box::new(Csg::difference(
+            Box::new(Block::new(
+                Point3::new(0.8, 0., -1.),
+                Point3::new(2.8, 2., 1.),
+                red,
+            )),
+            Box::new(Sphere::new(Point3::new(2.8, 2., 1.), 1.2, yellow)),
+        )));
+
+        // a puff of smoke shaped like two overlapping balls
+        world.add(Box::new(ConstantMedium::from_color(
+            Box::new(Csg::union(
+                Box::new(Sphere::new(Point3::new(4., 0.8, -0.5), 0.8, glass.clone())),
+                Box::new(Sphere::new(Point3::new(4.8, 0.8, 0.3), 0.8, glass)),
+            )),
+            2.,
+            Color::new(0.2, 0.4, 0.9),
+        )));
+
+        world
+    }
+}
+
+pub struct SdfShapes;
+
+impl SdfShapes {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let ground = Rc::new(Lambertian::from_color(Color::new(0.5, 0.5, 0.5)));
+        world.add(Box::new(Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            ground,
+        )));
+
+        let red = Rc::new(Lambertian::from_color(Color::new(0.8, 0.2, 0.2)));
+        let teal = Rc::new(Lambertian::from_color(Color::new(0.2, 0.7, 0.7)));
+        let white = Rc::new(Lambertian::from_color(Color::new(0.9, 0.9, 0.9)));
+        let gold = Rc::new(Metal::new(
+            Rc::new(SolidColor {
+                color: Color::new(0.8, 0.6, 0.2),
+            }),
+            0.2,
+        ));
+        let glass = Rc::new(Dielectric::new(1.5));
+
+        // a rounded box with a ring melted around its middle
+        world.add(Box::new(SdfObject::new(
+            Box::new(SmoothUnion {
+                sdfs: vec![
+                    Box::new(RoundBox {
+                        center: Point3::new(-4., 0.8, 0.),
+                        half_extents: Vec3::new(0.5, 0.5, 0.5),
+                        radius: 0.3,
+                    }),
+                    Box::new(sdf::Torus {
+                        center: Point3::new(-4., 0.8, 0.),
+                        major_radius: 0.9,
+                        minor_radius: 0.15,
+                    }),
+                ],
+                smoothness: 0.2,
+            }),
+            AABB {
+                minimum: Point3::new(-5.1, 0., -1.1),
+                maximum: Point3::new(-2.9, 1.6, 1.1),
+            },
+            red,
+        )));
+
+        let ball = |x: f64, y: f64, z: f64, radius: f64| -> Box<dyn sdf::Sdf> {
+            Box::new(sdf::Sphere {
+                center: Point3::new(x, y, z),
+                radius,
+            })
+        };
+        world.add(Box::new(SdfObject::new(
+            Box::new(SmoothUnion {
+                sdfs: vec![
+                    ball(-1.8, 0.7, 0., 0.7),
+                    ball(-1.2, 1.5, 0.2, 0.5),
+                    ball(-1.3, 0.6, 0.8, 0.5),
+                ],
+                smoothness: 0.5,
+            }),
+            AABB {
+                minimum: Point3::new(-2.6, 0., -0.8),
+                maximum: Point3::new(-0.6, 2.1, 1.4),
+            },
+            teal,
+        )));
+
+        world.add(Box::new(Translate::new(
+            Box::new(SdfObject::new(
+                Box::new(Twist {
+                    sdf: Box::new(RoundBox {
+                        center: Point3::new(0., 0., 0.),
+                        half_extents: Vec3::new(0.5, 1., 0.2),
+                        radius: 0.05,
+                    }),
+                    rate: 1.5,
+                }),
+                AABB {
+                    minimum: Point3::new(-0.8, -1.1, -0.8),
+                    maximum: Point3::new(0.8, 1.1, 0.8),
+                },
+                gold,
+            )),
+            Vec3::new(0.8, 1.1, 0.),
+        )));
+
+        world.add(Box::new(Translate::new(
+            Box::new(Scale::uniform(
+                Box::new(SdfObject::new(
+                    Box::new(Mandelbulb {
+                        power: 8.,
+                        iterations: 10,
+                    }),
+                    AABB {
+                        minimum: Point3::new(-1.2, -1.2, -1.2),
+                        maximum: Point3::new(1.2, 1.2, 1.2),
+                    },
+                    glass,
+                )),
+                0.9,
+            )),
+            Vec3::new(3.4, 1.1, 0.),
+        )));
+
+        // a row of little spheres, cut off by the bounds
+        world.add(Box::new(SdfObject::new(
+            Box::new(Repeat {
+                sdf: ball(0., 0.2, 2.5, 0.2),
+                period: Vec3::new(0.6, 0., 0.),
+            }),
+            AABB {
+                minimum: Point3::new(-4.5, 0., 2.3),
+                maximum: Point3::new(4.5, 0.4, 2.7),
+            },
+            white,
+        )));
+
+        world
+    }
+}
+
+pub struct Terrain;
+
+impl Terrain {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let perlin = Perlin::new(NoiseStrategy::PerlinInterpolation);
+        let grass = Rc::new(Lambertian::from_color(Color::new(0.3, 0.5, 0.2)));
+        world.add(Box::new(Heightfield::from_perlin(
+            &perlin,
+            128,
+            3.,
+            6,
+            Point3::new(-11., 0., -10.),
+            Vec3::new(10., 2., 14.),
+            grass,
+        )));
+
+        // the Earth in relief, using its own brightness as height, with the map draped over it
+        let earthmap = concat!(env!("CARGO_MANIFEST_DIR"), "/src/texture/earthmap.jpg");
+        let earth = Rc::new(Lambertian::new(Rc::new(ImageTexture::from_file(earthmap))));
+        world.add(Box::new(Heightfield::from_image(
+            earthmap,
+            Point3::new(1., 0., -5.),
+            Vec3::new(10., 0.5, 5.),
+            earth,
+        )));
+
+        world
+    }
+}
+
 pub struct SimpleLight;
 
 impl SimpleLight {
@@ -277,6 +649,99 @@ impl CornellBox {
     }
 }
 
+pub struct CornellTilted;
+
+impl CornellTilted {
+    pub fn new() -> HittableList {
+        let mut world = CornellBoxBase::new();
+
+        let white = Rc::new(Lambertian::new(Rc::new(SolidColor {
+            color: Color::new(0.73, 0.73, 0.73),
+        })));
+
+        // the short block, tipped back about the x-axis onto one of its edges
+        let box1 = Box::new(Block::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(165., 165., 165.),
+            white.clone(),
+        ));
+        let box1_r = Box::new(Rotate::about_axis(box1, Vec3::new(1., 0., 0.), -30.));
+        let box1_rt = Box::new(Translate::new(box1_r, Vec3::new(130., 0., 65.)));
+        world.add(box1_rt);
+
+        // the tall block, leaning against the green wall about the z-axis
+        let box2 = Box::new(Block::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(165., 330., 165.),
+            white.clone(),
+        ));
+        let box2_r = Box::new(Rotate::about_axis(box2, Vec3::new(0., 0., 1.), 10.));
+        let box2_rt = Box::new(Translate::new(box2_r, Vec3::new(400., 0., 295.)));
+        world.add(box2_rt);
+
+        // a squashed metal sphere resting on the floor
+        let metal = Rc::new(Metal::new(
+            Rc::new(SolidColor {
+                color: Color::new(0.8, 0.85, 0.88),
+            }),
+            0.,
+        ));
+        let sphere = Box::new(Sphere::new(Point3::new(0., 0., 0.), 1., metal));
+        let ellipsoid = Box::new(Scale::new(sphere, Vec3::new(80., 40., 80.)));
+        world.add(Box::new(Translate::new(
+            ellipsoid,
+            Vec3::new(420., 40., 120.),
+        )));
+
+        // and a glass marble beside the short block
+        let glass = Rc::new(Dielectric::new(1.5));
+        let marble = Box::new(Scale::uniform(
+            Box::new(Sphere::new(Point3::new(0., 0., 0.), 1., glass)),
+            45.,
+        ));
+        world.add(Box::new(Translate::new(marble, Vec3::new(75., 45., 120.))));
+
+        world
+    }
+}
+
+pub struct CornellTransformed;
+
+impl CornellTransformed {
+    pub fn new() -> HittableList {
+        let mut world = CornellBoxBase::new();
+
+        let white = Rc::new(Lambertian::new(Rc::new(SolidColor {
+            color: Color::new(0.73, 0.73, 0.73),
+        })));
+
+        // a block balanced on one of its corners
+        let block = Box::new(Block::new(
+            Point3::new(-82.5, -82.5, -82.5),
+            Point3::new(82.5, 82.5, 82.5),
+            white.clone(),
+        ));
+        let balanced = Mat4::identity()
+            .rotate(&Vec3::new(1., 0., 0.), 45.)
+            .rotate(&Vec3::new(0., 0., 1.), 35.26)
+            .translate(&Vec3::new(190., 142.9, 170.));
+        world.add(Box::new(Transform::new(block, balanced)));
+
+        // a glass ellipsoid, stretched along its local z-axis and pointed at the top-left corner
+        let glass = Rc::new(Dielectric::new(1.5));
+        let sphere = Box::new(Sphere::new(Point3::new(0., 0., 0.), 1., glass));
+        let stretched = Mat4::scaling(&Vec3::new(60., 60., 140.));
+        let pointed = Mat4::look_at(
+            &Point3::new(380., 200., 330.),
+            &Point3::new(0., 555., 555.),
+            &Vec3::new(0., 1., 0.),
+        );
+        world.add(Box::new(Transform::new(sphere, pointed * stretched)));
+
+        world
+    }
+}
+
 pub struct CornellSmoke;
 
 impl CornellSmoke {
@@ -318,6 +783,451 @@ impl CornellSmoke {
     }
 }
 
+pub struct CornellClouds;
+
+impl CornellClouds {
+    pub fn new() -> HittableList {
+        let mut world = CornellBoxBase::new();
+
+        let white = Rc::new(Lambertian::from_color(Color::new(0.73, 0.73, 0.73)));
+
+        // a plume of dark smoke, spreading out and thinning as it rises
+        let bounds = AABB {
+            minimum: Point3::new(75., 0., 200.),
+            maximum: Point3::new(275., 450., 400.),
+        };
+        let plume = DensityGrid::from_fn(32, 64, 32, bounds.clone(), |p| {
+            let (dx, dz) = (p.x - 175., p.z - 300.);
+            let width = 15. + 0.15 * p.y;
+            let falloff = f64::exp(-(dx * dx + dz * dz) / (2. * width * width));
+            0.05 * falloff * (1. - p.y / 450.)
+        });
+        world.add(Box::new(HeterogeneousMedium::from_color(
+            Box::new(Block::new(bounds.minimum, bounds.maximum, white.clone())),
+            Box::new(plume),
+            Color::new(0.2, 0.2, 0.2),
+        )));
+
+        // a cloud, which like real ones scatters mostly forwards with a little back towards the
+        // light
+        let cloud = NoiseDensity {
+            perlin: Perlin::new(NoiseStrategy::PerlinInterpolation),
+            frequency: 0.02,
+            depth: 6,
+            density: 0.03,
+        };
+        let cloud_phase = PhaseFunction::DoubleHenyeyGreenstein {
+            g1: 0.8,
+            g2: -0.3,
+            weight: 0.9,
+        };
+        world.add(Box::new(HeterogeneousMedium::new(
+            Box::new(Sphere::new(
+                Point3::new(390., 390., 300.),
+                110.,
+                white.clone(),
+            )),
+            Box::new(cloud),
+            Rc::new(Anisotropic::from_color(Color::new(1., 1., 1.), cloud_phase)),
+        )));
+
+        // a slab of marbled haze
+        let marble = TextureDensity {
+            texture: Rc::new(Noise::new(NoiseStrategy::PerlinInterpolation, 0.05)),
+            max_density: 0.04,
+        };
+        world.add(Box::new(HeterogeneousMedium::from_color(
+            Box::new(Block::new(
+                Point3::new(300., 0., 80.),
+                Point3::new(480., 160., 260.),
+                white,
+            )),
+            Box::new(marble),
+            Color::new(0.8, 0.5, 0.3),
+        )));
+
+        world
+    }
+}
+
+pub struct LightShafts;
+
+impl LightShafts {
+    /// A closed room lit only through a window by a bright light outside, meant to be filled with
+    /// an `Atmosphere` to show the shafts of light.
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let white = Rc::new(Lambertian::from_color(Color::new(0.73, 0.73, 0.73)));
+        let walls = [
+            (AxisAlignment::XZ, 0.),
+            (AxisAlignment::XZ, 555.),
+            (AxisAlignment::XY, 0.),
+            (AxisAlignment::XY, 555.),
+            (AxisAlignment::YZ, 0.),
+        ];
+        for &(axes, normal_plane) in walls.iter() {
+            world.add(Box::new(Rect::new(
+                axes,
+                RectCorner(555., 555.),
+                RectCorner(0., 0.),
+                normal_plane,
+                white.clone(),
+            )));
+        }
+
+        // the last wall, around a window from y = 250 to 450 and z = 150 to 400
+        let window_wall = [
+            (0., 0., 250., 555.),
+            (450., 0., 555., 555.),
+            (250., 0., 450., 150.),
+            (250., 400., 450., 555.),
+        ];
+        for &(y0, z0, y1, z1) in window_wall.iter() {
+            world.add(Box::new(Rect::new(
+                AxisAlignment::YZ,
+                RectCorner(y1, z1),
+                RectCorner(y0, z0),
+                555.,
+                white.clone(),
+            )));
+        }
+
+        // high up outside, so the light comes down through the window
+        let light = Rc::new(DiffuseLight::new(Color::new(20., 20., 20.)));
+        world.add(Box::new(Rect::new(
+            AxisAlignment::YZ,
+            RectCorner(1200., 555.),
+            RectCorner(600., 0.),
+            800.,
+            light,
+        )));
+
+        world.add(Box::new(Block::new(
+            Point3::new(330., 0., 300.),
+            Point3::new(430., 120., 400.),
+            white,
+        )));
+
+        world
+    }
+}
+
+pub struct Campfire;
+
+impl Campfire {
+    pub fn new() -> HittableList {
+        // stand-in for a simulation's output: a flame narrowing as it rises, hottest at the bottom
+        // in the middle, broken up by noise
+        let bounds = AABB {
+            minimum: Point3::new(-1., 0., -1.),
+            maximum: Point3::new(1., 3., 1.),
+        };
+        let dims = [32, 48, 32];
+        let perlin = Perlin::new(NoiseStrategy::PerlinInterpolation);
+        let (mut density, mut temperature) = (Vec::new(), Vec::new());
+        for k in 0..dims[2] {
+            for j in 0..dims[1] {
+                for i in 0..dims[0] {
+                    let x = -1. + 2. * i as f64 / (dims[0] - 1) as f64;
+                    let y = 3. * j as f64 / (dims[1] - 1) as f64;
+                    let z = -1. + 2. * k as f64 / (dims[2] - 1) as f64;
+                    let turbulence = perlin.turbulence(&Point3::new(3. * x, 2. * y, 3. * z), 5);
+                    let radius = 0.9 * (1. - y / 3.2) * (0.7 + turbulence);
+                    let falloff = f64::max(1. - f64::sqrt(x * x + z * z) / radius, 0.);
+                    density.push(4. * falloff);
+                    temperature.push(1200. + 1000. * falloff * (1. - y / 4.));
+                }
+            }
+        }
+        Campfire::with_volume(VolumeFile {
+            dims,
+            bounds,
+            channels: vec![density, temperature],
+        })
+    }
+
+    /// The campfire burning the volume in the file at `path`, which needs density and temperature
+    /// channels, where its bounds put it.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<HittableList> {
+        let volume = VolumeFile::load(path)?;
+        if volume.channels.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a fire needs density and temperature channels",
+            ));
+        }
+        Ok(Campfire::with_volume(volume))
+    }
+
+    fn with_volume(volume: VolumeFile) -> HittableList {
+        let mut world = HittableList::new();
+
+        let ground = Rc::new(Lambertian::from_color(Color::new(0.4, 0.35, 0.3)));
+        world.add(Box::new(Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            ground,
+        )));
+
+        let white = Rc::new(Lambertian::from_color(Color::new(1., 1., 1.)));
+        let fire = Blackbody::new(
+            Box::new(volume.grid(1).unwrap()),
+            Color::new(0.2, 0.2, 0.2),
+            0.3,
+        );
+        world.add(Box::new(HeterogeneousMedium::new(
+            Box::new(Block::new(
+                volume.bounds.minimum.clone(),
+                volume.bounds.maximum.clone(),
+                white,
+            )),
+            Box::new(volume.grid(0).unwrap()),
+            Rc::new(fire),
+        )));
+
+        // some logs, lit only by the fire
+        let wood = Rc::new(Lambertian::from_color(Color::new(0.3, 0.15, 0.05)));
+        for &angle in [0., 60., 120.].iter() {
+            world.add(Box::new(Rotate::new(
+                Box::new(Capsule::new(
+                    Point3::new(-1.3, 0.15, 0.),
+                    Point3::new(1.3, 0.15, 0.),
+                    0.15,
+                    wood.clone(),
+                )),
+                angle,
+            )));
+        }
+
+        world
+    }
+}
+
+pub struct VoxelIsland;
+
+impl VoxelIsland {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let materials: Vec<Rc<dyn Material>> = vec![
+            Rc::new(Lambertian::from_color(Color::new(0.3, 0.6, 0.2))),
+            Rc::new(Lambertian::from_color(Color::new(0.45, 0.3, 0.15))),
+            Rc::new(Metal::new(
+                Rc::new(SolidColor {
+                    color: Color::new(0.3, 0.5, 0.8),
+                }),
+                0.1,
+            )),
+            Rc::new(DiffuseLight::new(Color::new(8., 6., 2.))),
+        ];
+        let (grass, dirt, water, lantern) = (1, 2, 3, 4);
+
+        // hills of dirt topped with grass, with water filling the low ground up to level 4
+        let dims = [48, 16, 48];
+        let perlin = Perlin::new(NoiseStrategy::PerlinInterpolation);
+        let mut cells = vec![0; dims[0] * dims[1] * dims[2]];
+        for k in 0..dims[2] {
+            for i in 0..dims[0] {
+                let p = Point3::new(i as f64 / 12., 0., k as f64 / 12.);
+                let height = (2. + 20. * perlin.turbulence(&p, 4)) as usize;
+                for j in 0..usize::min(usize::max(height, 4), dims[1]) {
+                    cells[i + dims[0] * (j + dims[1] * k)] = if j >= height {
+                        water
+                    } else if j + 1 == height {
+                        grass
+                    } else {
+                        dirt
+                    };
+                }
+            }
+        }
+        // lanterns standing on a few of the hills
+        for &(i, k) in [(10, 12), (30, 20), (20, 36), (38, 40)].iter() {
+            let top = (0..dims[1])
+                .take_while(|&j| cells[i + dims[0] * (j + dims[1] * k)] != 0)
+                .count();
+            if top < dims[1] {
+                cells[i + dims[0] * (top + dims[1] * k)] = lantern;
+            }
+        }
+
+        world.add(Box::new(VoxelGrid::new(
+            dims,
+            cells,
+            materials,
+            Point3::new(-24., 0., -24.),
+            1.,
+        )));
+
+        world
+    }
+
+    /// The model in the MagicaVoxel file at `path` in place of the island, with voxels one unit
+    /// across from the island's corner, so one 48 voxels wide fills the view as the island does.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<HittableList> {
+        let mut world = HittableList::new();
+        world.add(Box::new(VoxelGrid::from_vox(
+            path,
+            Point3::new(-24., 0., -24.),
+            1.,
+        )?));
+        Ok(world)
+    }
+}
+
+pub struct FurBall;
+
+impl FurBall {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+        let mut rng = rand::thread_rng();
+
+        let ground = Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            Rc::new(Lambertian::from_color(Color::new(0.35, 0.25, 0.15))),
+        );
+        world.add(Box::new(ground));
+
+        // a ball covered in ginger fur, combed back and drooping a little under its own weight
+        let center = Point3::new(0., 1., 0.);
+        let radius = 0.7;
+        world.add(Box::new(Sphere::new(
+            center.clone(),
+            radius,
+            Rc::new(Lambertian::from_color(Color::new(0.3, 0.12, 0.04))),
+        )));
+        let fur: Rc<dyn Material> =
+            Rc::new(Hair::from_color(Color::new(0.85, 0.45, 0.15), 8., 2.5));
+        let comb = Vec3::new(0., 0., -0.15);
+        let droop = Vec3::new(0., -0.12, 0.);
+        for _ in 0..6000 {
+            let normal = Vec3::random(RandomVectorType::Unit);
+            let root = &center + radius * &normal;
+            let length = rng.gen_range(0.25..0.35);
+            let wiggle = 0.05 * Vec3::random(RandomVectorType::InUnitSphere);
+            let points = [
+                root.clone(),
+                &root + (length / 3.) * &normal,
+                &root + (2. * length / 3.) * &normal + 0.5 * &comb + &wiggle,
+                &root + length * &normal + &comb + &droop + wiggle,
+            ];
+            world.add(Box::new(Curve::new(
+                points,
+                0.008,
+                0.002,
+                CurveShape::Ribbon,
+                fur.clone(),
+            )));
+        }
+
+        // tufts of grass around it, as round blades bending away from their roots
+        let grass: Rc<dyn Material> = Rc::new(Lambertian::from_color(Color::new(0.25, 0.5, 0.1)));
+        for _ in 0..3000 {
+            let root = Point3::new(rng.gen_range(-3. ..3.), 0., rng.gen_range(-3. ..2.));
+            if (&root - Point3::new(0., 0., 0.)).length() < 0.6 {
+                continue;
+            }
+            let height = rng.gen_range(0.2..0.5);
+            let lean = 0.4 * height * Vec3::random(RandomVectorType::InUnitDisk);
+            let lean = Vec3::new(lean.x, 0., lean.y);
+            let points = [
+                root.clone(),
+                &root + Vec3::new(0., height / 3., 0.),
+                &root + Vec3::new(0., 2. * height / 3., 0.) + 0.4 * &lean,
+                &root + Vec3::new(0., height, 0.) + lean,
+            ];
+            world.add(Box::new(Curve::new(
+                points,
+                0.015,
+                0.002,
+                CurveShape::Cylinder,
+                grass.clone(),
+            )));
+        }
+
+        world
+    }
+}
+
+pub struct Forest;
+
+impl Forest {
+    /// A low-poly fir tree of triangles, standing on the origin and about 5 high: an
+    /// eight-sided trunk and three cones of foliage.
+    fn tree() -> Rc<dyn Hittable> {
+        let bark: Rc<dyn Material> = Rc::new(Lambertian::from_color(Color::new(0.3, 0.2, 0.1)));
+        let leaves: Rc<dyn Material> = Rc::new(Lambertian::from_color(Color::new(0.1, 0.35, 0.12)));
+        let around = |i: usize, sides: usize, radius: f64, y: f64| {
+            let angle = 2. * PI * i as f64 / sides as f64;
+            Point3::new(radius * f64::cos(angle), y, radius * f64::sin(angle))
+        };
+
+        let mut triangles: Vec<Rc<dyn Hittable>> = Vec::new();
+        for i in 0..8 {
+            let (a, b) = (around(i, 8, 0.2, 0.), around(i + 1, 8, 0.2, 0.));
+            let (c, d) = (around(i, 8, 0.15, 1.5), around(i + 1, 8, 0.15, 1.5));
+            triangles.push(Rc::new(Triangle::new(
+                a,
+                b.clone(),
+                c.clone(),
+                bark.clone(),
+            )));
+            triangles.push(Rc::new(Triangle::new(b, d, c, bark.clone())));
+        }
+        for &(bottom, top, radius) in [(1., 3., 1.6), (2.2, 4.2, 1.2), (3.3, 5.2, 0.8)].iter() {
+            let tip = Point3::new(0., top, 0.);
+            let center = Point3::new(0., bottom, 0.);
+            for i in 0..12 {
+                let (a, b) = (
+                    around(i, 12, radius, bottom),
+                    around(i + 1, 12, radius, bottom),
+                );
+                triangles.push(Rc::new(Triangle::new(
+                    a.clone(),
+                    b.clone(),
+                    tip.clone(),
+                    leaves.clone(),
+                )));
+                triangles.push(Rc::new(Triangle::new(a, b, center.clone(), leaves.clone())));
+            }
+        }
+        Rc::new(LinearBVH::new(&triangles, &BVHOptions::default()))
+    }
+
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+        let mut rng = rand::thread_rng();
+
+        let ground = Plane::new(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            Rc::new(Lambertian::from_color(Color::new(0.3, 0.4, 0.15))),
+        );
+        world.add(Box::new(ground));
+
+        // twenty thousand trees, all sharing one mesh, leaving a clearing in front of the camera
+        let tree = Forest::tree();
+        let mut instances = Vec::new();
+        while instances.len() < 20000 {
+            let (x, z) = (rng.gen_range(-300. ..300.), rng.gen_range(-100. ..500.));
+            if x * x + z * z < 15. * 15. {
+                continue;
+            }
+            let size = rng.gen_range(0.7..1.4);
+            let matrix = Mat4::identity()
+                .scale(&Vec3::new(size, size * rng.gen_range(0.9..1.2), size))
+                .rotate(&Vec3::new(0., 1., 0.), rng.gen_range(0. ..360.))
+                .translate(&Vec3::new(x, 0., z));
+            instances.push(Transform::shared(tree.clone(), matrix));
+        }
+        world.add(Box::new(Tlas::new(instances, &BVHOptions::default())));
+
+        world
+    }
+}
+
 pub struct FinalScene;
 
 impl FinalScene {
@@ -409,8 +1319,15 @@ impl FinalScene {
             radius_boundary2,
             material_glass_ptr.clone(),
         );
-        let medium2 =
-            ConstantMedium::from_color(Box::new(sphere_boundary2), 0.0001, Color::new(1., 1., 1.));
+        // the air, which scatters like the molecules in it do
+        let medium2 = ConstantMedium::new(
+            Box::new(sphere_boundary2),
+            0.0001,
+            Rc::new(Anisotropic::from_color(
+                Color::new(1., 1., 1.),
+                PhaseFunction::Rayleigh,
+            )),
+        );
         world.add(Box::new(medium2));
 
         let radius_earth = 100.;
@@ -445,10 +1362,63 @@ impl FinalScene {
             )));
         }
         world.add(Box::new(Translate::new(
-            Box::new(Rotate::new(Box::new(BVHNode::new(&boxes2, 0, boxes2.len())), 15.)),
+            Box::new(Rotate::new(
+                Box::new(BVHNode::new(&boxes2, 0, boxes2.len())),
+                15.,
+            )),
             Vec3::new(-100., 270., 395.),
         )));
 
         world
     }
 }
+
+pub struct Metals;
+
+impl Metals {
+    pub fn new() -> HittableList {
+        let mut world = HittableList::new();
+
+        let checker = Rc::new(Checker {
+            odd: Rc::new(SolidColor {
+                color: Color::new(0.1, 0.1, 0.1),
+            }),
+            even: Rc::new(SolidColor {
+                color: Color::new(0.9, 0.9, 0.9),
+            }),
+        });
+        world.add(Box::new(Plane::new(
+            Point3::new(0., -1e-4, 0.),
+            Vec3::new(0., 1., 0.),
+            Rc::new(Lambertian::new(checker)),
+        )));
+
+        // gold, copper, aluminium and silver from left to right, polished at the back, rougher
+        // towards the front, and brushed around the vertical at the very front
+        let iors = [
+            ComplexIOR::GOLD,
+            ComplexIOR::COPPER,
+            ComplexIOR::ALUMINUM,
+            ComplexIOR::SILVER,
+        ];
+        for (i, ior) in iors.iter().enumerate() {
+            let x = 2.5 * (i as f64 - 1.5);
+            let materials = vec![
+                Conductor::new(ior.clone(), 0.02),
+                Conductor::new(ior.clone(), 0.15),
+                Conductor::new(ior.clone(), 0.4),
+                Conductor::anisotropic(ior.clone(), 0.4, 0.03),
+            ];
+            for (j, material) in materials.into_iter().enumerate() {
+                let z = 3. * (j as f64 - 1.5);
+                world.add(Box::new(Sphere::new(
+                    Point3::new(x, 1., z),
+                    1.,
+                    Rc::new(material),
+                )));
+            }
+        }
+
+        world
+    }
+}
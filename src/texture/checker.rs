@@ -5,6 +5,11 @@ use super::Texture;
 use crate::color::Color;
 use crate::geometry::Point3;
 
+/// A solid checkerboard, switching between `odd` and `even` every π/10 along each axis.
+///
+/// As it's solid, a surface lying right on one of the boundaries, like a ground plane at y = 0,
+/// flickers between the two and comes out as noise. Put it a hair to one side, say at y = -1e-4,
+/// and it's a clean 2D checkerboard.
 pub struct Checker {
     pub odd: Rc<dyn Texture>,
     pub even: Rc<dyn Texture>,
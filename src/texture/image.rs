@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use std::path::PathBuf;
+use std::path::Path;
 
 use stb_image::image;
 use stb_image::image::LoadResult;
@@ -20,10 +20,19 @@ pub struct ImageTexture {
 impl ImageTexture {
     const BYTES_PER_PIXEL: usize = 3;
 
+    /// The map of the Earth that ships with the source.
     pub fn new() -> ImageTexture {
-        let filename = PathBuf::from("/rust/raytracer/src/texture/earthmap.jpg");
+        ImageTexture::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/texture/earthmap.jpg"
+        ))
+    }
 
-        match image::load(filename) {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ImageTexture {
+        // Loading from memory, since loading from a file with a forced number of channels gets
+        // the size of the data wrong.
+        let bytes = std::fs::read(path).expect("Error reading image");
+        match image::load_from_memory_with_depth(&bytes, ImageTexture::BYTES_PER_PIXEL, false) {
             LoadResult::Error(s) => panic!("Error loading image: {}", s),
             LoadResult::ImageF32(_) => panic!("Sorry, can't hangle f32 image format (yet)"),
             LoadResult::ImageU8(img) => ImageTexture {
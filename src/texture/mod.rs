@@ -11,8 +11,7 @@ mod noise;
 pub use noise::Noise;
 
 mod perlin;
-pub use perlin::NoiseStrategy;
-use perlin::Perlin;
+pub use perlin::{NoiseStrategy, Perlin};
 
 mod solid_color;
 pub use solid_color::SolidColor;
@@ -54,9 +54,9 @@ impl Perlin {
         let v = p.y - f64::floor(p.y);
         let w = p.z - f64::floor(p.z);
 
-        let i = f64::floor(p.x) as isize as usize;
-        let j = f64::floor(p.y) as isize as usize;
-        let k = f64::floor(p.z) as isize as usize;
+        let i = f64::floor(p.x) as isize;
+        let j = f64::floor(p.y) as isize;
+        let k = f64::floor(p.z) as isize;
 
         // flattened 2x2x2 array
         // let mut c = [Vec3::new(0., 0., 0.); 8];
@@ -65,9 +65,9 @@ impl Perlin {
             for dj in 0..2 {
                 for dk in 0..2 {
                     c.push(
-                        &self.random_uvec[self.perm_x[(i + di) & 255]
-                            ^ self.perm_y[(j + dj) & 255]
-                            ^ self.perm_z[(k + dk) & 255]],
+                        &self.random_uvec[self.perm_x[((i + di) & 255) as usize]
+                            ^ self.perm_y[((j + dj) & 255) as usize]
+                            ^ self.perm_z[((k + dk) & 255) as usize]],
                     );
                 }
             }
@@ -130,4 +130,26 @@ mod test {
         let p2 = generate_permutation(n);
         assert_ne!(p1, p2);
     }
+
+    #[test]
+    fn test_noise_negative_coordinates() {
+        let perlin = Perlin::new(NoiseStrategy::PerlinInterpolation);
+        // the lattice repeats every 256 units, on both sides of 0
+        let points = [
+            (
+                Point3::new(-0.5, 0.3, -2.25),
+                Point3::new(255.5, 0.3, 253.75),
+            ),
+            (Point3::new(-1., -1., -1.), Point3::new(255., 255., 255.)),
+            (
+                Point3::new(-300.5, 7.5, -0.125),
+                Point3::new(-44.5, 7.5, 255.875),
+            ),
+        ];
+        for (p, q) in points.iter() {
+            let noise = (perlin.noise)(&perlin, p);
+            assert!(noise.is_finite());
+            assert!(f64::abs(noise - (perlin.noise)(&perlin, q)) < 1e-12);
+        }
+    }
 }